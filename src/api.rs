@@ -1,6 +1,11 @@
-use std::{error::Error, time::Duration};
+use std::{
+    error::Error,
+    net::{Ipv4Addr, SocketAddrV4},
+    sync::Arc,
+    time::Duration,
+};
 
-use crate::{AsSocket, SockOpt};
+use crate::{AsSocket, Encryptor, SockOpt, Transport};
 
 pub struct Socket<T> {
     pub opt: SockOpt,
@@ -70,6 +75,91 @@ impl<T: AsSocket> Socket<T> {
         self
     }
 
+    pub fn set_encryptor<E: Encryptor + 'static>(mut self, encryptor: E) -> Self {
+        self.opt.encryptor = Some(Arc::new(encryptor));
+        self
+    }
+
+    pub fn set_psk(mut self, psk: &[u8]) -> Self {
+        self.opt.psk = Some(psk.to_vec());
+        self
+    }
+
+    pub fn set_cookie_challenge(mut self, cookie_challenge: bool) -> Self {
+        self.opt.cookie_challenge = cookie_challenge;
+        self
+    }
+
+    pub fn set_cookie_ttl(mut self, cookie_ttl: f64) -> Self {
+        self.opt.cookie_ttl = Duration::from_secs_f64(cookie_ttl);
+        self
+    }
+
+    pub fn set_max_peers(mut self, max_peers: usize) -> Self {
+        self.opt.max_peers = max_peers;
+        self
+    }
+
+    pub fn set_connect_timeout(mut self, connect_timeout: f64) -> Self {
+        self.opt.connect_timeout = Duration::from_secs_f64(connect_timeout);
+        self
+    }
+
+    pub fn set_max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.opt.max_frame_size = max_frame_size;
+        self
+    }
+
+    pub fn set_reresolve_on_reconnect(mut self, reresolve_on_reconnect: bool) -> Self {
+        self.opt.reresolve_on_reconnect = reresolve_on_reconnect;
+        self
+    }
+
+    pub fn set_multicast_group(mut self, multicast_group: SocketAddrV4) -> Self {
+        self.opt.multicast_group = Some(multicast_group);
+        self
+    }
+
+    pub fn set_multicast_interface(mut self, multicast_interface: Ipv4Addr) -> Self {
+        self.opt.multicast_interface = multicast_interface;
+        self
+    }
+
+    pub fn set_req_timeout(mut self, req_timeout: f64) -> Self {
+        self.opt.req_timeout = Duration::from_secs_f64(req_timeout);
+        self
+    }
+
+    pub fn set_req_retries(mut self, req_retries: usize) -> Self {
+        self.opt.req_retries = req_retries;
+        self
+    }
+
+    pub fn set_survey_deadline(mut self, survey_deadline: f64) -> Self {
+        self.opt.survey_deadline = Duration::from_secs_f64(survey_deadline);
+        self
+    }
+
+    pub fn set_safe_ordered(mut self, safe_ordered: bool) -> Self {
+        self.opt.safe_ordered = safe_ordered;
+        self
+    }
+
+    pub fn set_safe_nack_ivl(mut self, safe_nack_ivl: f64) -> Self {
+        self.opt.safe_nack_ivl = Duration::from_secs_f64(safe_nack_ivl);
+        self
+    }
+
+    pub fn set_adaptive_rto(mut self, adaptive_rto: bool) -> Self {
+        self.opt.adaptive_rto = adaptive_rto;
+        self
+    }
+
+    pub fn set_congestion_control(mut self, congestion_control: bool) -> Self {
+        self.opt.congestion_control = congestion_control;
+        self
+    }
+
     pub fn bind(self, addr: &str) -> Result<T::Output, Box<dyn Error>> {
         T::bind(addr, self.opt)
     }
@@ -77,4 +167,20 @@ impl<T: AsSocket> Socket<T> {
     pub fn connect(self, addr: &str) -> Result<T::Output, Box<dyn Error>> {
         T::connect(addr, self.opt)
     }
+
+    pub fn bind_with<R: Transport + 'static>(
+        self,
+        transport: R,
+        addr: &str,
+    ) -> Result<T::Output, Box<dyn Error>> {
+        T::bind_with(Box::new(transport), addr, self.opt)
+    }
+
+    pub fn connect_with<R: Transport + 'static>(
+        self,
+        transport: R,
+        addr: &str,
+    ) -> Result<T::Output, Box<dyn Error>> {
+        T::connect_with(Box::new(transport), addr, self.opt)
+    }
 }
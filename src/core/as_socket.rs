@@ -1,6 +1,7 @@
 use std::error::Error;
 
 use super::sock_opt::SockOpt;
+use crate::transport::Transport;
 
 pub trait AsSocket {
     type Output: AsSocket;
@@ -11,6 +12,20 @@ pub trait AsSocket {
     /// Create a bound socket at a random high port and connect it to a remote address
     fn connect(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>>;
 
+    /// Create a socket over a user supplied transport, bound at a specified address
+    fn bind_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>>;
+
+    /// Create a socket over a user supplied transport, connected to a remote address
+    fn connect_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>>;
+
     // Send a multipart message
     fn send(&mut self, data: &[&[u8]]) -> Result<(), Box<dyn Error>>;
 
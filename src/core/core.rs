@@ -1,211 +1,772 @@
 use std::{
+    borrow::Cow,
+    cell::Cell,
     collections::HashMap,
     error::Error,
     hash::Hasher,
-    net::{SocketAddr, UdpSocket},
-    str::FromStr,
-    time::Instant,
+    net::SocketAddr,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use super::sock_opt::SockOpt;
 use crate::{
-    frame::{self, ControlFrame, Frame},
+    frame::{self, ControlFrame, Frame, MessageFrame},
     hash::Fnv1a64,
-    random::XORShift,
-    ts::get_ts_u64,
+    hmac::{self, HmacSha256},
+    random::{self, XORShift},
+    transport::{self, Addr, Multicast, Transport},
 };
 
 #[derive(PartialEq, Eq)]
 pub struct ConnectStatus {
-    addr: SocketAddr,
+    /// The address as given to connect, kept for re-resolution.
+    target: String,
+    candidates: Vec<Addr>,
+    candidate: usize,
     session: u64,
     last_reconnect: Instant,
+    /// Highest MAC counter taken from the peer on the other end, see `fresh`.
+    mac_counter: u64,
+}
+
+/// Number of MAC counters below the highest one a `ReplayWindow` still tells apart.
+const REPLAY_WINDOW: u64 = 128;
+
+/// MAC counters taken from one multicast publisher. Group frames may arrive out of order, so a
+/// counter below the highest one taken is still taken once, as long as it is in the window.
+#[derive(Default)]
+struct ReplayWindow {
+    highest: u64,
+    /// Bit `n` is set once `highest - n` was taken.
+    seen: u128,
+}
+
+impl ReplayWindow {
+    /// Take a counter, false if it was taken before or is too far behind to tell.
+    fn take(&mut self, counter: u64) -> bool {
+        if counter > self.highest {
+            let shift = counter - self.highest;
+            self.seen = if shift >= REPLAY_WINDOW {
+                0
+            } else {
+                self.seen << shift
+            };
+            self.seen |= 1;
+            self.highest = counter;
+
+            return true;
+        }
+
+        let age = self.highest - counter;
+        if age >= REPLAY_WINDOW || self.seen & (1 << age) != 0 {
+            return false;
+        }
+
+        self.seen |= 1 << age;
+        true
+    }
 }
 
 #[derive(PartialEq, Eq)]
 pub enum SockMode {
     Bind,
-    Connect(ConnectStatus),
+    Connect,
 }
 
 pub struct Peer {
-    pub addr: SocketAddr,
+    pub addr: Addr,
     pub last_seen: Instant,
     pub last_sent: Instant,
+
+    /// Number of the last heartbeat timed and when it went out, and the round trip measured from
+    /// its echo until taken with `take_rtt_sample`.
+    pub heartbeat_number: u64,
+    pub heartbeat_sent: Option<(u64, Instant)>,
+    pub rtt_sample: Option<Duration>,
+
+    /// Highest MAC counter taken from the peer, see `fresh`.
+    pub mac_counter: u64,
 }
 
 impl Peer {
-    pub fn new(addr: SocketAddr) -> Self {
+    pub fn new(addr: Addr) -> Self {
         Self {
             addr,
             last_seen: Instant::now(),
             last_sent: Instant::now(),
+
+            heartbeat_number: 0,
+            heartbeat_sent: None,
+            rtt_sample: None,
+
+            mac_counter: 0,
+        }
+    }
+
+    /// Number the next heartbeat to the peer. A timed one is numbered afresh and replaces any
+    /// still waiting on its echo, so a round trip is only ever measured against the heartbeat
+    /// it answers. Untimed heartbeats go out as 0 and aren't echoed.
+    pub fn heartbeat(&mut self, timed: bool) -> u64 {
+        if !timed {
+            return 0;
         }
+
+        self.heartbeat_number += 1;
+        self.heartbeat_sent = Some((self.heartbeat_number, Instant::now()));
+
+        self.heartbeat_number
     }
 }
 
 pub struct Core {
-    sock: UdpSocket,
+    transport: Box<dyn Transport>,
+    multicast: Option<Multicast>,
+    recv_buf: Vec<u8>,
     opt: SockOpt,
     rng: XORShift,
 
+    epoch: Instant,
+    cookie_secret: [u8; 32],
+    /// Counter sent with every authenticated control frame, so peers can tell replays apart.
+    /// Starts from the wall clock, so it keeps increasing across restarts.
+    mac_counter: Cell<u64>,
+    /// MAC counters taken from each publisher in the multicast group.
+    multicast_counters: HashMap<SocketAddr, ReplayWindow>,
+
     pub mode: SockMode,
+    /// Outbound connections, each holding its own session. A bound core can add some too.
+    connections: Vec<ConnectStatus>,
     pub peer_update: bool,
     pub peers: HashMap<u64, Peer>,
+
+    /// Sessions handed out to peers that have yet to prove the pre-shared key.
+    pub pending: HashMap<u64, Peer>,
+
+    /// Hold at most one session, see `set_exclusive`.
+    exclusive: bool,
+
+    /// Scheme of the address the transport was opened from, unknown for user supplied ones.
+    scheme: Option<transport::Scheme>,
 }
 
 impl Core {
-    pub fn bind(addr: &str, opt: SockOpt) -> Result<Core, Box<dyn Error>> {
-        let socket = UdpSocket::bind(addr)?;
-        socket.set_nonblocking(true)?;
+    fn new(
+        transport: Box<dyn Transport>,
+        opt: SockOpt,
+        mode: SockMode,
+    ) -> Result<Core, Box<dyn Error>> {
+        let overhead = match &opt.encryptor {
+            Some(encryptor) => encryptor.overhead(),
+            None => 0,
+        };
+
+        if opt.max_frame_size <= frame::SEQUENCED_DATA_HEADER_SIZE
+            || opt.max_frame_size - frame::DATA_HEADER_SIZE > frame::MAX_CHUNK_SIZE
+        {
+            return Err("max_frame_size out of range for the frame format".into());
+        }
+
+        if opt.max_frame_size + overhead > transport.max_frame_size() {
+            return Err("max_frame_size exceeds what the transport can carry".into());
+        }
+
+        let recv_buf = vec![0u8; transport.max_frame_size()];
 
         Ok(Core {
-            sock: socket,
+            transport,
+            multicast: None,
+            recv_buf,
             opt,
-            rng: XORShift::new(get_ts_u64()),
+            // Seeded per core, so sessions from servers started together differ for a client
+            // connected to several of them.
+            rng: XORShift::new(random::entropy()),
+
+            epoch: Instant::now(),
+            cookie_secret: Core::cookie_secret(),
+            mac_counter: Cell::new(
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|elapsed| elapsed.as_micros() as u64)
+                    .unwrap_or(0),
+            ),
+            multicast_counters: HashMap::new(),
 
-            mode: SockMode::Bind,
+            mode,
+            connections: Vec::new(),
 
             peer_update: true,
             peers: HashMap::new(),
+            pending: HashMap::new(),
+
+            exclusive: false,
+
+            scheme: None,
         })
     }
 
+    pub fn bind(addr: &str, opt: SockOpt) -> Result<Core, Box<dyn Error>> {
+        let transport = transport::bind(addr, &opt)?;
+
+        let mut core = Core::new(transport, opt, SockMode::Bind)?;
+        core.scheme = Some(transport::scheme(addr)?);
+
+        Ok(core)
+    }
+
     pub fn connect(addr: &str, opt: SockOpt) -> Result<Core, Box<dyn Error>> {
-        let mut sock = UdpSocket::bind("0.0.0.0:0")?;
-        sock.set_nonblocking(true)?;
+        let (transport, target) = transport::connect(addr, &opt)?;
 
-        let peer_addr = SocketAddr::from_str(addr)?;
-        Core::connect_socket(&mut sock, &peer_addr)?;
+        let mut core = Core::connect_from(transport, &target, opt)?;
+        core.scheme = Some(transport::scheme(addr)?);
 
-        let peers = HashMap::new();
+        Ok(core)
+    }
 
-        Ok(Core {
-            sock,
-            opt,
-            rng: XORShift::new(get_ts_u64()),
+    /// Bind a user supplied transport to `addr`.
+    pub fn bind_with(
+        mut transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Core, Box<dyn Error>> {
+        transport.bind(addr)?;
 
-            mode: SockMode::Connect(ConnectStatus {
-                addr: peer_addr,
-                session: 0,
-                last_reconnect: Instant::now(),
-            }),
+        Core::new(transport, opt, SockMode::Bind)
+    }
 
-            peer_update: true,
-            peers,
-        })
+    /// Connect a user supplied transport to the peer at `addr`.
+    pub fn connect_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Core, Box<dyn Error>> {
+        Core::connect_from(transport, addr, opt)
     }
 
-    fn connect_socket(sock: &mut UdpSocket, peer_addr: &SocketAddr) -> Result<(), Box<dyn Error>> {
-        sock.connect(peer_addr)?;
-        sock.send(&ControlFrame::Connect.encode())?;
+    /// Connect from a transport that others can reach too, so further connections can be made
+    /// with `add_connection`.
+    pub fn connect_multi(addr: &str, opt: SockOpt) -> Result<Core, Box<dyn Error>> {
+        let (transport, target) = transport::connect_multi(addr, &opt)?;
+
+        let mut core = Core::connect_from(transport, &target, opt)?;
+        core.scheme = Some(transport::scheme(addr)?);
+
+        Ok(core)
+    }
+
+    /// Strip the scheme from an address for `add_connection`, erroring if it names another
+    /// transport than the one open.
+    pub fn target(&self, addr: &str) -> Result<String, Box<dyn Error>> {
+        transport::target(addr, self.scheme)
+    }
+
+    fn connect_from(
+        transport: Box<dyn Transport>,
+        target: &str,
+        opt: SockOpt,
+    ) -> Result<Core, Box<dyn Error>> {
+        let mut core = Core::new(transport, opt, SockMode::Connect)?;
+        core.add_connection(target)?;
+
+        Ok(core)
+    }
+
+    /// Open another outbound connection to `target`, given without a scheme, alongside any held
+    /// already. Each connection keeps its own session and is reconnected on its own.
+    pub fn add_connection(&mut self, target: &str) -> Result<(), Box<dyn Error>> {
+        let candidates = self.transport.resolve(target)?;
+        if candidates.is_empty() {
+            return Err(format!("{} did not resolve to any address", target).into());
+        }
+
+        let mut status = ConnectStatus {
+            target: target.to_string(),
+            candidates,
+            candidate: 0,
+            session: 0,
+            last_reconnect: Instant::now(),
+            mac_counter: 0,
+        };
+
+        // Start with the first address that takes the Connect. Peers that aren't reachable yet
+        // are retried from reconnect().
+        for (i, peer_addr) in status.candidates.iter().enumerate() {
+            if self.connect_socket(peer_addr).is_ok() {
+                status.candidate = i;
+                break;
+            }
+        }
+
+        self.connections.push(status);
 
         Ok(())
     }
 
-    fn reconnect(&mut self) -> Result<(), Box<dyn Error>> {
-        if let SockMode::Connect(ConnectStatus {
-            addr,
-            session,
-            last_reconnect,
-        }) = &mut self.mode
-        {
-            if let None = self.peers.get(&session) {
-                let now = Instant::now();
+    /// The connection a handshake frame from `peer_addr` belongs to. A lone connection of a
+    /// connecting core takes frames from any address, as before there could be several.
+    fn connection_for(&mut self, peer_addr: &Addr) -> Option<&mut ConnectStatus> {
+        let i = self
+            .connections
+            .iter()
+            .position(|status| status.candidates.get(status.candidate) == Some(peer_addr));
 
-                if now.duration_since(*last_reconnect) > self.opt.reconnect_wait {
-                    *last_reconnect = now;
-                    Core::connect_socket(&mut self.sock, &addr)?;
-                }
+        match i {
+            Some(i) => self.connections.get_mut(i),
+            None if self.mode == SockMode::Connect && self.connections.len() == 1 => {
+                self.connections.first_mut()
             }
+            None => None,
         }
+    }
+
+    fn connect_socket(&mut self, peer_addr: &Addr) -> Result<(), Box<dyn Error>> {
+        self.transport.connect(peer_addr)?;
+        self.transport.send_to(
+            &self.seal(&self.encode_control(&ControlFrame::Connect))?,
+            peer_addr,
+        )?;
 
-        return Ok(());
+        Ok(())
     }
 
-    fn recv_buffer(&mut self) -> Result<(Vec<u8>, SocketAddr), Box<dyn Error>> {
-        let mut buffer = vec![0u8; frame::MAX_FRAME_SIZE];
+    /// Retry the handshake once `reconnect_wait` has passed without a session, moving on to the
+    /// next resolved address each time. With `reresolve_on_reconnect` the target is resolved
+    /// afresh, so a peer that moved is found again. A lookup still running leaves the addresses
+    /// as they are for this attempt.
+    fn reconnect(&mut self) -> Result<(), Box<dyn Error>> {
+        let now = Instant::now();
 
-        let recv_addr = match &mut self.mode {
-            SockMode::Connect(ConnectStatus { addr, .. }) => {
-                let bytes_recv = self.sock.recv(&mut buffer)?;
-                buffer.truncate(bytes_recv);
+        for i in 0..self.connections.len() {
+            let status = &mut self.connections[i];
 
-                *addr
+            if self.peers.contains_key(&status.session)
+                || now.duration_since(status.last_reconnect) <= self.opt.reconnect_wait
+            {
+                continue;
             }
-            SockMode::Bind => {
-                let (bytes_recv, recv_addr) = self.sock.recv_from(&mut buffer)?;
-                buffer.truncate(bytes_recv);
-                recv_addr
+
+            status.last_reconnect = now;
+
+            if self.opt.reresolve_on_reconnect
+                && let Ok(candidates) = self.transport.reresolve(&status.target)
+                && !candidates.is_empty()
+            {
+                status.candidates = candidates;
             }
+
+            status.candidate = (status.candidate + 1) % status.candidates.len();
+            let addr = status.candidates[status.candidate].clone();
+            let _ = self.connect_socket(&addr);
+        }
+
+        Ok(())
+    }
+
+    /// Encode a control frame. Handshake and liveness frames carry the next MAC counter and an
+    /// HMAC of the frame and counter when a pre-shared key is set.
+    fn encode_control(&self, control_frame: &ControlFrame) -> Vec<u8> {
+        let buffer = control_frame.encode();
+
+        if frame::requires_mac(buffer[1]) {
+            return self.sign(buffer);
+        }
+
+        buffer
+    }
+
+    /// Append the next MAC counter and an HMAC over the frame and counter, with a pre-shared key.
+    fn sign(&self, mut buffer: Vec<u8>) -> Vec<u8> {
+        let Some(psk) = &self.opt.psk else {
+            return buffer;
+        };
+
+        let counter = self.mac_counter.get() + 1;
+        self.mac_counter.set(counter);
+
+        buffer.extend_from_slice(&counter.to_be_bytes());
+        let tag = HmacSha256::mac(psk, &buffer);
+        buffer.extend_from_slice(&tag);
+
+        buffer
+    }
+
+    /// Check the HMAC trailing a handshake or liveness frame against the pre-shared key and strip
+    /// it along with the counter, which is returned for `fresh`. Frames that fail verification
+    /// yield `None` and are dropped. Frames without a MAC come back with a counter of 0.
+    fn authenticate(&self, buffer: Vec<u8>) -> Option<(Vec<u8>, u64)> {
+        if self.opt.psk.is_none() || buffer.len() < 2 || !frame::requires_mac(buffer[1]) {
+            return Some((buffer, 0));
+        }
+
+        self.verify(buffer)
+    }
+
+    /// Check and strip the counter and HMAC `sign` appended.
+    fn verify(&self, mut buffer: Vec<u8>) -> Option<(Vec<u8>, u64)> {
+        let Some(psk) = &self.opt.psk else {
+            return Some((buffer, 0));
         };
 
-        Ok((buffer, recv_addr))
+        if buffer.len() < frame::CONTROL_HEADER_SIZE + frame::MAC_COUNTER_SIZE + frame::MAC_SIZE {
+            return None;
+        }
+
+        let (data, tag) = buffer.split_at(buffer.len() - frame::MAC_SIZE);
+        if !HmacSha256::verify(psk, data, tag) {
+            return None;
+        }
+
+        let counter_at = data.len() - frame::MAC_COUNTER_SIZE;
+        let counter = u64::from_be_bytes(data[counter_at..].try_into().ok()?);
+
+        buffer.truncate(counter_at);
+        Some((buffer, counter))
+    }
+
+    /// Whether an authenticated control frame is newer than anything taken from its sender,
+    /// recording its counter if so. Replays of captured frames fail this. Counters are tracked per
+    /// session, or per outbound connection for frames that change its session. Frames that open
+    /// a session have nothing to be checked against, a replay of those only gets a session
+    /// handed out that can't be proven.
+    fn fresh(&mut self, control_frame: &ControlFrame, peer_addr: &Addr, counter: u64) -> bool {
+        if self.opt.psk.is_none() {
+            return true;
+        }
+
+        let session_id = match control_frame {
+            ControlFrame::Heartbeat((session_id, _))
+            | ControlFrame::HeartbeatEcho((session_id, _))
+            | ControlFrame::Disconnected(session_id)
+            | ControlFrame::Connected(session_id) => *session_id,
+            _ => return true,
+        };
+
+        let last = match self.peers.get_mut(&session_id) {
+            Some(peer) => Some(&mut peer.mac_counter),
+            None => match self.pending.get_mut(&session_id) {
+                Some(peer) => Some(&mut peer.mac_counter),
+                None => None,
+            },
+        };
+
+        let last = match last {
+            Some(last) => last,
+            None => match control_frame {
+                ControlFrame::Disconnected(_) | ControlFrame::Connected(_) => {
+                    match self.connection_for(peer_addr) {
+                        Some(status) => &mut status.mac_counter,
+                        None => return true,
+                    }
+                }
+                _ => return true,
+            },
+        };
+
+        if counter <= *last {
+            return false;
+        }
+
+        *last = counter;
+        true
+    }
+
+    fn cookie_secret() -> [u8; 32] {
+        let mut secret = [0u8; 32];
+        for chunk in secret.chunks_exact_mut(8) {
+            chunk.copy_from_slice(&random::entropy().to_be_bytes());
+        }
+
+        secret
+    }
+
+    fn cookie_for(&self, issued: u64, peer_addr: &Addr) -> Vec<u8> {
+        let mut hmac = HmacSha256::new(&self.cookie_secret);
+        hmac.update(&issued.to_be_bytes());
+        hmac.update(peer_addr.to_string().as_bytes());
+
+        let mut cookie = issued.to_be_bytes().to_vec();
+        cookie.extend_from_slice(&hmac.finalize()[..frame::COOKIE_SIZE - 8]);
+        cookie
+    }
+
+    /// Issue a stateless cookie binding the peer address to the current time.
+    fn issue_cookie(&self, peer_addr: &Addr) -> Vec<u8> {
+        self.cookie_for(self.epoch.elapsed().as_millis() as u64, peer_addr)
+    }
+
+    /// Check that a cookie echoed by a peer was issued by us, to its address, within `cookie_ttl`.
+    fn check_cookie(&self, cookie: &[u8], peer_addr: &Addr) -> bool {
+        if cookie.len() != frame::COOKIE_SIZE {
+            return false;
+        }
+
+        let Ok(issued_b) = cookie[0..8].try_into() else {
+            return false;
+        };
+        let issued = u64::from_be_bytes(issued_b);
+        let now = self.epoch.elapsed().as_millis() as u64;
+
+        if issued > now || now - issued > self.opt.cookie_ttl.as_millis() as u64 {
+            return false;
+        }
+
+        hmac::ct_eq(&self.cookie_for(issued, peer_addr), cookie)
+    }
+
+    /// Hold at most one session. While the peer is live, `Connect`s from anywhere else are
+    /// dropped. A `Connect` from the peer's own address, or from anyone once the peer has been
+    /// silent for `peer_keepalive`, replaces it.
+    pub fn set_exclusive(&mut self, exclusive: bool) {
+        self.exclusive = exclusive;
+    }
+
+    /// In exclusive mode, make room for `peer_addr` by evicting a stale or restarted peer.
+    /// Returns false when a live peer holds the slot.
+    /// The peer heartbeats at its own interval, so it's only known to be gone once silent for as
+    /// long as `maint` would prune it.
+    fn evict_for(&mut self, peer_addr: &Addr) -> Result<bool, Box<dyn Error>> {
+        let stale_after = self.opt.peer_keepalive;
+
+        let held = self
+            .peers
+            .iter()
+            .chain(self.pending.iter())
+            .map(|(session_id, peer)| (*session_id, peer.addr.clone(), peer.last_seen))
+            .collect::<Vec<(u64, Addr, Instant)>>();
+
+        if held
+            .iter()
+            .any(|(_, addr, last_seen)| *addr != *peer_addr && last_seen.elapsed() <= stale_after)
+        {
+            return Ok(false);
+        }
+
+        for (session_id, addr, _) in held {
+            self.peers.remove(&session_id);
+            self.pending.remove(&session_id);
+            self.peer_update = true;
+
+            if addr != *peer_addr {
+                let _ = self.send_direct(
+                    &self.encode_control(&ControlFrame::Disconnected(session_id)),
+                    &addr,
+                );
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Allocate a session for a peer and send it back in a `Connected` frame.
+    fn admit(&mut self, peer_addr: &Addr) -> Result<(), Box<dyn Error>> {
+        if self.exclusive && !self.evict_for(peer_addr)? {
+            return Ok(());
+        }
+
+        if self.peers.len() + self.pending.len() >= self.opt.max_peers {
+            return Ok(());
+        }
+
+        let mut hasher = Fnv1a64::new();
+        hasher.write(&self.rng.sample().to_be_bytes());
+        hasher.write(peer_addr.to_string().as_bytes());
+        let session_id = hasher.finish();
+
+        let peer = Peer::new(peer_addr.clone());
+
+        // With a pre-shared key the session is only handed out, the peer is admitted once it
+        // answers with an authenticated heartbeat for it.
+        if self.opt.psk.is_some() {
+            self.pending.insert(session_id, peer);
+        } else {
+            self.peers.insert(session_id, peer);
+            self.peer_update = true;
+        }
+
+        self.send_direct(
+            &self.encode_control(&ControlFrame::Connected(session_id)),
+            peer_addr,
+        )?;
+
+        Ok(())
+    }
+
+    /// Encrypt the payload of an encoded frame, leaving its header in the clear.
+    fn seal<'a>(&self, data: &'a [u8]) -> Result<Cow<'a, [u8]>, Box<dyn Error>> {
+        let Some(encryptor) = &self.opt.encryptor else {
+            return Ok(Cow::Borrowed(data));
+        };
+
+        let Some((header, payload, session_id)) = frame::split_header(data) else {
+            return Err("Frame too short to encrypt".into());
+        };
+
+        let mut sealed = header.to_vec();
+        sealed.extend_from_slice(&encryptor.encrypt(session_id, header, payload)?);
+
+        Ok(Cow::Owned(sealed))
+    }
+
+    /// Decrypt the payload of a received datagram. Datagrams that fail to authenticate yield
+    /// `None` and are dropped.
+    fn open(&self, buffer: Vec<u8>) -> Option<Vec<u8>> {
+        let Some(encryptor) = &self.opt.encryptor else {
+            return Some(buffer);
+        };
+
+        let (header, payload, session_id) = frame::split_header(&buffer)?;
+        let payload = encryptor.decrypt(session_id, header, payload).ok()?;
+
+        let mut opened = header.to_vec();
+        opened.extend_from_slice(&payload);
+
+        Some(opened)
+    }
+
+    fn recv_buffer(&mut self) -> Result<(Vec<u8>, Addr), Box<dyn Error>> {
+        let (bytes_recv, recv_addr) = self.transport.recv_from(&mut self.recv_buf)?;
+
+        Ok((self.recv_buf[..bytes_recv].to_vec(), recv_addr))
+    }
+
+    fn recv_multicast_buffer(&mut self) -> Option<(Vec<u8>, SocketAddr)> {
+        let (bytes_recv, addr) = self
+            .multicast
+            .as_ref()?
+            .recv_from(&mut self.recv_buf)
+            .ok()?;
+
+        Some((self.recv_buf[..bytes_recv].to_vec(), addr))
     }
 
     fn control(
         &mut self,
         control_frame: &ControlFrame,
-        peer_addr: &SocketAddr,
+        peer_addr: &Addr,
     ) -> Result<bool, Box<dyn Error>> {
         match control_frame {
             ControlFrame::Connect => {
-                let mut hasher = Fnv1a64::new();
-                hasher.write(&self.rng.sample().to_be_bytes());
-                hasher.write(peer_addr.to_string().as_bytes());
-                let session_id = hasher.finish();
-
-                self.peers.insert(
-                    session_id,
-                    Peer {
-                        addr: peer_addr.clone(),
-                        last_sent: Instant::now(),
-                        last_seen: Instant::now(),
-                    },
-                );
-
-                self.send_direct(&ControlFrame::Connected(session_id).encode(), peer_addr)?;
-                self.peer_update = true;
+                if self.opt.cookie_challenge {
+                    let cookie = self.issue_cookie(peer_addr);
+                    self.send_direct(
+                        &self.encode_control(&ControlFrame::Cookie(cookie)),
+                        peer_addr,
+                    )?;
+                } else {
+                    self.admit(peer_addr)?;
+                }
             }
+            // Only echo to the address being connected to, a Cookie from anywhere else would
+            // have us reflect CookieEchoes at whoever it names.
+            ControlFrame::Cookie(cookie) => {
+                if self
+                    .connections
+                    .iter()
+                    .any(|status| status.candidates.get(status.candidate) == Some(peer_addr))
+                {
+                    self.send_direct(
+                        &self.encode_control(&ControlFrame::CookieEcho(cookie.clone())),
+                        peer_addr,
+                    )?;
+                }
+            }
+            ControlFrame::CookieEcho(cookie) => {
+                if self.opt.cookie_challenge && self.check_cookie(cookie, peer_addr) {
+                    self.admit(peer_addr)?;
+                }
+            }
+            // Only a connection still waiting on its handshake takes a session. Anything else
+            // would admit a peer past the cookie challenge and `max_peers`.
             ControlFrame::Connected(session_id) => {
-                if let SockMode::Connect(ConnectStatus { session, .. }) = &mut self.mode {
-                    *session = *session_id;
-                    self.peers.drain();
+                let Some(previous) = self.connection_for(peer_addr).map(|status| status.session)
+                else {
+                    return Ok(false);
+                };
+
+                if self.peers.contains_key(&previous) {
+                    return Ok(false);
                 }
 
-                self.peers.insert(
-                    *session_id,
-                    Peer {
-                        addr: peer_addr.clone(),
-                        last_seen: Instant::now(),
-                        last_sent: Instant::now(),
-                    },
-                );
+                if let Some(status) = self.connection_for(peer_addr) {
+                    status.session = *session_id;
+                }
+
+                let mut peer = Peer::new(peer_addr.clone());
+                let number = peer.heartbeat(self.opt.adaptive_rto);
+                self.peers.insert(*session_id, peer);
 
-                self.send_direct(&ControlFrame::Heartbeat(*session_id).encode(), peer_addr)?;
+                self.send_direct(
+                    &self.encode_control(&ControlFrame::Heartbeat((*session_id, number))),
+                    peer_addr,
+                )?;
                 self.peer_update = true;
             }
             ControlFrame::Disconnected(session_id) => {
-                if let SockMode::Connect(ConnectStatus { session, .. }) = &mut self.mode {
-                    *session = 0;
-                    self.peers.drain();
+                let lone = self.mode == SockMode::Connect && self.connections.len() == 1;
+                if let Some(status) = self
+                    .connections
+                    .iter_mut()
+                    .find(|status| lone || status.session == *session_id)
+                {
+                    self.peers.remove(&status.session);
+                    status.session = 0;
                 }
 
                 self.peers.remove(&session_id);
 
-                self.send_direct(&ControlFrame::Connect.encode(), peer_addr)?;
+                self.send_direct(&self.encode_control(&ControlFrame::Connect), peer_addr)?;
                 self.peer_update = true;
             }
-            ControlFrame::Heartbeat(session_id) => {
-                if let Some(peer) = self.peers.get_mut(&session_id) {
+            // Without a pre-shared key a heartbeat never moves a session to another address,
+            // echoes go where the session lives. With one, a heartbeat that got here is
+            // authenticated and fresh, and is the only thing that moves a session.
+            ControlFrame::Heartbeat((session_id, number)) => {
+                let moves = self.opt.psk.is_some();
+
+                let addr = if let Some(peer) = self.peers.get_mut(&session_id) {
                     peer.last_seen = Instant::now();
-                    if peer.addr != *peer_addr {
-                        peer.addr = *peer_addr;
+                    if moves {
+                        peer.addr = peer_addr.clone();
                     }
+                    Some(peer.addr.clone())
+                } else if let Some(mut peer) = self.pending.remove(session_id) {
+                    peer.last_seen = Instant::now();
+                    if moves {
+                        peer.addr = peer_addr.clone();
+                    }
+                    let addr = peer.addr.clone();
+                    self.peers.insert(*session_id, peer);
+                    self.peer_update = true;
+                    Some(addr)
                 } else {
-                    self.send_direct(&ControlFrame::Disconnected(*session_id).encode(), peer_addr)?;
+                    None
+                };
+
+                if let Some(addr) = addr {
+                    if *number != 0 {
+                        self.send_direct(
+                            &self.encode_control(&ControlFrame::HeartbeatEcho((
+                                *session_id,
+                                *number,
+                            ))),
+                            &addr,
+                        )?;
+                    }
+                } else {
+                    self.send_direct(
+                        &self.encode_control(&ControlFrame::Disconnected(*session_id)),
+                        peer_addr,
+                    )?;
                 };
             }
+            ControlFrame::HeartbeatEcho((session_id, number)) => {
+                if let Some(peer) = self.peers.get_mut(session_id)
+                    && let Some((sent_number, sent)) = peer.heartbeat_sent
+                    && sent_number == *number
+                {
+                    peer.heartbeat_sent = None;
+                    peer.rtt_sample = Some(sent.elapsed());
+                }
+            }
             // Any other type of control frame is handled by the messaging layer. Forward them
             // here.
             _ => return Ok(true),
@@ -215,38 +776,83 @@ impl Core {
         Ok(false)
     }
 
+    /// Note data from a session, heartbeating it if we've been quiet. Data for an unknown session
+    /// is answered with `Disconnected` and yields false.
+    fn data_from(&mut self, session_id: u64, addr: &Addr) -> Result<bool, Box<dyn Error>> {
+        let Some(peer) = self.peers.get_mut(&session_id) else {
+            let _ = self.send_direct(
+                &self.encode_control(&ControlFrame::Disconnected(session_id)),
+                addr,
+            );
+            return Ok(false);
+        };
+
+        // Data frames carry no MAC, so with a pre-shared key only heartbeats move a session.
+        peer.last_seen = Instant::now();
+        if peer.addr != *addr && self.opt.psk.is_none() {
+            peer.addr = addr.clone();
+        }
+
+        if Instant::now().duration_since(peer.last_sent) > self.opt.peer_heartbeat_ivl {
+            peer.last_sent = Instant::now();
+            let number = peer.heartbeat(self.opt.adaptive_rto);
+            let peer_addr = peer.addr.clone();
+            if let Err(_) = self.send_direct(
+                &self.encode_control(&ControlFrame::Heartbeat((session_id, number))),
+                &peer_addr,
+            ) {
+                self.reconnect()?;
+            }
+        }
+
+        Ok(true)
+    }
+
     pub fn recv(&mut self) -> Result<Frame, Box<dyn Error>> {
         loop {
-            let (buffer, addr) = self.recv_buffer()?;
+            if let Ok((message, addr)) = self.transport.recv_message() {
+                if self.data_from(message.session_id, &addr)? {
+                    return Ok(Frame::MessageFrame(message));
+                }
+
+                continue;
+            }
+
+            let (buffer, addr) = match self.recv_buffer() {
+                Ok(recv) => recv,
+                Err(e) => match self.recv_multicast_buffer() {
+                    Some((buffer, addr)) => {
+                        if let Some(frame) = self.accept_multicast(buffer, addr) {
+                            return Ok(frame);
+                        }
+
+                        continue;
+                    }
+                    None => return Err(e),
+                },
+            };
+
+            let Some(buffer) = self.open(buffer) else {
+                continue;
+            };
+
+            let Some((buffer, counter)) = self.authenticate(buffer) else {
+                continue;
+            };
 
             let Ok(Some(frame)) = Frame::parse(&buffer) else {
                 continue;
             };
 
+            if let Frame::ControlFrame(control_frame) = &frame
+                && !self.fresh(control_frame, &addr, counter)
+            {
+                continue;
+            }
+
             match &frame {
                 Frame::DataFrame(data_frame) => {
-                    if let Some(peer) = self.peers.get_mut(&data_frame.session_id) {
-                        peer.last_seen = Instant::now();
-                        if peer.addr != addr {
-                            peer.addr = addr;
-                        }
-
-                        if Instant::now().duration_since(peer.last_sent)
-                            > self.opt.peer_heartbeat_ivl
-                        {
-                            peer.last_sent = Instant::now();
-                            if let Err(_) = self.send_direct(
-                                &ControlFrame::Heartbeat(data_frame.session_id).encode(),
-                                &addr,
-                            ) {
-                                self.reconnect()?;
-                            }
-                        }
-                    } else {
-                        let _ = self.send_direct(
-                            &ControlFrame::Disconnected(data_frame.session_id).encode(),
-                            &addr,
-                        );
+                    if !self.data_from(data_frame.session_id, &addr)? {
                         continue;
                     }
                 }
@@ -261,27 +867,82 @@ impl Core {
                         continue;
                     }
                 },
+                Frame::MessageFrame(_) => continue,
             }
 
             return Ok(frame);
         }
     }
 
-    pub fn send_direct(
-        &mut self,
-        data: &[u8],
-        peer_addr: &SocketAddr,
-    ) -> Result<(), Box<dyn Error>> {
-        match self.mode {
-            SockMode::Connect(ConnectStatus { addr, .. }) => {
-                debug_assert_eq!(*peer_addr, addr);
-                self.sock.send(data)?;
-            }
-            SockMode::Bind => {
-                self.sock.send_to(data, peer_addr)?;
-            }
+    /// Data frames from the multicast group are only taken from the host of a peer we hold a
+    /// unicast session with, which is what keeps track of the publisher's liveness. The group
+    /// has no session to check against, so the publisher's own socket is known by its address
+    /// only. With a pre-shared key the frame must carry a valid MAC with a counter its publisher
+    /// hasn't used before, see `ReplayWindow`.
+    fn accept_multicast(&mut self, buffer: Vec<u8>, addr: SocketAddr) -> Option<Frame> {
+        if !self
+            .peers
+            .values()
+            .any(|peer| matches!(peer.addr, Addr::Inet(peer_addr) if peer_addr.ip() == addr.ip()))
+        {
+            return None;
+        }
+
+        let buffer = self.open(buffer)?;
+        let (buffer, counter) = self.verify(buffer)?;
+
+        if self.opt.psk.is_some()
+            && !self
+                .multicast_counters
+                .entry(addr)
+                .or_default()
+                .take(counter)
+        {
+            return None;
         }
 
+        match Frame::parse(&buffer) {
+            Ok(Some(Frame::DataFrame(data_frame))) => Some(Frame::DataFrame(data_frame)),
+            _ => None,
+        }
+    }
+
+    /// Publish data frames to the multicast group in `multicast_group`.
+    pub fn publish_multicast(&mut self) -> Result<(), Box<dyn Error>> {
+        let Some(group) = self.opt.multicast_group else {
+            return Err("No multicast group set".into());
+        };
+
+        self.multicast = Some(Multicast::publisher(group, self.opt.multicast_interface)?);
+
+        Ok(())
+    }
+
+    /// Join the multicast group in `multicast_group` to receive data frames from it.
+    pub fn join_multicast(&mut self) -> Result<(), Box<dyn Error>> {
+        let Some(group) = self.opt.multicast_group else {
+            return Err("No multicast group set".into());
+        };
+
+        self.multicast = Some(Multicast::subscriber(group, self.opt.multicast_interface)?);
+
+        Ok(())
+    }
+
+    /// Send a data frame to every subscriber of the multicast group at once.
+    pub fn send_multicast(&mut self, data: &[u8]) -> Result<(), Box<dyn Error>> {
+        let Some(multicast) = &self.multicast else {
+            return Err("Not publishing to a multicast group".into());
+        };
+
+        multicast.send(&self.seal(&self.sign(data.to_vec()))?)?;
+
+        Ok(())
+    }
+
+    pub fn send_direct(&mut self, data: &[u8], peer_addr: &Addr) -> Result<(), Box<dyn Error>> {
+        self.transport.send_to(&self.seal(data)?, peer_addr)?;
+
         Ok(())
     }
 
@@ -293,22 +954,46 @@ impl Core {
         };
 
         peer.last_sent = now;
-        let addr = peer.addr;
+        let addr = peer.addr.clone();
 
         if now.duration_since(peer.last_seen) > self.opt.peer_keepalive {
             self.peers.remove(session_id);
             return Ok(());
         }
 
-        match self.mode {
-            SockMode::Connect(_) => {
-                self.sock.send(data)?;
-            }
-            SockMode::Bind => {
-                self.sock.send_to(data, addr)?;
-            }
+        self.transport.send_to(&self.seal(data)?, &addr)?;
+
+        Ok(())
+    }
+
+    /// Whether messages are moved whole by the transport, rather than chunked into frames.
+    pub fn carries_messages(&self) -> bool {
+        self.transport.carries_messages()
+    }
+
+    /// Hand a whole message to a peer, for transports that carry messages. Messages never leave
+    /// the process, so they aren't passed through the encryptor.
+    pub fn send_message(
+        &mut self,
+        message: MessageFrame,
+        session_id: &u64,
+    ) -> Result<(), Box<dyn Error>> {
+        let now = Instant::now();
+
+        let Some(peer) = self.peers.get_mut(session_id) else {
+            return Err("No such peer".into());
+        };
+
+        peer.last_sent = now;
+        let addr = peer.addr.clone();
+
+        if now.duration_since(peer.last_seen) > self.opt.peer_keepalive {
+            self.peers.remove(session_id);
+            return Ok(());
         }
 
+        self.transport.send_message(message, &addr)?;
+
         Ok(())
     }
 
@@ -318,22 +1003,25 @@ impl Core {
         let mut send_heartbeat = Vec::with_capacity(self.peers.len());
         let mut prune = Vec::with_capacity(self.peers.len());
 
-        self.peers.iter().for_each(|(session_id, peer)| {
+        self.peers.iter_mut().for_each(|(session_id, peer)| {
             if now.duration_since(peer.last_seen) > self.opt.peer_keepalive {
                 prune.push(*session_id);
             }
 
             if now.duration_since(peer.last_sent) > self.opt.peer_heartbeat_ivl {
-                send_heartbeat.push((*session_id, peer.addr));
+                peer.last_sent = now;
+                let number = peer.heartbeat(self.opt.adaptive_rto);
+                send_heartbeat.push((*session_id, number, peer.addr.clone()));
             }
         });
 
         send_heartbeat
             .drain(..)
-            .for_each(|(session_id, peer_addr)| {
-                if let Err(_) =
-                    self.send_direct(&ControlFrame::Heartbeat(session_id).encode(), &peer_addr)
-                {
+            .for_each(|(session_id, number, peer_addr)| {
+                if let Err(_) = self.send_direct(
+                    &self.encode_control(&ControlFrame::Heartbeat((session_id, number))),
+                    &peer_addr,
+                ) {
                     prune.push(session_id);
                 }
             });
@@ -343,11 +1031,24 @@ impl Core {
             self.peers.remove(&session_id);
         });
 
+        self.pending
+            .retain(|_, peer| now.duration_since(peer.last_seen) < self.opt.peer_keepalive);
+
         self.reconnect()?;
 
         Ok(())
     }
 
+    /// Take the round trip to a session measured from its last heartbeat echo, if one came back
+    /// since the last call.
+    pub fn take_rtt_sample(&mut self, session_id: &u64) -> Option<Duration> {
+        self.peers.get_mut(session_id)?.rtt_sample.take()
+    }
+
+    pub fn local_addr(&self) -> Result<Addr, Box<dyn Error>> {
+        Ok(self.transport.local_addr()?)
+    }
+
     pub fn update_peers(&mut self) -> Option<Vec<u64>> {
         if self.peer_update {
             self.peer_update = false;
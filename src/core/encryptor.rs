@@ -0,0 +1,36 @@
+use std::{error::Error, fmt};
+
+/// Per-session payload encryption, invoked by `Core` on every frame it puts on or takes off the
+/// wire.
+///
+/// Frame headers stay in the clear so the receiving side can route on `session_id`. They are
+/// passed in as associated data, so an AEAD scheme can authenticate them along with the payload.
+pub trait Encryptor: Send + Sync {
+    /// Seal the payload of an outgoing frame belonging to `session_id`.
+    fn encrypt(
+        &self,
+        session_id: u64,
+        header: &[u8],
+        payload: &[u8],
+    ) -> Result<Vec<u8>, Box<dyn Error>>;
+
+    /// Open the payload of an incoming frame. Returning an error drops the frame before it
+    /// reaches the connection or messaging layer.
+    fn decrypt(
+        &self,
+        session_id: u64,
+        header: &[u8],
+        payload: &[u8],
+    ) -> Result<Vec<u8>, Box<dyn Error>>;
+
+    /// Number of bytes `encrypt` adds to a payload (nonce, tag, ...).
+    fn overhead(&self) -> usize {
+        0
+    }
+}
+
+impl fmt::Debug for dyn Encryptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Encryptor")
+    }
+}
@@ -1,7 +1,9 @@
 mod as_socket;
 mod core;
+mod encryptor;
 mod sock_opt;
 
 pub use as_socket::AsSocket;
 pub use core::Core;
+pub use encryptor::Encryptor;
 pub use sock_opt::SockOpt;
@@ -1,4 +1,11 @@
-use std::time::Duration;
+use std::{
+    net::{Ipv4Addr, SocketAddrV4},
+    sync::Arc,
+    time::Duration,
+};
+
+use super::encryptor::Encryptor;
+use crate::frame;
 
 #[derive(Clone, Debug)]
 pub struct SockOpt {
@@ -13,6 +20,23 @@ pub struct SockOpt {
     pub reconnect_wait: Duration,
     pub safe_resend_ivl: Duration,
     pub safe_hash_dedup_ttl: Duration,
+    pub encryptor: Option<Arc<dyn Encryptor>>,
+    pub psk: Option<Vec<u8>>,
+    pub cookie_challenge: bool,
+    pub cookie_ttl: Duration,
+    pub max_peers: usize,
+    pub connect_timeout: Duration,
+    pub max_frame_size: usize,
+    pub reresolve_on_reconnect: bool,
+    pub multicast_group: Option<SocketAddrV4>,
+    pub multicast_interface: Ipv4Addr,
+    pub req_timeout: Duration,
+    pub req_retries: usize,
+    pub survey_deadline: Duration,
+    pub safe_ordered: bool,
+    pub safe_nack_ivl: Duration,
+    pub adaptive_rto: bool,
+    pub congestion_control: bool,
 }
 
 impl Default for SockOpt {
@@ -29,6 +53,23 @@ impl Default for SockOpt {
             reconnect_wait: Duration::from_secs_f64(5.),
             safe_resend_ivl: Duration::from_secs_f64(0.2),
             safe_hash_dedup_ttl: Duration::from_secs_f64(1.0),
+            encryptor: None,
+            psk: None,
+            cookie_challenge: false,
+            cookie_ttl: Duration::from_secs_f64(5.),
+            max_peers: 1024,
+            connect_timeout: Duration::from_secs_f64(1.),
+            max_frame_size: frame::MAX_FRAME_SIZE,
+            reresolve_on_reconnect: false,
+            multicast_group: None,
+            multicast_interface: Ipv4Addr::UNSPECIFIED,
+            req_timeout: Duration::from_secs_f64(1.),
+            req_retries: 3,
+            survey_deadline: Duration::from_secs_f64(1.),
+            safe_ordered: false,
+            safe_nack_ivl: Duration::from_secs_f64(0.05),
+            adaptive_rto: false,
+            congestion_control: false,
         }
     }
 }
@@ -17,6 +17,13 @@ use crate::hash::Fnv1a64;
 //
 // HEADER = 34b
 
+// v0.2.0 sequenced DataFrame, sent by Safe sockets
+// | DataFrame header, kind 12
+// | sequence; 8
+// | data
+//
+// HEADER = 42b
+
 // v0.2.0 ControlFrame
 // | version; 1
 // | kind; 1
@@ -27,9 +34,16 @@ use crate::hash::Fnv1a64;
 
 pub const VERSION: u8 = 1;
 pub const DATA_HEADER_SIZE: usize = 34;
+pub const SEQUENCE_SIZE: usize = 8;
+pub const SEQUENCED_DATA_HEADER_SIZE: usize = DATA_HEADER_SIZE + SEQUENCE_SIZE;
+pub const SEQUENCED_DATA_KIND: u8 = 12;
 pub const CONTROL_HEADER_SIZE: usize = 10;
 pub const MAX_FRAME_SIZE: usize = 500;
 pub const MAX_DATA_SIZE: usize = MAX_FRAME_SIZE - DATA_HEADER_SIZE;
+pub const MAX_CHUNK_SIZE: usize = u16::MAX as usize;
+pub const MAC_SIZE: usize = 32;
+pub const MAC_COUNTER_SIZE: usize = 8;
+pub const COOKIE_SIZE: usize = 24;
 
 pub struct DataFrame {
     pub version: u8,
@@ -43,6 +57,8 @@ pub struct DataFrame {
     pub part_size: u32,
     pub chunk_size: u16,
     pub chunk_offset: u32,
+    /// Position of the frame in its session's stream of safe frames, for sequenced frames.
+    pub sequence: Option<u64>,
     pub chunk: Vec<u8>,
 }
 
@@ -77,6 +93,50 @@ impl DataFrame {
         frame
     }
 
+    /// Turn an encoded frame into a sequenced one at `sequence`.
+    pub fn sequence(frame: &[u8], sequence: u64) -> Vec<u8> {
+        let mut sequenced = Vec::with_capacity(frame.len() + SEQUENCE_SIZE);
+
+        sequenced.extend_from_slice(&frame[..DATA_HEADER_SIZE]);
+        sequenced[1] = SEQUENCED_DATA_KIND;
+        sequenced.extend_from_slice(&sequence.to_be_bytes());
+        sequenced.extend_from_slice(&frame[DATA_HEADER_SIZE..]);
+
+        sequenced
+    }
+
+    /// Read the sequence number of an encoded sequenced frame without parsing the rest.
+    pub fn sequence_of(frame: &[u8]) -> Option<u64> {
+        if frame.len() < SEQUENCED_DATA_HEADER_SIZE || frame[1] != SEQUENCED_DATA_KIND {
+            return None;
+        }
+
+        Some(u64::from_be_bytes(
+            frame[DATA_HEADER_SIZE..SEQUENCED_DATA_HEADER_SIZE]
+                .try_into()
+                .ok()?,
+        ))
+    }
+
+    /// Read the message id, part index and byte range within the part of an encoded frame
+    /// without parsing the rest.
+    pub fn chunk_of(frame: &[u8]) -> Option<(u64, u8, u32, u32)> {
+        if frame.len() < DATA_HEADER_SIZE {
+            return None;
+        }
+
+        let message_id = u64::from_be_bytes(frame[10..18].try_into().ok()?);
+        let chunk_size = u16::from_be_bytes(frame[28..30].try_into().ok()?);
+        let chunk_offset = u32::from_be_bytes(frame[30..34].try_into().ok()?);
+
+        Some((
+            message_id,
+            frame[19],
+            chunk_offset,
+            chunk_offset + chunk_size as u32,
+        ))
+    }
+
     pub fn parse(buf: &[u8]) -> Result<Option<DataFrame>, Box<dyn Error>> {
         if buf.len() < DATA_HEADER_SIZE {
             return Ok(None);
@@ -86,6 +146,19 @@ impl DataFrame {
             return Ok(None);
         }
 
+        let (sequence, header_size) = match buf[1] {
+            SEQUENCED_DATA_KIND => match DataFrame::sequence_of(buf) {
+                Some(sequence) => (Some(sequence), SEQUENCED_DATA_HEADER_SIZE),
+                None => return Ok(None),
+            },
+            _ => (None, DATA_HEADER_SIZE),
+        };
+
+        let chunk_size = u16::from_be_bytes((&buf[28..30]).try_into()?);
+        if buf.len() - header_size != chunk_size as usize {
+            return Ok(None);
+        }
+
         Ok(Some(DataFrame {
             version: buf[0],
             kind: buf[1],
@@ -96,14 +169,15 @@ impl DataFrame {
 
             message_size: u32::from_be_bytes((&buf[20..24]).try_into()?),
             part_size: u32::from_be_bytes((&buf[24..28]).try_into()?),
-            chunk_size: u16::from_be_bytes((&buf[28..30]).try_into()?),
+            chunk_size,
             chunk_offset: u32::from_be_bytes((&buf[30..34]).try_into()?),
-            chunk: buf[34..].to_vec(),
+            sequence,
+            chunk: buf[header_size..].to_vec(),
         }))
     }
 
     pub fn hash(&self) -> u64 {
-        let buffer = DataFrame::encode(
+        let mut buffer = DataFrame::encode(
             self.kind,
             self.session_id,
             self.message_id,
@@ -116,18 +190,137 @@ impl DataFrame {
             &self.chunk,
         );
 
+        if let Some(sequence) = self.sequence {
+            buffer = DataFrame::sequence(&buffer, sequence);
+        }
+
         let mut hasher = Fnv1a64::new();
         hasher.write(&buffer);
         hasher.finish()
     }
 }
 
+/// Acknowledges every sequenced frame of a session below `cumulative`, and those in the
+/// half-open `ranges` above it.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Sack {
+    pub session_id: u64,
+    pub cumulative: u64,
+    pub ranges: Vec<(u64, u64)>,
+}
+
+impl Sack {
+    /// Ranges that fit in one control frame of `max_frame_size` alongside the cumulative.
+    pub fn max_ranges(max_frame_size: usize) -> usize {
+        max_frame_size.saturating_sub(CONTROL_HEADER_SIZE + 8) / 16
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(8 + self.ranges.len() * 16);
+
+        buf.extend_from_slice(&self.cumulative.to_be_bytes());
+        for (start, end) in self.ranges.iter() {
+            buf.extend_from_slice(&start.to_be_bytes());
+            buf.extend_from_slice(&end.to_be_bytes());
+        }
+
+        buf
+    }
+
+    fn parse(session_id: u64, buf: &[u8]) -> Option<Sack> {
+        if buf.len() < 8 || !(buf.len() - 8).is_multiple_of(16) {
+            return None;
+        }
+
+        let cumulative = u64::from_be_bytes(buf[0..8].try_into().ok()?);
+        let mut ranges = Vec::with_capacity((buf.len() - 8) / 16);
+
+        for range in buf[8..].chunks_exact(16) {
+            ranges.push((
+                u64::from_be_bytes(range[0..8].try_into().ok()?),
+                u64::from_be_bytes(range[8..16].try_into().ok()?),
+            ));
+        }
+
+        Some(Sack {
+            session_id,
+            cumulative,
+            ranges,
+        })
+    }
+}
+
+/// Asks for the chunks of a stalled message the receiver is missing, as `(part_index, start,
+/// end)` byte ranges within each part. A part never seen is asked for whole, up to `u32::MAX`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Nack {
+    pub session_id: u64,
+    pub message_id: u64,
+    pub ranges: Vec<(u8, u32, u32)>,
+}
+
+impl Nack {
+    /// Ranges that fit in one control frame of `max_frame_size` alongside the message id.
+    pub fn max_ranges(max_frame_size: usize) -> usize {
+        max_frame_size.saturating_sub(CONTROL_HEADER_SIZE + 8) / 9
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(8 + self.ranges.len() * 9);
+
+        buf.extend_from_slice(&self.message_id.to_be_bytes());
+        for (part_index, start, end) in self.ranges.iter() {
+            buf.push(*part_index);
+            buf.extend_from_slice(&start.to_be_bytes());
+            buf.extend_from_slice(&end.to_be_bytes());
+        }
+
+        buf
+    }
+
+    fn parse(session_id: u64, buf: &[u8]) -> Option<Nack> {
+        if buf.len() < 8 || !(buf.len() - 8).is_multiple_of(9) {
+            return None;
+        }
+
+        let message_id = u64::from_be_bytes(buf[0..8].try_into().ok()?);
+        let mut ranges = Vec::with_capacity((buf.len() - 8) / 9);
+
+        for range in buf[8..].chunks_exact(9) {
+            ranges.push((
+                range[0],
+                u32::from_be_bytes(range[1..5].try_into().ok()?),
+                u32::from_be_bytes(range[5..9].try_into().ok()?),
+            ));
+        }
+
+        Some(Nack {
+            session_id,
+            message_id,
+            ranges,
+        })
+    }
+}
+
 pub enum ControlFrame {
     Connect,
     Connected(u64),
     Disconnected(u64),
-    Heartbeat(u64),
+    /// Session and heartbeat number, 0 unless the sender wants it echoed.
+    Heartbeat((u64, u64)),
     Ack((u64, Vec<u8>)),
+    Cookie(Vec<u8>),
+    CookieEcho(Vec<u8>),
+    Subscribe((u64, Vec<Vec<u8>>)),
+    Unsubscribe((u64, Vec<Vec<u8>>)),
+    Join((u64, Vec<Vec<u8>>)),
+    Leave((u64, Vec<Vec<u8>>)),
+    Sack(Sack),
+    Nack(Nack),
+    /// Session and the number of the heartbeat answered.
+    HeartbeatEcho((u64, u64)),
+    /// Every sequence number below the second was acknowledged or given up on by the sender.
+    Settled((u64, u64)),
 }
 
 impl ControlFrame {
@@ -147,8 +340,31 @@ impl ControlFrame {
             Self::Connect => ControlFrame::_enc(0, 1, &[]),
             Self::Connected(session) => ControlFrame::_enc(*session, 2, &[]),
             Self::Disconnected(session) => ControlFrame::_enc(*session, 3, &[]),
-            Self::Heartbeat(session) => ControlFrame::_enc(*session, 4, &[]),
+            Self::Heartbeat((session, number)) => {
+                ControlFrame::_enc(*session, 4, &number.to_be_bytes())
+            }
             Self::Ack((session, chunk)) => ControlFrame::_enc(*session, 5, chunk),
+            Self::Cookie(cookie) => ControlFrame::_enc(0, 6, cookie),
+            Self::CookieEcho(cookie) => ControlFrame::_enc(0, 7, cookie),
+            Self::Subscribe((session, topics)) => {
+                ControlFrame::_enc(*session, 8, &encode_list(topics))
+            }
+            Self::Unsubscribe((session, topics)) => {
+                ControlFrame::_enc(*session, 9, &encode_list(topics))
+            }
+            Self::Join((session, groups)) => ControlFrame::_enc(*session, 10, &encode_list(groups)),
+            Self::Leave((session, groups)) => {
+                ControlFrame::_enc(*session, 11, &encode_list(groups))
+            }
+            // Kind 12 is the sequenced DataFrame.
+            Self::Sack(sack) => ControlFrame::_enc(sack.session_id, 13, &sack.encode()),
+            Self::Nack(nack) => ControlFrame::_enc(nack.session_id, 14, &nack.encode()),
+            Self::HeartbeatEcho((session, number)) => {
+                ControlFrame::_enc(*session, 15, &number.to_be_bytes())
+            }
+            Self::Settled((session, below)) => {
+                ControlFrame::_enc(*session, 16, &below.to_be_bytes())
+            }
         }
     }
 
@@ -167,21 +383,169 @@ impl ControlFrame {
             3 => Some(ControlFrame::Disconnected(u64::from_be_bytes(
                 buf[2..10].try_into()?,
             ))),
-            4 => Some(ControlFrame::Heartbeat(u64::from_be_bytes(
-                buf[2..10].try_into()?,
+            4 => Some(ControlFrame::Heartbeat((
+                u64::from_be_bytes(buf[2..10].try_into()?),
+                heartbeat_number(buf),
             ))),
             5 => Some(ControlFrame::Ack((
                 u64::from_be_bytes(buf[2..10].try_into()?),
                 buf[CONTROL_HEADER_SIZE..].to_vec(),
             ))),
+            6 => Some(ControlFrame::Cookie(buf[CONTROL_HEADER_SIZE..].to_vec())),
+            7 => Some(ControlFrame::CookieEcho(
+                buf[CONTROL_HEADER_SIZE..].to_vec(),
+            )),
+            8 => {
+                let session = u64::from_be_bytes(buf[2..10].try_into()?);
+                parse_list(&buf[CONTROL_HEADER_SIZE..])
+                    .map(|topics| ControlFrame::Subscribe((session, topics)))
+            }
+            9 => {
+                let session = u64::from_be_bytes(buf[2..10].try_into()?);
+                parse_list(&buf[CONTROL_HEADER_SIZE..])
+                    .map(|topics| ControlFrame::Unsubscribe((session, topics)))
+            }
+            10 => {
+                let session = u64::from_be_bytes(buf[2..10].try_into()?);
+                parse_list(&buf[CONTROL_HEADER_SIZE..])
+                    .map(|groups| ControlFrame::Join((session, groups)))
+            }
+            11 => {
+                let session = u64::from_be_bytes(buf[2..10].try_into()?);
+                parse_list(&buf[CONTROL_HEADER_SIZE..])
+                    .map(|groups| ControlFrame::Leave((session, groups)))
+            }
+            13 => {
+                let session = u64::from_be_bytes(buf[2..10].try_into()?);
+                Sack::parse(session, &buf[CONTROL_HEADER_SIZE..]).map(ControlFrame::Sack)
+            }
+            14 => {
+                let session = u64::from_be_bytes(buf[2..10].try_into()?);
+                Nack::parse(session, &buf[CONTROL_HEADER_SIZE..]).map(ControlFrame::Nack)
+            }
+            15 => Some(ControlFrame::HeartbeatEcho((
+                u64::from_be_bytes(buf[2..10].try_into()?),
+                heartbeat_number(buf),
+            ))),
+            16 => {
+                if buf.len() < CONTROL_HEADER_SIZE + 8 {
+                    return Ok(None);
+                }
+
+                Some(ControlFrame::Settled((
+                    u64::from_be_bytes(buf[2..10].try_into()?),
+                    u64::from_be_bytes(
+                        buf[CONTROL_HEADER_SIZE..CONTROL_HEADER_SIZE + 8].try_into()?,
+                    ),
+                )))
+            }
             _ => None,
         })
     }
 }
 
+/// The number carried by a heartbeat or its echo, 0 if there's none.
+fn heartbeat_number(buf: &[u8]) -> u64 {
+    buf[CONTROL_HEADER_SIZE..]
+        .first_chunk::<8>()
+        .map_or(0, |number| u64::from_be_bytes(*number))
+}
+
+/// Encode a list of byte strings, each prefixed with its u16 big-endian length.
+pub fn encode_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(items.iter().fold(0, |a, v| a + 2 + v.len()));
+
+    for item in items.iter() {
+        buf.extend_from_slice(&(item.len() as u16).to_be_bytes());
+        buf.extend_from_slice(item);
+    }
+
+    buf
+}
+
+/// Parse a list encoded by `encode_list`. Malformed lists yield `None`.
+pub fn parse_list(mut buf: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let mut items = vec![];
+
+    while !buf.is_empty() {
+        if buf.len() < 2 {
+            return None;
+        }
+
+        let len = u16::from_be_bytes(buf[0..2].try_into().ok()?) as usize;
+        if buf.len() < 2 + len {
+            return None;
+        }
+
+        items.push(buf[2..2 + len].to_vec());
+        buf = &buf[2 + len..];
+    }
+
+    Some(items)
+}
+
+/// Split a list into batches whose `encode_list` encoding fits in `max_size` bytes, so each
+/// batch travels in one control frame. An item too large on its own gets a batch of its own.
+pub fn batch_list(items: &[Vec<u8>], max_size: usize) -> Vec<Vec<Vec<u8>>> {
+    let mut batches: Vec<Vec<Vec<u8>>> = vec![];
+    let mut batch_size = 0;
+
+    for item in items.iter() {
+        if batches.is_empty() || (batch_size > 0 && batch_size + 2 + item.len() > max_size) {
+            batches.push(vec![]);
+            batch_size = 0;
+        }
+
+        batch_size += 2 + item.len();
+        if let Some(batch) = batches.last_mut() {
+            batch.push(item.clone());
+        }
+    }
+
+    batches
+}
+
+/// Whether control frames of `kind` carry an HMAC when a pre-shared key is in use. These are the
+/// handshake and liveness frames.
+pub fn requires_mac(kind: u8) -> bool {
+    matches!(kind, 1..=4 | 7 | 15)
+}
+
+/// Split an encoded frame into its header, payload and session id, based on the frame kind.
+pub fn split_header(buf: &[u8]) -> Option<(&[u8], &[u8], u64)> {
+    if buf.len() < 2 {
+        return None;
+    }
+
+    let header_size = match buf[1] {
+        0 => DATA_HEADER_SIZE,
+        SEQUENCED_DATA_KIND => SEQUENCED_DATA_HEADER_SIZE,
+        _ => CONTROL_HEADER_SIZE,
+    };
+
+    if buf.len() < header_size {
+        return None;
+    }
+
+    let session_id = u64::from_be_bytes(buf[2..10].try_into().ok()?);
+    let (header, payload) = buf.split_at(header_size);
+
+    Some((header, payload, session_id))
+}
+
+/// A whole multipart message, handed across an in-process transport as is. Never chunked or
+/// encoded to the wire.
+#[derive(Clone)]
+pub struct MessageFrame {
+    pub session_id: u64,
+    pub message_id: u64,
+    pub parts: Vec<Vec<u8>>,
+}
+
 pub enum Frame {
     ControlFrame(ControlFrame),
     DataFrame(DataFrame),
+    MessageFrame(MessageFrame),
 }
 
 impl Frame {
@@ -197,7 +561,7 @@ impl Frame {
         let kind = buf[1];
 
         match kind {
-            0 => match DataFrame::parse(buf)? {
+            0 | SEQUENCED_DATA_KIND => match DataFrame::parse(buf)? {
                 Some(data_frame) => Ok(Some(Frame::DataFrame(data_frame))),
                 None => Ok(None),
             },
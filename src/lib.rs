@@ -1,11 +1,15 @@
 pub mod api;
 mod core;
 pub mod frame;
+mod proxy;
 pub mod queue;
 mod sockets;
+pub mod transport;
 mod util;
 
 pub use crate::api::*;
 pub use crate::core::*;
+pub use crate::proxy::{Proxy, proxy};
 pub use crate::sockets::*;
+pub use crate::transport::{Addr, Transport};
 pub use crate::util::*;
@@ -0,0 +1,153 @@
+use std::{error::Error, io, thread, time::Duration};
+
+use crate::core::AsSocket;
+
+/// Moves messages between two sockets in both directions, unchanged, so envelopes such as the
+/// session id frame of a `Router` survive the trip. Every message moved can be mirrored to a
+/// capture socket.
+pub struct Proxy<F, B, C = F> {
+    frontend: F,
+    backend: B,
+    capture: Option<C>,
+
+    /// A message the far side refused, retried before anything else moves that way.
+    to_backend: Option<Vec<Vec<u8>>>,
+    to_frontend: Option<Vec<Vec<u8>>>,
+}
+
+impl<F: AsSocket, B: AsSocket> Proxy<F, B> {
+    pub fn new(frontend: F, backend: B) -> Self {
+        Self {
+            frontend,
+            backend,
+            capture: None,
+
+            to_backend: None,
+            to_frontend: None,
+        }
+    }
+}
+
+impl<F: AsSocket, B: AsSocket, C: AsSocket> Proxy<F, B, C> {
+    /// Mirror every message moved, in either direction, to `capture`. Capture is best effort,
+    /// a message it refuses is not retried.
+    pub fn with_capture<D: AsSocket>(self, capture: D) -> Proxy<F, B, D> {
+        Proxy {
+            frontend: self.frontend,
+            backend: self.backend,
+            capture: Some(capture),
+
+            to_backend: self.to_backend,
+            to_frontend: self.to_frontend,
+        }
+    }
+
+    pub fn frontend(&mut self) -> &mut F {
+        &mut self.frontend
+    }
+
+    pub fn backend(&mut self) -> &mut B {
+        &mut self.backend
+    }
+
+    pub fn capture(&mut self) -> Option<&mut C> {
+        self.capture.as_mut()
+    }
+
+    /// Move a message from one socket to another. A message the destination refuses, for
+    /// example at its `send_hwm`, is held in `pending` and the direction stalls until it goes.
+    fn forward<S: AsSocket, D: AsSocket>(
+        from: &mut S,
+        to: &mut D,
+        pending: &mut Option<Vec<Vec<u8>>>,
+        capture: &mut Option<C>,
+    ) -> bool {
+        let message = match pending.take() {
+            Some(message) => message,
+            None => match from.recv() {
+                Ok(message) => message,
+                Err(_) => return false,
+            },
+        };
+
+        let parts = message
+            .iter()
+            .map(|part| part.as_slice())
+            .collect::<Vec<&[u8]>>();
+
+        if to.send(&parts).is_err() {
+            *pending = Some(message);
+            return false;
+        }
+
+        if let Some(capture) = capture {
+            let _ = capture.send(&parts);
+        }
+
+        true
+    }
+
+    /// Tick a socket. A socket at its `recv_hwm` is only backpressure, forwarding drains it.
+    fn tick<S: AsSocket>(socket: &mut S) -> Result<(), Box<dyn Error>> {
+        match socket.tick() {
+            Err(e)
+                if e.downcast_ref::<io::Error>().map(|e| e.kind())
+                    == Some(io::ErrorKind::WouldBlock) =>
+            {
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    /// Tick every socket and move whatever messages are ready, in both directions. Returns the
+    /// number of messages moved.
+    pub fn step(&mut self) -> Result<usize, Box<dyn Error>> {
+        Self::tick(&mut self.frontend)?;
+        Self::tick(&mut self.backend)?;
+
+        let mut moved = 0;
+
+        while Self::forward(
+            &mut self.frontend,
+            &mut self.backend,
+            &mut self.to_backend,
+            &mut self.capture,
+        ) {
+            moved += 1;
+        }
+
+        while Self::forward(
+            &mut self.backend,
+            &mut self.frontend,
+            &mut self.to_frontend,
+            &mut self.capture,
+        ) {
+            moved += 1;
+        }
+
+        Self::tick(&mut self.frontend)?;
+        Self::tick(&mut self.backend)?;
+
+        if let Some(capture) = &mut self.capture {
+            Self::tick(capture)?;
+        }
+
+        Ok(moved)
+    }
+
+    /// Step forever, sleeping briefly whenever a step moves nothing. Returns on the first
+    /// socket error other than `WouldBlock`.
+    pub fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        loop {
+            if self.step()? == 0 {
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+    }
+}
+
+/// Bridge two sockets until one of them fails, see `Proxy`.
+pub fn proxy<F: AsSocket, B: AsSocket>(frontend: F, backend: B) -> Result<(), Box<dyn Error>> {
+    Proxy::new(frontend, backend).run()
+}
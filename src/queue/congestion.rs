@@ -0,0 +1,60 @@
+use std::time::{Duration, Instant};
+
+/// Window a peer starts with, and the floor and ceiling it moves between, in frames.
+pub const INITIAL_CWND: f64 = 10.;
+pub const MIN_CWND: f64 = 2.;
+pub const MAX_CWND: f64 = 65536.;
+
+/// AIMD congestion window to one peer, in frames in flight. Grows by one frame per frame acked
+/// until the first loss (slow start), then by about one frame per window acked, and halves on
+/// loss, at most once per `hold`.
+#[derive(Clone, Debug)]
+pub struct Cwnd {
+    pub cwnd: f64,
+    pub ssthresh: f64,
+    last_cut: Option<Instant>,
+}
+
+impl Default for Cwnd {
+    fn default() -> Self {
+        Self {
+            cwnd: INITIAL_CWND,
+            ssthresh: MAX_CWND,
+            last_cut: None,
+        }
+    }
+}
+
+impl Cwnd {
+    /// Number of frames that may be in flight.
+    pub fn window(&self) -> usize {
+        self.cwnd as usize
+    }
+
+    /// Grow for `n` frames newly acked.
+    pub fn on_ack(&mut self, n: usize) {
+        for _ in 0..n {
+            if self.cwnd < self.ssthresh {
+                self.cwnd += 1.;
+            } else {
+                self.cwnd += 1. / self.cwnd;
+            }
+        }
+
+        self.cwnd = self.cwnd.min(MAX_CWND);
+    }
+
+    /// Halve on a loss, unless the window was already cut within `hold`, so a burst of losses
+    /// from one window counts once.
+    pub fn on_loss(&mut self, hold: Duration) {
+        if let Some(last_cut) = self.last_cut
+            && last_cut.elapsed() < hold
+        {
+            return;
+        }
+
+        self.ssthresh = (self.cwnd / 2.).max(MIN_CWND);
+        self.cwnd = self.ssthresh;
+        self.last_cut = Some(Instant::now());
+    }
+}
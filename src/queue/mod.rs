@@ -1,5 +1,9 @@
+pub mod congestion;
 pub mod recv_queue;
+pub mod rtt;
 pub mod send_queue;
 
+pub use congestion::Cwnd;
 pub use recv_queue::RecvQueue;
-pub use send_queue::SendQueue;
+pub use rtt::Rtt;
+pub use send_queue::{SendQueue, SendStats};
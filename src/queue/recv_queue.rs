@@ -1,11 +1,14 @@
 use std::{
-    collections::{HashMap, HashSet, VecDeque},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
     error::Error,
     io,
     time::Instant,
 };
 
-use crate::{SockOpt, frame::DataFrame};
+use crate::{
+    SockOpt,
+    frame::{DataFrame, MessageFrame, Nack, Sack},
+};
 
 #[derive(Clone)]
 pub struct MessagePart {
@@ -54,6 +57,28 @@ impl MessagePart {
 
         return Ok(false);
     }
+
+    /// Byte ranges of the part not yet received.
+    pub fn missing(&self) -> Vec<(u32, u32)> {
+        let mut assigned = self.assigned_ranges.iter().cloned().collect::<Vec<_>>();
+        assigned.sort();
+
+        let mut missing = vec![];
+        let mut offset = 0;
+
+        for (start, end) in assigned {
+            if start > offset {
+                missing.push((offset, start));
+            }
+            offset = offset.max(end);
+        }
+
+        if offset < self.size {
+            missing.push((offset, self.size));
+        }
+
+        missing
+    }
 }
 
 pub struct IncomingMessage {
@@ -65,6 +90,11 @@ pub struct IncomingMessage {
     pub parts: Vec<Option<MessagePart>>,
 
     pub last_modify: Instant,
+    pub last_nack: Option<Instant>,
+
+    /// Lowest and highest sequence number among its frames, if they came sequenced.
+    pub first_sequence: Option<u64>,
+    pub last_sequence: Option<u64>,
 }
 
 impl IncomingMessage {
@@ -81,7 +111,29 @@ impl IncomingMessage {
             parts,
 
             last_modify: Instant::now(),
+            last_nack: None,
+
+            first_sequence: None,
+            last_sequence: None,
+        }
+    }
+
+    /// Byte ranges still missing, per part index. Parts not seen at all are missing whole.
+    pub fn missing(&self) -> Vec<(u8, u32, u32)> {
+        let mut missing = vec![];
+
+        for (i, part) in self.parts.iter().enumerate() {
+            match part {
+                Some(part) => missing.extend(
+                    part.missing()
+                        .into_iter()
+                        .map(|(start, end)| (i as u8, start, end)),
+                ),
+                None => missing.push((i as u8, 0, u32::MAX)),
+            }
         }
+
+        missing
     }
 
     pub fn add_frame(&mut self, frame: &DataFrame) -> Result<bool, Box<dyn Error>> {
@@ -95,6 +147,11 @@ impl IncomingMessage {
 
         self.last_modify = Instant::now();
 
+        if let Some(sequence) = frame.sequence {
+            self.first_sequence = Some(self.first_sequence.map_or(sequence, |s| s.min(sequence)));
+            self.last_sequence = Some(self.last_sequence.map_or(sequence, |s| s.max(sequence)));
+        }
+
         if self.completed_parts == self.part_count && self.assigned == self.size {
             return Ok(true);
         }
@@ -103,6 +160,63 @@ impl IncomingMessage {
     }
 }
 
+/// A reassembled message and the session and message id it came under.
+pub type Pulled = (Vec<Vec<u8>>, (u64, u64));
+
+/// Messages of one session waiting for their turn, keyed by sequence number, along with the
+/// sequence number of their first frame if they came sequenced.
+#[derive(Default)]
+pub struct OrderedStream {
+    /// The sequence number delivered next. Anything below it was delivered or skipped.
+    pub next: u64,
+    pub held: BTreeMap<u64, (MessageFrame, Option<u64>)>,
+}
+
+/// Sequenced frames taken from one session, to be acknowledged together.
+pub struct SackState {
+    /// Every sequence number below it has been taken.
+    pub cumulative: u64,
+    pub above: BTreeSet<u64>,
+
+    /// Taken a frame since the last `Sack`.
+    pub dirty: bool,
+    pub last_sack: Instant,
+}
+
+impl Default for SackState {
+    fn default() -> Self {
+        Self {
+            cumulative: 0,
+            above: BTreeSet::new(),
+
+            dirty: false,
+            last_sack: Instant::now(),
+        }
+    }
+}
+
+impl SackState {
+    fn advance(&mut self) {
+        while self.above.remove(&self.cumulative) {
+            self.cumulative += 1;
+        }
+    }
+
+    /// The received sequence numbers above the cumulative, as half-open ranges.
+    fn ranges(&self) -> Vec<(u64, u64)> {
+        let mut ranges: Vec<(u64, u64)> = vec![];
+
+        for sequence in self.above.iter() {
+            match ranges.last_mut() {
+                Some((_, end)) if *end == *sequence => *end += 1,
+                _ => ranges.push((*sequence, sequence + 1)),
+            }
+        }
+
+        ranges
+    }
+}
+
 pub struct RecvQueue {
     pub opt: SockOpt,
 
@@ -114,6 +228,15 @@ pub struct RecvQueue {
 
     dedup: HashSet<(u64, u64)>,
     dedup_deque: VecDeque<((u64, u64), Instant)>,
+
+    /// Per session streams for `pull_ordered`.
+    pub streams: HashMap<u64, OrderedStream>,
+
+    /// Per session sequenced frames taken, for `take_sacks`.
+    pub sacks: HashMap<u64, SackState>,
+
+    /// First frame sequence numbers of complete messages, for `pull_ordered`.
+    first_sequences: HashMap<(u64, u64), u64>,
 }
 
 impl RecvQueue {
@@ -129,6 +252,12 @@ impl RecvQueue {
 
             dedup: HashSet::new(),
             dedup_deque: VecDeque::new(),
+
+            streams: HashMap::new(),
+
+            sacks: HashMap::new(),
+
+            first_sequences: HashMap::new(),
         }
     }
 
@@ -139,8 +268,26 @@ impl RecvQueue {
             return;
         }
 
-        self.incoming
-            .retain(|_, v| now.duration_since(v.last_modify) < self.opt.uncompleted_message_ttl);
+        // A message's frames go out under consecutive sequence numbers, so a sequenced message
+        // of a live session is kept past the ttl until the frame after its last one taken is
+        // taken or given up on too. Anything it still misses by then won't be resent. A sender
+        // with our resend options has given up within `held_for`, so that's the most it is kept.
+        let held_for = self
+            .opt
+            .uncompleted_message_ttl
+            .max(self.opt.safe_resend_ivl * (self.opt.safe_resend_limit as u32 + 1));
+        let sacks = &self.sacks;
+        self.incoming.retain(|(session_id, _), v| {
+            let idle = now.duration_since(v.last_modify);
+
+            idle < self.opt.uncompleted_message_ttl
+                || idle < held_for
+                    && v.last_sequence.is_some_and(|last| {
+                        sacks
+                            .get(session_id)
+                            .is_some_and(|state| state.cumulative <= last + 1)
+                    })
+        });
 
         self.last_maint = now;
     }
@@ -148,6 +295,11 @@ impl RecvQueue {
     pub fn push(&mut self, frame: &DataFrame) -> Result<(), Box<dyn Error>> {
         let key = (frame.session_id, frame.message_id);
 
+        // Refused up front, a frame completing its message would have nowhere to go.
+        if self.complete.len() >= self.opt.recv_hwm {
+            return Err(Box::new(io::Error::from(io::ErrorKind::WouldBlock)));
+        }
+
         let message = match self.incoming.get_mut(&key) {
             Some(m) => m,
             None => {
@@ -174,6 +326,10 @@ impl RecvQueue {
                         self.complete_deque.push_back(key);
                         reassembly
                     });
+
+                    if let Some(sequence) = message.first_sequence {
+                        self.first_sequences.insert(key, sequence);
+                    }
                 } else {
                     return Err(Box::new(io::Error::from(io::ErrorKind::WouldBlock)));
                 }
@@ -183,7 +339,29 @@ impl RecvQueue {
         Ok(())
     }
 
-    pub fn pull(&mut self) -> Option<(Vec<Vec<u8>>, (u64, u64))> {
+    /// Queue a message that arrived whole, skipping reassembly.
+    pub fn push_message(&mut self, message: MessageFrame) -> Result<(), Box<dyn Error>> {
+        let key = (message.session_id, message.message_id);
+
+        if self.complete.len() >= self.opt.recv_hwm {
+            return Err(Box::new(io::Error::from(io::ErrorKind::WouldBlock)));
+        }
+
+        self.complete.entry(key).or_insert_with(|| {
+            self.complete_deque.push_back(key);
+            message.parts
+        });
+
+        Ok(())
+    }
+
+    pub fn pull(&mut self) -> Option<Pulled> {
+        self.pull_sequenced()
+            .map(|(message, key, _)| (message, key))
+    }
+
+    /// `pull`, along with the sequence number of the message's first frame if it came sequenced.
+    fn pull_sequenced(&mut self) -> Option<(Vec<Vec<u8>>, (u64, u64), Option<u64>)> {
         self.maint();
 
         loop {
@@ -192,12 +370,12 @@ impl RecvQueue {
             };
 
             if let Some(message) = self.complete.remove(&key) {
-                return Some((message, key));
+                return Some((message, key, self.first_sequences.remove(&key)));
             }
         }
     }
 
-    pub fn pull_safe(&mut self) -> Option<(Vec<Vec<u8>>, (u64, u64))> {
+    pub fn pull_safe(&mut self) -> Option<Pulled> {
         let Some((message, key)) = self.pull() else {
             return None;
         };
@@ -219,4 +397,219 @@ impl RecvQueue {
 
         return None;
     }
+
+    /// Pull the next message of any session in the order it was sent, for messages carrying an
+    /// 8 byte big-endian sequence number as their first part. The sequence number is stripped.
+    /// Messages ahead of a gap are held, and a sequence number seen before is dropped for good.
+    /// A gap is skipped once every frame sent before the message after it was acknowledged or
+    /// given up on by the sender, see `settle`. Use `admits_ordered` to keep the number held
+    /// within `recv_hwm`.
+    pub fn pull_ordered(&mut self) -> Option<Pulled> {
+        while let Some((mut message, (session_id, message_id), first_sequence)) =
+            self.pull_sequenced()
+        {
+            if message.is_empty() {
+                continue;
+            }
+
+            let Ok(sequence) = <[u8; 8]>::try_from(message[0].as_slice()) else {
+                continue;
+            };
+            let sequence = u64::from_be_bytes(sequence);
+            message.remove(0);
+
+            let stream = self.streams.entry(session_id).or_default();
+
+            if sequence < stream.next {
+                continue;
+            }
+
+            stream.held.entry(sequence).or_insert((
+                MessageFrame {
+                    session_id,
+                    message_id,
+                    parts: message,
+                },
+                first_sequence,
+            ));
+        }
+
+        for (session_id, stream) in self.streams.iter_mut() {
+            let Some((&first, (_, first_sequence))) = stream.held.first_key_value() else {
+                continue;
+            };
+
+            // Nothing sent before the held message is coming anymore, the gap won't fill.
+            if first != stream.next
+                && let Some(first_sequence) = first_sequence
+                && let Some(state) = self.sacks.get(session_id)
+                && state.cumulative >= *first_sequence
+            {
+                stream.next = first;
+            }
+
+            if first == stream.next
+                && let Some((message, _)) = stream.held.remove(&first)
+            {
+                stream.next += 1;
+
+                return Some((message.parts, (*session_id, message.message_id)));
+            }
+        }
+
+        None
+    }
+
+    /// Whether to take a frame of an ordered message. Once `recv_hwm` messages are held behind a
+    /// gap, only frames of messages already under way or due before the held ones are taken.
+    /// Anything else is left unacknowledged for the sender to retransmit later.
+    pub fn admits_ordered(&self, frame: &DataFrame) -> bool {
+        // The message's own sequence number leads its first part.
+        let sequence = match frame.part_index == 0 && frame.chunk_offset == 0 {
+            true => frame.chunk.first_chunk::<8>().copied(),
+            false => None,
+        };
+
+        self.admits(frame.session_id, frame.message_id, sequence)
+    }
+
+    /// `admits_ordered` for a message that arrived whole.
+    pub fn admits_ordered_message(&self, message: &MessageFrame) -> bool {
+        let sequence = message
+            .parts
+            .first()
+            .and_then(|part| <[u8; 8]>::try_from(part.as_slice()).ok());
+
+        self.admits(message.session_id, message.message_id, sequence)
+    }
+
+    fn admits(&self, session_id: u64, message_id: u64, sequence: Option<[u8; 8]>) -> bool {
+        let Some(stream) = self.streams.get(&session_id) else {
+            return true;
+        };
+
+        let Some(&first) = stream.held.keys().next() else {
+            return true;
+        };
+
+        if stream.held.len() < self.opt.recv_hwm
+            || self.incoming.contains_key(&(session_id, message_id))
+        {
+            return true;
+        }
+
+        sequence.is_some_and(|sequence| u64::from_be_bytes(sequence) < first)
+    }
+
+    /// Whether a sequenced frame from a session was taken before, or settled past. Such a frame
+    /// belongs to a message already delivered or given up on, and would only open a new one.
+    pub fn taken(&self, session_id: u64, sequence: u64) -> bool {
+        self.sacks
+            .get(&session_id)
+            .is_some_and(|state| sequence < state.cumulative || state.above.contains(&sequence))
+    }
+
+    /// Note a sequenced frame taken from a session, to be acknowledged by the next `take_sacks`.
+    /// A repeat is noted too, as the sender missed the last acknowledgement.
+    pub fn record_sequence(&mut self, session_id: u64, sequence: u64) {
+        let state = self.sacks.entry(session_id).or_default();
+        state.dirty = true;
+
+        if sequence < state.cumulative {
+            return;
+        }
+
+        state.above.insert(sequence);
+        state.advance();
+    }
+
+    /// Take word from the sender that every sequence number below `below` was acknowledged or
+    /// given up on, so the gaps below it won't fill.
+    pub fn settle(&mut self, session_id: u64, below: u64) {
+        let Some(state) = self.sacks.get_mut(&session_id) else {
+            return;
+        };
+
+        if below <= state.cumulative {
+            return;
+        }
+
+        state.above = state.above.split_off(&below);
+        state.cumulative = below;
+        state.advance();
+    }
+
+    /// Build an acknowledgement for each session that sent frames since the last call, split so
+    /// no more than `max_ranges` ranges go in one. While a gap is open one goes out every
+    /// `safe_nack_ivl` regardless, so the sender gets to settle it.
+    pub fn take_sacks(&mut self, max_ranges: usize) -> Vec<Sack> {
+        let now = Instant::now();
+        let mut sacks = vec![];
+
+        for (session_id, state) in self.sacks.iter_mut() {
+            let gap_open = !state.above.is_empty()
+                && now.duration_since(state.last_sack) >= self.opt.safe_nack_ivl;
+
+            if !state.dirty && !gap_open {
+                continue;
+            }
+            state.dirty = false;
+            state.last_sack = now;
+
+            let ranges = state.ranges();
+            if ranges.is_empty() {
+                sacks.push(Sack {
+                    session_id: *session_id,
+                    cumulative: state.cumulative,
+                    ranges,
+                });
+                continue;
+            }
+
+            for batch in ranges.chunks(max_ranges.max(1)) {
+                sacks.push(Sack {
+                    session_id: *session_id,
+                    cumulative: state.cumulative,
+                    ranges: batch.to_vec(),
+                });
+            }
+        }
+
+        sacks
+    }
+
+    /// Ask for the missing chunks of every message that hasn't progressed for `safe_nack_ivl`,
+    /// at most once per `safe_nack_ivl` each. Only the first `max_ranges` gaps of a message are
+    /// asked for at once.
+    pub fn take_nacks(&mut self, max_ranges: usize) -> Vec<Nack> {
+        let now = Instant::now();
+        let mut nacks = vec![];
+
+        for ((session_id, message_id), message) in self.incoming.iter_mut() {
+            if now.duration_since(message.last_modify) < self.opt.safe_nack_ivl {
+                continue;
+            }
+
+            if let Some(last_nack) = message.last_nack
+                && now.duration_since(last_nack) < self.opt.safe_nack_ivl
+            {
+                continue;
+            }
+
+            let mut ranges = message.missing();
+            if ranges.is_empty() {
+                continue;
+            }
+            ranges.truncate(max_ranges.max(1));
+
+            message.last_nack = Some(now);
+            nacks.push(Nack {
+                session_id: *session_id,
+                message_id: *message_id,
+                ranges,
+            });
+        }
+
+        nacks
+    }
 }
@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+/// Bounds on the retransmission timeout, however fast or slow the link measures.
+pub const MIN_RTO: Duration = Duration::from_millis(5);
+pub const MAX_RTO: Duration = Duration::from_secs(60);
+
+/// Clock granularity floor on the variance term.
+const GRANULARITY: Duration = Duration::from_millis(1);
+
+/// Round trip estimate to one peer and the retransmission timeout derived from it, computed as
+/// TCP does (RFC 6298).
+#[derive(Clone, Debug)]
+pub struct Rtt {
+    pub srtt: Option<Duration>,
+    pub rttvar: Duration,
+    rto: Duration,
+}
+
+impl Rtt {
+    /// Start with `initial` as the timeout until the first sample comes in.
+    pub fn new(initial: Duration) -> Self {
+        Self {
+            srtt: None,
+            rttvar: Duration::ZERO,
+            rto: initial.clamp(MIN_RTO, MAX_RTO),
+        }
+    }
+
+    /// Fold in a measured round trip.
+    pub fn sample(&mut self, rtt: Duration) {
+        match self.srtt {
+            None => {
+                self.srtt = Some(rtt);
+                self.rttvar = rtt / 2;
+            }
+            Some(srtt) => {
+                let delta = srtt.abs_diff(rtt);
+                self.rttvar = (self.rttvar * 3 + delta) / 4;
+                self.srtt = Some((srtt * 7 + rtt) / 8);
+            }
+        }
+
+        let srtt = self.srtt.unwrap_or(rtt);
+        self.rto = (srtt + (self.rttvar * 4).max(GRANULARITY)).clamp(MIN_RTO, MAX_RTO);
+    }
+
+    /// Time to wait for an ack of something already sent `send_ct` times before, doubling with
+    /// each resend.
+    pub fn rto(&self, send_ct: usize) -> Duration {
+        self.rto.saturating_mul(1 << send_ct.min(16)).min(MAX_RTO)
+    }
+}
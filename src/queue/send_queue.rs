@@ -1,19 +1,35 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
 use std::error::Error;
 use std::hash::Hasher;
 use std::io;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use crate::SockOpt;
-use crate::frame::{self, DataFrame};
+use crate::frame::{self, DataFrame, MessageFrame};
+use crate::queue::{Cwnd, Rtt};
 use crate::util;
 use crate::util::hash::Fnv1a64;
 
 pub enum QueueItem {
     Frame(Vec<u8>),
+    Message(MessageFrame),
     Marker,
 }
 
+/// A snapshot of the send path to one peer.
+#[derive(Clone, Debug)]
+pub struct SendStats {
+    pub cwnd: usize,
+    pub ssthresh: usize,
+    pub in_flight: usize,
+    pub srtt: Option<Duration>,
+    pub rto: Duration,
+}
+
+/// Frames of one message in flight by part index and chunk offset, with the end of the chunk and
+/// the frame's hash.
+pub type Chunks = HashMap<(u8, u32), (u32, u64)>;
+
 pub struct SendQueue {
     opt: SockOpt,
 
@@ -21,19 +37,65 @@ pub struct SendQueue {
     pub frames: VecDeque<QueueItem>,
 
     pub sent: HashMap<u64, Vec<u8>>,
-    pub exp: VecDeque<(u64, Instant, usize)>,
+    pub sent_messages: HashMap<u64, MessageFrame>,
+    /// Frames and messages in flight as `(resend due, hash, times resent)`, soonest due first.
+    pub exp: BTreeSet<(Instant, u64, usize)>,
+
+    /// Round trip estimate to the peer, timing the resend of anything unacked under
+    /// `adaptive_rto`. Sampled from acks of things sent once, recorded in `sent_at`.
+    pub rtt: Rtt,
+    pub sent_at: HashMap<u64, Instant>,
+
+    /// Congestion window to the peer, grown by acks and cut by resends and `Nack`s. Caps the
+    /// frames in flight under `congestion_control`.
+    pub cwnd: Cwnd,
+
+    /// Ids of messages given up on after `safe_resend_limit` resends, until taken. Holds at most
+    /// `send_hwm` ids, the oldest go first.
+    pub dropped: VecDeque<u64>,
+
+    /// Sequence number given to the next message, for ordered delivery.
+    pub sequence: u64,
+
+    /// Sequence number given to the next frame sent safely, and the hash of each frame in flight
+    /// by its sequence number, for `confirm_sack`.
+    pub frame_sequence: u64,
+    pub sequenced: BTreeMap<u64, u64>,
+
+    /// Hashes of frames a receiver asked for again, resent ahead of anything else.
+    pub nacked: VecDeque<u64>,
+
+    /// Frames in flight by message id, for `nack`.
+    pub chunks: HashMap<u64, Chunks>,
 }
 
 impl SendQueue {
     pub fn new(opt: SockOpt) -> Self {
         Self {
-            opt,
+            opt: opt.clone(),
 
             message_count: 0,
             frames: VecDeque::new(),
 
             sent: HashMap::new(),
-            exp: VecDeque::new(),
+            sent_messages: HashMap::new(),
+            exp: BTreeSet::new(),
+
+            rtt: Rtt::new(opt.safe_resend_ivl),
+            sent_at: HashMap::new(),
+
+            cwnd: Cwnd::default(),
+
+            dropped: VecDeque::new(),
+
+            sequence: 0,
+
+            frame_sequence: 0,
+            sequenced: BTreeMap::new(),
+
+            nacked: VecDeque::new(),
+
+            chunks: HashMap::new(),
         }
     }
 
@@ -49,18 +111,63 @@ impl SendQueue {
     }
 
     pub fn len(&self) -> usize {
-        self.frames.len() + self.sent.len()
+        self.frames.len() + self.sent.len() + self.sent_messages.len()
+    }
+
+    /// Frames and messages sent safely and not yet confirmed or given up on.
+    pub fn in_flight(&self) -> usize {
+        self.sent.len() + self.sent_messages.len()
+    }
+
+    /// Lowest sequence number still in flight. Every one below it was confirmed or given up on.
+    pub fn settled_below(&self) -> u64 {
+        self.sequenced
+            .first_key_value()
+            .map_or(self.frame_sequence, |(sequence, _)| *sequence)
+    }
+
+    /// Whether `send_hwm` messages are already queued, so another would be refused.
+    pub fn full(&self) -> bool {
+        self.message_count >= self.opt.send_hwm
+    }
+
+    /// Whether the congestion window holds back anything new.
+    fn window_full(&self) -> bool {
+        self.opt.congestion_control && self.in_flight() >= self.cwnd.window()
+    }
+
+    pub fn stats(&self) -> SendStats {
+        SendStats {
+            cwnd: self.cwnd.window(),
+            ssthresh: self.cwnd.ssthresh as usize,
+            in_flight: self.in_flight(),
+            srtt: self.rtt.srtt,
+            rto: self.resend_timeout(0),
+        }
     }
 
     pub fn push(&mut self, session: u64, data: &[&[u8]], nonce: u64) -> Result<(), Box<dyn Error>> {
         println!("sendhwm: {} cur: {}", self.opt.send_hwm, self.message_count);
-        if self.message_count >= self.opt.send_hwm {
+        self.push_with_id(session, data, SendQueue::hash(data, nonce))
+    }
+
+    /// Queue a message under a caller chosen message id, for sockets that correlate messages by
+    /// id, rather than one derived from the message and a nonce.
+    pub fn push_with_id(
+        &mut self,
+        session: u64,
+        data: &[&[u8]],
+        message_hash: u64,
+    ) -> Result<(), Box<dyn Error>> {
+        if self.full() {
             return Err(Box::new(io::Error::from(io::ErrorKind::WouldBlock)));
         }
 
-        let message_hash = SendQueue::hash(data, nonce);
         let message_size = data.iter().fold(0, |a, v| a + v.len());
         let parts = data.len();
+        // Leave room for the sequence number `pull_safe` adds, so frames fit `max_frame_size`
+        // sent either way.
+        let max_data_size = self.opt.max_frame_size - frame::SEQUENCED_DATA_HEADER_SIZE;
 
         if parts > u8::MAX as usize {
             return Err("Message too long, exceeds 256 parts".into());
@@ -75,7 +182,7 @@ impl SendQueue {
 
             let mut chunk_offset: usize = 0;
 
-            part.chunks(frame::MAX_DATA_SIZE).for_each(|chunk| {
+            part.chunks(max_data_size).for_each(|chunk| {
                 let chunk_size = chunk.len();
 
                 let frame = DataFrame::encode(
@@ -102,6 +209,37 @@ impl SendQueue {
         Ok(())
     }
 
+    /// Queue a message to be moved whole, for transports that carry messages.
+    pub fn push_message(
+        &mut self,
+        session: u64,
+        data: &[&[u8]],
+        nonce: u64,
+    ) -> Result<(), Box<dyn Error>> {
+        self.push_message_with_id(session, data, SendQueue::hash(data, nonce))
+    }
+
+    /// `push_with_id` for transports that carry messages.
+    pub fn push_message_with_id(
+        &mut self,
+        session: u64,
+        data: &[&[u8]],
+        message_id: u64,
+    ) -> Result<(), Box<dyn Error>> {
+        if self.full() {
+            return Err(Box::new(io::Error::from(io::ErrorKind::WouldBlock)));
+        }
+
+        self.frames.push_back(QueueItem::Message(MessageFrame {
+            session_id: session,
+            message_id,
+            parts: data.iter().map(|part| part.to_vec()).collect(),
+        }));
+        self.message_count += 1;
+
+        Ok(())
+    }
+
     pub fn pull(&mut self) -> Option<Vec<u8>> {
         loop {
             let Some(m) = self.frames.pop_front() else {
@@ -113,27 +251,122 @@ impl SendQueue {
                 QueueItem::Marker => {
                     self.message_count -= 1;
                 }
+                QueueItem::Message(message) => {
+                    self.frames.push_front(QueueItem::Message(message));
+                    return None;
+                }
             }
         }
     }
 
-    pub fn pull_safe(&mut self) -> Option<Vec<u8>> {
+    pub fn pull_message(&mut self) -> Option<MessageFrame> {
+        let Some(QueueItem::Message(_)) = self.frames.front() else {
+            return None;
+        };
+
+        let Some(QueueItem::Message(message)) = self.frames.pop_front() else {
+            return None;
+        };
+
+        self.message_count -= 1;
+        Some(message)
+    }
+
+    /// Time to wait for an ack of something already resent `send_ct` times.
+    fn resend_timeout(&self, send_ct: usize) -> Duration {
+        if self.opt.adaptive_rto {
+            self.rtt.rto(send_ct)
+        } else {
+            self.opt.safe_resend_ivl
+        }
+    }
+
+    /// Pull a message, holding a copy until `confirm_safe` is called with its message id, and
+    /// resending it every `safe_resend_ivl`, or on the measured timeout under `adaptive_rto`,
+    /// until then.
+    pub fn pull_message_safe(&mut self) -> Option<MessageFrame> {
         let now = Instant::now();
 
-        while self.exp.len() > 0 && now.duration_since(self.exp[0].1) > self.opt.safe_resend_ivl {
-            if let Some((hash, .., send_ct)) = self.exp.pop_front() {
-                if send_ct >= self.opt.safe_resend_limit {
-                    self.sent.remove(&hash);
-                    continue;
+        while let Some((due, message_id, send_ct)) = self.exp.first().copied()
+            && due < now
+        {
+            self.exp.pop_first();
+            self.sent_at.remove(&message_id);
+
+            if send_ct >= self.opt.safe_resend_limit {
+                if self.sent_messages.remove(&message_id).is_some() {
+                    self.cwnd.on_loss(self.rtt.rto(0));
+                    self.mark_dropped(message_id);
                 }
+                continue;
+            }
+
+            if let Some(message) = self.sent_messages.get(&message_id).cloned() {
+                self.cwnd.on_loss(self.rtt.rto(0));
+                self.exp.insert((
+                    now + self.resend_timeout(send_ct + 1),
+                    message_id,
+                    send_ct + 1,
+                ));
+                return Some(message);
+            }
+        }
+
+        if self.window_full() {
+            return None;
+        }
+
+        let message = self.pull_message()?;
+
+        self.sent_messages
+            .insert(message.message_id, message.clone());
+        self.sent_at.insert(message.message_id, now);
+        self.exp
+            .insert((now + self.resend_timeout(0), message.message_id, 0));
 
-                if let Some(frame) = self.sent.get(&hash) {
-                    self.exp.push_back((hash, now, send_ct + 1));
-                    return Some(frame.clone());
+        Some(message)
+    }
+
+    pub fn pull_safe(&mut self) -> Option<Vec<u8>> {
+        while let Some(hash) = self.nacked.pop_front() {
+            if let Some(frame) = self.sent.get(&hash) {
+                self.sent_at.remove(&hash);
+                return Some(frame.clone());
+            }
+        }
+
+        let now = Instant::now();
+
+        while let Some((due, hash, send_ct)) = self.exp.first().copied()
+            && due < now
+        {
+            self.exp.pop_first();
+            self.sent_at.remove(&hash);
+
+            if send_ct >= self.opt.safe_resend_limit {
+                if let Some(frame) = self.sent.remove(&hash)
+                    && let Ok(Some(data_frame)) = DataFrame::parse(&frame)
+                {
+                    self.forget(&frame);
+                    if let Some(sequence) = data_frame.sequence {
+                        self.sequenced.remove(&sequence);
+                    }
+                    self.cwnd.on_loss(self.rtt.rto(0));
+                    self.mark_dropped(data_frame.message_id);
                 }
-            } else {
-                break;
+                continue;
             }
+
+            if let Some(frame) = self.sent.get(&hash).cloned() {
+                self.cwnd.on_loss(self.rtt.rto(0));
+                self.exp
+                    .insert((now + self.resend_timeout(send_ct + 1), hash, send_ct + 1));
+                return Some(frame);
+            }
+        }
+
+        if self.window_full() {
+            return None;
         }
 
         loop {
@@ -143,23 +376,160 @@ impl SendQueue {
 
             match m {
                 QueueItem::Frame(f) => {
+                    let f = DataFrame::sequence(&f, self.frame_sequence);
+
                     let mut hasher = Fnv1a64::new();
                     hasher.write(&f);
                     let hash = hasher.finish();
 
+                    self.sequenced.insert(self.frame_sequence, hash);
+                    self.frame_sequence += 1;
+
+                    if let Some((message_id, part_index, start, end)) = DataFrame::chunk_of(&f) {
+                        self.chunks
+                            .entry(message_id)
+                            .or_default()
+                            .insert((part_index, start), (end, hash));
+                    }
+
                     self.sent.insert(hash, f.clone());
-                    self.exp.push_back((hash, Instant::now(), 0));
+                    self.sent_at.insert(hash, now);
+                    self.exp.insert((now + self.resend_timeout(0), hash, 0));
 
                     return Some(f);
                 }
                 QueueItem::Marker => {
                     self.message_count -= 1;
                 }
+                QueueItem::Message(message) => {
+                    self.frames.push_front(QueueItem::Message(message));
+                    return None;
+                }
             }
         }
     }
 
+    /// Drop a frame no longer in flight from `chunks`.
+    fn forget(&mut self, frame: &[u8]) {
+        let Some((message_id, part_index, start, _)) = DataFrame::chunk_of(frame) else {
+            return;
+        };
+
+        if let Some(chunks) = self.chunks.get_mut(&message_id) {
+            chunks.remove(&(part_index, start));
+            if chunks.is_empty() {
+                self.chunks.remove(&message_id);
+            }
+        }
+    }
+
+    fn mark_dropped(&mut self, message_id: u64) {
+        if self.dropped.contains(&message_id) {
+            return;
+        }
+
+        if self.dropped.len() >= self.opt.send_hwm {
+            self.dropped.pop_front();
+        }
+        self.dropped.push_back(message_id);
+    }
+
+    /// Take the ids of messages with a frame dropped since the last call.
+    pub fn take_dropped(&mut self) -> Vec<u64> {
+        self.dropped.drain(..).collect()
+    }
+
+    /// Confirm delivery of a frame by the hash of its encoding, or of a whole message by its id.
     pub fn confirm_safe(&mut self, hash: u64) {
-        self.sent.remove(&hash);
+        let frame = self.sent.remove(&hash);
+        if let Some(frame) = frame.as_deref() {
+            self.forget(frame);
+        }
+        if let Some(sequence) = frame.as_deref().and_then(DataFrame::sequence_of) {
+            self.sequenced.remove(&sequence);
+        }
+
+        if frame.is_some() || self.sent_messages.remove(&hash).is_some() {
+            self.cwnd.on_ack(1);
+        }
+
+        if let Some(sent_at) = self.sent_at.remove(&hash) {
+            self.rtt.sample(sent_at.elapsed());
+        }
+    }
+
+    /// Queue the frames of a message still in flight that overlap the `(part_index, start, end)`
+    /// ranges a receiver is missing, to go out on the next `pull_safe`. Returns the number of
+    /// frames queued.
+    pub fn nack(&mut self, message_id: u64, ranges: &[(u8, u32, u32)]) -> usize {
+        let Some(chunks) = self.chunks.get(&message_id) else {
+            return 0;
+        };
+
+        let mut ct = 0;
+
+        for ((part_index, start), (end, hash)) in chunks.iter() {
+            let missing = ranges
+                .iter()
+                .any(|(index, from, to)| index == part_index && start < to && end > from);
+
+            if missing && !self.nacked.contains(hash) {
+                self.nacked.push_back(*hash);
+                ct += 1;
+            }
+        }
+
+        if ct > 0 {
+            self.cwnd.on_loss(self.rtt.rto(0));
+        }
+
+        ct
+    }
+
+    /// Confirm delivery of every frame below `cumulative` and of those in `ranges`, by sequence
+    /// number. Returns the number of frames newly confirmed.
+    pub fn confirm_sack(&mut self, cumulative: u64, ranges: &[(u64, u64)]) -> usize {
+        let above = self.sequenced.split_off(&cumulative);
+        let mut confirmed = std::mem::replace(&mut self.sequenced, above)
+            .into_values()
+            .collect::<Vec<u64>>();
+
+        for (start, end) in ranges.iter() {
+            if start >= end {
+                continue;
+            }
+
+            let in_range = self
+                .sequenced
+                .range(start..end)
+                .map(|(sequence, _)| *sequence)
+                .collect::<Vec<u64>>();
+
+            for sequence in in_range {
+                if let Some(hash) = self.sequenced.remove(&sequence) {
+                    confirmed.push(hash);
+                }
+            }
+        }
+
+        // One sample per ack, from the latest frame it covers sent only once.
+        if let Some(sent_at) = confirmed
+            .iter()
+            .filter_map(|hash| self.sent_at.remove(hash))
+            .max()
+        {
+            self.rtt.sample(sent_at.elapsed());
+        }
+
+        let mut ct = 0;
+        for hash in confirmed.iter() {
+            if let Some(frame) = self.sent.remove(hash) {
+                self.forget(&frame);
+                ct += 1;
+            }
+        }
+        self.cwnd.on_ack(ct);
+
+        ct
     }
 }
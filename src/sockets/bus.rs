@@ -0,0 +1,206 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    io,
+};
+
+use crate::{
+    core::{AsSocket, Core, SockOpt},
+    frame::Frame,
+    queue::{RecvQueue, SendQueue},
+    transport::Transport,
+};
+
+/// Many-to-many messaging. Every message sent goes to each directly connected peer, and a Bus
+/// receives from all of them. Messages are not forwarded, so a mesh has to be fully connected
+/// for everyone to hear everyone.
+pub struct Bus {
+    core: Core,
+    opt: SockOpt,
+
+    unique: u64,
+    peers: Vec<u64>,
+    peer_set: HashSet<u64>,
+
+    send_queues: HashMap<u64, SendQueue>,
+    recv_queue: RecvQueue,
+}
+
+impl Bus {
+    fn new_from(core: Core, opt: SockOpt) -> Self {
+        Self {
+            core,
+            opt: opt.clone(),
+
+            unique: 0,
+            peers: Vec::new(),
+            peer_set: HashSet::new(),
+
+            send_queues: HashMap::new(),
+            recv_queue: RecvQueue::new(opt),
+        }
+    }
+
+    fn check_peer_update(&mut self) {
+        if let Some(peer_update) = self.core.update_peers() {
+            self.peers = peer_update;
+            self.peer_set = HashSet::new();
+            self.peer_set.extend(self.peers.iter());
+
+            self.send_queues.retain(|k, _| self.peer_set.contains(k));
+        }
+    }
+
+    /// Connect to one more Bus, keeping every connection already held. Each pair of nodes
+    /// should be connected once, in one direction, or messages between them arrive twice.
+    pub fn connect_to(&mut self, addr: &str) -> Result<(), Box<dyn Error>> {
+        let target = self.core.target(addr)?;
+        self.core.add_connection(&target)
+    }
+}
+
+impl AsSocket for Bus {
+    type Output = Bus;
+
+    fn bind(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Bus::new_from(Core::bind(addr, opt.clone())?, opt))
+    }
+
+    /// Connect from an ephemeral address that stays reachable, so the Bus can take more
+    /// connections with `connect_to`.
+    fn connect(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Bus::new_from(Core::connect_multi(addr, opt.clone())?, opt))
+    }
+
+    fn bind_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Bus::new_from(
+            Core::bind_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
+    fn connect_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Bus::new_from(
+            Core::connect_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
+    /// Queue a message to every peer.
+    fn send(&mut self, data: &[&[u8]]) -> Result<(), Box<dyn Error>> {
+        self.check_peer_update();
+
+        if self.peers.is_empty() {
+            return Err("No peer".into());
+        }
+
+        // Queue to every peer or to none, so one peer at `send_hwm` doesn't leave the message
+        // half sent.
+        if self.peers.iter().any(|session_id| {
+            self.send_queues
+                .get(session_id)
+                .is_some_and(SendQueue::full)
+        }) {
+            return Err(Box::new(io::Error::from(io::ErrorKind::WouldBlock)));
+        }
+
+        for session_id in self.peers.iter() {
+            let send_queue = self
+                .send_queues
+                .entry(*session_id)
+                .or_insert(SendQueue::new(self.opt.clone()));
+
+            if self.core.carries_messages() {
+                send_queue.push_message(*session_id, data, self.unique)?;
+            } else {
+                send_queue.push(*session_id, data, self.unique)?;
+            }
+            self.unique = self.unique.wrapping_add(1);
+        }
+
+        Ok(())
+    }
+
+    fn recv(&mut self) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+        if let Some((message, ..)) = self.recv_queue.pull() {
+            return Ok(message);
+        }
+
+        Err(Box::new(io::Error::from(io::ErrorKind::WouldBlock)))
+    }
+
+    fn tick(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut recv_error: Option<Box<dyn Error>> = None;
+
+        while let Ok(frame) = self.core.recv() {
+            let pushed = match frame {
+                Frame::DataFrame(data_frame) => self.recv_queue.push(&data_frame),
+                Frame::MessageFrame(message) => self.recv_queue.push_message(message),
+                _ => continue,
+            };
+
+            if let Err(e) = pushed {
+                recv_error = Some(e);
+            }
+        }
+
+        self.check_peer_update();
+
+        let n_per = if !self.send_queues.is_empty() {
+            self.opt.max_tick_send / self.send_queues.len()
+        } else {
+            0
+        };
+
+        for (session_id, send_queue) in self.send_queues.iter_mut() {
+            let mut ct = 0;
+
+            while let Some(frame) = send_queue.pull() {
+                if self.core.send_peer(&frame, session_id).is_err() {
+                    break;
+                }
+
+                ct += 1;
+                if ct > n_per {
+                    break;
+                }
+            }
+
+            while let Some(message) = send_queue.pull_message() {
+                if self.core.send_message(message, session_id).is_err() {
+                    break;
+                }
+
+                ct += 1;
+                if ct > n_per {
+                    break;
+                }
+            }
+        }
+
+        if let Some(err) = recv_error {
+            return Err(err);
+        }
+
+        self.core.maint()?;
+        self.check_peer_update();
+
+        Ok(())
+    }
+
+    fn opt(&mut self) -> &mut SockOpt {
+        &mut self.opt
+    }
+
+    fn peers(&self) -> usize {
+        self.peers.len()
+    }
+}
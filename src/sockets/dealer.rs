@@ -8,6 +8,7 @@ use crate::{
     core::{AsSocket, Core, SockOpt},
     frame::Frame,
     queue::{RecvQueue, SendQueue},
+    transport::Transport,
 };
 
 pub struct Dealer {
@@ -69,6 +70,28 @@ impl AsSocket for Dealer {
         Ok(Dealer::new_from(Core::connect(addr, opt.clone())?, opt))
     }
 
+    fn bind_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Dealer::new_from(
+            Core::bind_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
+    fn connect_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Dealer::new_from(
+            Core::connect_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
     fn send(&mut self, data: &[&[u8]]) -> Result<(), Box<dyn Error>> {
         self.check_peer_update();
         self.unique = self.unique.wrapping_add(1);
@@ -79,7 +102,11 @@ impl AsSocket for Dealer {
             .entry(peer)
             .or_insert(SendQueue::new(self.opt.clone()));
 
-        send_queue.push(peer, data, self.unique)?;
+        if self.core.carries_messages() {
+            send_queue.push_message(peer, data, self.unique)?;
+        } else {
+            send_queue.push(peer, data, self.unique)?;
+        }
 
         Ok(())
     }
@@ -96,11 +123,13 @@ impl AsSocket for Dealer {
         let mut recv_error: Option<Box<dyn Error>> = None;
 
         while let Ok(frame) = self.core.recv() {
-            let Frame::DataFrame(data_frame) = frame else {
-                continue;
+            let pushed = match frame {
+                Frame::DataFrame(data_frame) => self.recv_queue.push(&data_frame),
+                Frame::MessageFrame(message) => self.recv_queue.push_message(message),
+                _ => continue,
             };
 
-            if let Err(e) = self.recv_queue.push(&data_frame) {
+            if let Err(e) = pushed {
                 recv_error = Some(e);
             }
         }
@@ -126,6 +155,17 @@ impl AsSocket for Dealer {
                     }
                 }
             }
+
+            while let Some(message) = send_queue.pull_message() {
+                if self.core.send_message(message, session_id).is_err() {
+                    break;
+                } else {
+                    ct += 1;
+                    if ct > n_per {
+                        break;
+                    }
+                }
+            }
         }
 
         if let Some(err) = recv_error {
@@ -1,26 +1,56 @@
 use std::{error::Error, io};
 
+use super::membership::Membership;
 use crate::{
     core::{AsSocket, Core, SockOpt},
-    frame::Frame,
+    frame::{ControlFrame, Frame},
     queue::RecvQueue,
+    transport::Transport,
 };
 
 pub struct Dish {
     core: Core,
     opt: SockOpt,
 
+    groups: Membership,
+
     recv_queue: RecvQueue,
 }
 
 impl Dish {
-    fn new_from(core: Core, opt: SockOpt) -> Self {
-        Self {
+    fn new_from(mut core: Core, opt: SockOpt) -> Result<Self, Box<dyn Error>> {
+        if opt.multicast_group.is_some() {
+            core.join_multicast()?;
+        }
+
+        Ok(Self {
             core,
             opt: opt.clone(),
 
+            groups: Membership::new(&opt, ControlFrame::Join, ControlFrame::Leave),
+
             recv_queue: RecvQueue::new(opt),
+        })
+    }
+
+    /// Receive messages the Radio sends to `group` with `send_group`. Messages sent with plain
+    /// `send` are received regardless of membership.
+    pub fn join(&mut self, group: &[u8]) -> Result<(), Box<dyn Error>> {
+        if group.len() > u16::MAX as usize {
+            return Err("Group too long, exceeds 65535 bytes".into());
         }
+
+        self.groups.insert(&mut self.core, group);
+
+        Ok(())
+    }
+
+    /// Stop receiving messages for a group passed to `join`. Messages already in flight may
+    /// still arrive.
+    pub fn leave(&mut self, group: &[u8]) -> Result<(), Box<dyn Error>> {
+        self.groups.remove(&mut self.core, group);
+
+        Ok(())
     }
 }
 
@@ -28,11 +58,27 @@ impl AsSocket for Dish {
     type Output = Dish;
 
     fn bind(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
-        Ok(Dish::new_from(Core::bind(addr, opt.clone())?, opt))
+        Dish::new_from(Core::bind(addr, opt.clone())?, opt)
     }
 
     fn connect(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
-        Ok(Dish::new_from(Core::connect(addr, opt.clone())?, opt))
+        Dish::new_from(Core::connect(addr, opt.clone())?, opt)
+    }
+
+    fn bind_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Dish::new_from(Core::bind_with(transport, addr, opt.clone())?, opt)
+    }
+
+    fn connect_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Dish::new_from(Core::connect_with(transport, addr, opt.clone())?, opt)
     }
 
     fn send(&mut self, _data: &[&[u8]]) -> Result<(), Box<dyn Error>> {
@@ -51,20 +97,26 @@ impl AsSocket for Dish {
         let mut recv_error: Option<Box<dyn Error>> = None;
 
         while let Ok(frame) = self.core.recv() {
-            let Frame::DataFrame(data_frame) = frame else {
-                continue;
+            let pushed = match frame {
+                Frame::DataFrame(data_frame) => self.recv_queue.push(&data_frame),
+                Frame::MessageFrame(message) => self.recv_queue.push_message(message),
+                _ => continue,
             };
 
-            if let Err(e) = self.recv_queue.push(&data_frame) {
+            if let Err(e) = pushed {
                 recv_error = Some(e);
             }
         }
 
+        self.groups.check_peer_update(&mut self.core);
+        self.groups.maint(&mut self.core);
+
         if let Some(e) = recv_error {
             return Err(e);
         }
 
         self.core.maint()?;
+        self.groups.check_peer_update(&mut self.core);
 
         return Ok(());
     }
@@ -0,0 +1,104 @@
+use std::{
+    collections::HashSet,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    core::{Core, SockOpt},
+    frame::{self, ControlFrame},
+};
+
+/// Encodes a batch of names for a session as the control frame announcing them.
+type Announce = fn((u64, Vec<Vec<u8>>)) -> ControlFrame;
+
+/// Names a receiving socket holds on every peer, the topics of a `Sub` or the groups of a
+/// `Dish`. The whole set is resent on new peers and every `peer_heartbeat_ivl`, so the far side
+/// recovers from lost control frames.
+pub(crate) struct Membership {
+    peers: Vec<u64>,
+
+    names: HashSet<Vec<u8>>,
+    /// Names dropped since the last refresh, repeated once with it in case the drop was lost.
+    dropped: HashSet<Vec<u8>>,
+    last_refresh: Instant,
+
+    add: Announce,
+    drop: Announce,
+
+    max_frame_size: usize,
+    refresh_ivl: Duration,
+}
+
+impl Membership {
+    pub fn new(opt: &SockOpt, add: Announce, drop: Announce) -> Self {
+        Self {
+            peers: Vec::new(),
+
+            names: HashSet::new(),
+            dropped: HashSet::new(),
+            last_refresh: Instant::now(),
+
+            add,
+            drop,
+
+            max_frame_size: opt.max_frame_size,
+            refresh_ivl: opt.peer_heartbeat_ivl,
+        }
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &Vec<u8>> {
+        self.names.iter()
+    }
+
+    pub fn insert(&mut self, core: &mut Core, name: &[u8]) {
+        self.names.insert(name.to_vec());
+        self.dropped.remove(name);
+        self.announce(core, self.add, &[name.to_vec()]);
+    }
+
+    pub fn remove(&mut self, core: &mut Core, name: &[u8]) {
+        if !self.names.remove(name) {
+            return;
+        }
+
+        self.dropped.insert(name.to_vec());
+        self.announce(core, self.drop, &[name.to_vec()]);
+    }
+
+    /// Send a list of names to every peer, batched so each control frame fits in a frame.
+    fn announce(&self, core: &mut Core, announce: Announce, names: &[Vec<u8>]) {
+        let batches = frame::batch_list(names, self.max_frame_size - frame::CONTROL_HEADER_SIZE);
+
+        for session_id in self.peers.iter() {
+            for batch in batches.iter() {
+                let control_frame = announce((*session_id, batch.clone()));
+                let _ = core.send_peer(&control_frame.encode(), session_id);
+            }
+        }
+    }
+
+    fn refresh(&mut self, core: &mut Core) {
+        let names = self.names.iter().cloned().collect::<Vec<Vec<u8>>>();
+        self.announce(core, self.add, &names);
+
+        let dropped = self.dropped.drain().collect::<Vec<Vec<u8>>>();
+        self.announce(core, self.drop, &dropped);
+
+        self.last_refresh = Instant::now();
+    }
+
+    /// Take up new peers, announcing the whole set to them.
+    pub fn check_peer_update(&mut self, core: &mut Core) {
+        if let Some(peer_update) = core.update_peers() {
+            self.peers = peer_update;
+            self.refresh(core);
+        }
+    }
+
+    /// Resend the whole set once `peer_heartbeat_ivl` has passed since the last time.
+    pub fn maint(&mut self, core: &mut Core) {
+        if Instant::now().duration_since(self.last_refresh) > self.refresh_ivl {
+            self.refresh(core);
+        }
+    }
+}
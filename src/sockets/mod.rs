@@ -1,9 +1,36 @@
+mod bus;
 mod dealer;
 mod dish;
+mod membership;
+mod pair;
+mod publisher;
+mod pull;
+mod push;
 mod radio;
+mod rep;
+mod req;
+mod respondent;
+mod router;
 mod safe_dealer;
+mod safe_dish;
+mod safe_radio;
+mod subscriber;
+mod surveyor;
 
+pub use bus::Bus;
 pub use dealer::Dealer;
 pub use dish::Dish;
+pub use pair::Pair;
+pub use publisher::Pub;
+pub use pull::Pull;
+pub use push::Push;
 pub use radio::Radio;
+pub use rep::Rep;
+pub use req::Req;
+pub use respondent::Respondent;
+pub use router::Router;
 pub use safe_dealer::SafeDealer;
+pub use safe_dish::SafeDish;
+pub use safe_radio::SafeRadio;
+pub use subscriber::Sub;
+pub use surveyor::Surveyor;
@@ -0,0 +1,169 @@
+use std::{error::Error, io};
+
+use crate::{
+    core::{AsSocket, Core, SockOpt},
+    frame::Frame,
+    queue::{RecvQueue, SendQueue},
+    transport::Transport,
+};
+
+pub struct Pair {
+    core: Core,
+    opt: SockOpt,
+
+    unique: u64,
+    peer: Option<u64>,
+
+    send_queue: SendQueue,
+    recv_queue: RecvQueue,
+}
+
+impl Pair {
+    fn new_from(mut core: Core, opt: SockOpt) -> Self {
+        core.set_exclusive(true);
+
+        Self {
+            core,
+            opt: opt.clone(),
+
+            unique: 0,
+            peer: None,
+
+            send_queue: SendQueue::new(opt.clone()),
+            recv_queue: RecvQueue::new(opt),
+        }
+    }
+
+    fn check_peer_update(&mut self) {
+        if let Some(peer_update) = self.core.update_peers() {
+            let peer = peer_update.first().copied();
+
+            // Frames already queued carry the old session, a replacement starts clean.
+            if peer != self.peer {
+                self.send_queue = SendQueue::new(self.opt.clone());
+            }
+
+            self.peer = peer;
+        }
+    }
+}
+
+impl AsSocket for Pair {
+    type Output = Pair;
+
+    fn bind(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Pair::new_from(Core::bind(addr, opt.clone())?, opt))
+    }
+
+    fn connect(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Pair::new_from(Core::connect(addr, opt.clone())?, opt))
+    }
+
+    fn bind_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Pair::new_from(
+            Core::bind_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
+    fn connect_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Pair::new_from(
+            Core::connect_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
+    fn send(&mut self, data: &[&[u8]]) -> Result<(), Box<dyn Error>> {
+        self.check_peer_update();
+
+        let Some(peer) = self.peer else {
+            return Err("No peer".into());
+        };
+
+        if self.core.carries_messages() {
+            self.send_queue.push_message(peer, data, self.unique)?;
+        } else {
+            self.send_queue.push(peer, data, self.unique)?;
+        }
+        self.unique = self.unique.wrapping_add(1);
+
+        Ok(())
+    }
+
+    fn recv(&mut self) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+        if let Some((message, ..)) = self.recv_queue.pull() {
+            return Ok(message);
+        }
+
+        Err(Box::new(io::Error::from(io::ErrorKind::WouldBlock)))
+    }
+
+    fn tick(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut recv_error: Option<Box<dyn Error>> = None;
+
+        while let Ok(frame) = self.core.recv() {
+            let pushed = match frame {
+                Frame::DataFrame(data_frame) => self.recv_queue.push(&data_frame),
+                Frame::MessageFrame(message) => self.recv_queue.push_message(message),
+                _ => continue,
+            };
+
+            if let Err(e) = pushed {
+                recv_error = Some(e);
+            }
+        }
+
+        self.check_peer_update();
+
+        if let Some(session_id) = self.peer {
+            let mut ct = 0;
+
+            while let Some(frame) = self.send_queue.pull() {
+                if self.core.send_peer(&frame, &session_id).is_err() {
+                    break;
+                }
+
+                ct += 1;
+                if ct > self.opt.max_tick_send {
+                    break;
+                }
+            }
+
+            while let Some(message) = self.send_queue.pull_message() {
+                if self.core.send_message(message, &session_id).is_err() {
+                    break;
+                }
+
+                ct += 1;
+                if ct > self.opt.max_tick_send {
+                    break;
+                }
+            }
+        }
+
+        if let Some(err) = recv_error {
+            return Err(err);
+        }
+
+        self.core.maint()?;
+        self.check_peer_update();
+
+        Ok(())
+    }
+
+    fn opt(&mut self) -> &mut SockOpt {
+        &mut self.opt
+    }
+
+    fn peers(&self) -> usize {
+        self.core.peers.len()
+    }
+}
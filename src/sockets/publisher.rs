@@ -0,0 +1,206 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    io,
+};
+
+use crate::{
+    core::{AsSocket, Core, SockOpt},
+    frame::{ControlFrame, Frame},
+    queue::SendQueue,
+    transport::Transport,
+};
+
+pub struct Pub {
+    core: Core,
+    opt: SockOpt,
+
+    unique: u64,
+    peers: Vec<u64>,
+    peer_set: HashSet<u64>,
+
+    /// Topic prefixes each subscriber session has asked for.
+    subscriptions: HashMap<u64, HashSet<Vec<u8>>>,
+    send_queues: HashMap<u64, SendQueue>,
+}
+
+impl Pub {
+    fn new_from(core: Core, opt: SockOpt) -> Self {
+        Self {
+            core,
+            opt: opt.clone(),
+
+            unique: 0,
+            peers: Vec::new(),
+            peer_set: HashSet::new(),
+
+            subscriptions: HashMap::new(),
+            send_queues: HashMap::new(),
+        }
+    }
+
+    fn check_peer_update(&mut self) {
+        if let Some(peer_update) = self.core.update_peers() {
+            self.peers = peer_update;
+            self.peer_set = HashSet::new();
+            self.peer_set.extend(self.peers.iter());
+
+            self.send_queues.retain(|k, _| self.peer_set.contains(k));
+            self.subscriptions.retain(|k, _| self.peer_set.contains(k));
+        }
+    }
+
+    fn wants(topics: &HashSet<Vec<u8>>, topic: &[u8]) -> bool {
+        topics.iter().any(|prefix| topic.starts_with(prefix))
+    }
+}
+
+impl AsSocket for Pub {
+    type Output = Pub;
+
+    fn bind(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Pub::new_from(Core::bind(addr, opt.clone())?, opt))
+    }
+
+    fn connect(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Pub::new_from(Core::connect(addr, opt.clone())?, opt))
+    }
+
+    fn bind_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Pub::new_from(
+            Core::bind_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
+    fn connect_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Pub::new_from(
+            Core::connect_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
+    /// Queue a message for every subscriber with a topic prefix matching its first part.
+    fn send(&mut self, data: &[&[u8]]) -> Result<(), Box<dyn Error>> {
+        let topic = data.first().copied().unwrap_or_default();
+
+        // Every subscriber to the topic gets the message or none does.
+        if self.subscriptions.iter().any(|(session_id, topics)| {
+            Pub::wants(topics, topic)
+                && self
+                    .send_queues
+                    .get(session_id)
+                    .is_some_and(SendQueue::full)
+        }) {
+            return Err(Box::new(io::Error::from(io::ErrorKind::WouldBlock)));
+        }
+
+        for (session_id, topics) in self.subscriptions.iter() {
+            if !Pub::wants(topics, topic) {
+                continue;
+            }
+
+            let send_queue = self
+                .send_queues
+                .entry(*session_id)
+                .or_insert(SendQueue::new(self.opt.clone()));
+
+            if self.core.carries_messages() {
+                send_queue.push_message(*session_id, data, self.unique)?;
+            } else {
+                send_queue.push(*session_id, data, self.unique)?;
+            }
+            self.unique = self.unique.wrapping_add(1);
+        }
+
+        Ok(())
+    }
+
+    fn recv(&mut self) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+        Err("recv not available on Pub socket".into())
+    }
+
+    fn tick(&mut self) -> Result<(), Box<dyn Error>> {
+        self.check_peer_update();
+
+        while let Ok(frame) = self.core.recv() {
+            let Frame::ControlFrame(control_frame) = frame else {
+                continue;
+            };
+
+            match control_frame {
+                ControlFrame::Subscribe((session_id, topics)) => {
+                    if !self.core.peers.contains_key(&session_id) {
+                        continue;
+                    }
+
+                    self.subscriptions
+                        .entry(session_id)
+                        .or_default()
+                        .extend(topics);
+                }
+                ControlFrame::Unsubscribe((session_id, topics)) => {
+                    if let Some(subscribed) = self.subscriptions.get_mut(&session_id) {
+                        topics.iter().for_each(|topic| {
+                            subscribed.remove(topic);
+                        });
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        let n_per = if !self.send_queues.is_empty() {
+            self.opt.max_tick_send / self.send_queues.len()
+        } else {
+            0
+        };
+
+        for (session_id, send_queue) in self.send_queues.iter_mut() {
+            let mut ct = 0;
+
+            while let Some(frame) = send_queue.pull() {
+                if self.core.send_peer(&frame, session_id).is_err() {
+                    break;
+                }
+
+                ct += 1;
+                if ct > n_per {
+                    break;
+                }
+            }
+
+            while let Some(message) = send_queue.pull_message() {
+                if self.core.send_message(message, session_id).is_err() {
+                    break;
+                }
+
+                ct += 1;
+                if ct > n_per {
+                    break;
+                }
+            }
+        }
+
+        self.core.maint()?;
+        self.check_peer_update();
+
+        Ok(())
+    }
+
+    fn opt(&mut self) -> &mut SockOpt {
+        &mut self.opt
+    }
+
+    fn peers(&self) -> usize {
+        self.core.peers.len()
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    error::Error,
+    io,
+};
+
+use crate::{
+    core::{AsSocket, Core, SockOpt},
+    frame::Frame,
+    queue::RecvQueue,
+    transport::Transport,
+};
+
+pub struct Pull {
+    core: Core,
+    opt: SockOpt,
+
+    recv_queue: RecvQueue,
+
+    /// Completed messages per sending session, drained in turn so a busy pusher can't starve
+    /// the others.
+    inbox: HashMap<u64, VecDeque<Vec<Vec<u8>>>>,
+    turns: VecDeque<u64>,
+    buffered: usize,
+}
+
+impl Pull {
+    fn new_from(core: Core, opt: SockOpt) -> Self {
+        Self {
+            core,
+            opt: opt.clone(),
+
+            recv_queue: RecvQueue::new(opt),
+
+            inbox: HashMap::new(),
+            turns: VecDeque::new(),
+            buffered: 0,
+        }
+    }
+
+    /// Move completed messages out of the receive queue into the per session inbox, up to
+    /// `recv_hwm` messages.
+    fn fill(&mut self) {
+        while self.buffered < self.opt.recv_hwm {
+            let Some((message, (session_id, _))) = self.recv_queue.pull() else {
+                break;
+            };
+
+            let inbox = self.inbox.entry(session_id).or_default();
+            if inbox.is_empty() {
+                self.turns.push_back(session_id);
+            }
+
+            inbox.push_back(message);
+            self.buffered += 1;
+        }
+    }
+}
+
+impl AsSocket for Pull {
+    type Output = Pull;
+
+    fn bind(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Pull::new_from(Core::bind(addr, opt.clone())?, opt))
+    }
+
+    fn connect(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Pull::new_from(Core::connect(addr, opt.clone())?, opt))
+    }
+
+    fn bind_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Pull::new_from(
+            Core::bind_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
+    fn connect_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Pull::new_from(
+            Core::connect_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
+    fn send(&mut self, _data: &[&[u8]]) -> Result<(), Box<dyn Error>> {
+        Err("send not available on Pull socket".into())
+    }
+
+    /// Receive the next message, taking one from each sending peer in turn.
+    fn recv(&mut self) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+        self.fill();
+
+        while let Some(session_id) = self.turns.pop_front() {
+            let Some(inbox) = self.inbox.get_mut(&session_id) else {
+                continue;
+            };
+
+            let Some(message) = inbox.pop_front() else {
+                continue;
+            };
+
+            if inbox.is_empty() {
+                self.inbox.remove(&session_id);
+            } else {
+                self.turns.push_back(session_id);
+            }
+
+            self.buffered -= 1;
+            return Ok(message);
+        }
+
+        Err(Box::new(io::Error::from(io::ErrorKind::WouldBlock)))
+    }
+
+    fn tick(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut recv_error: Option<Box<dyn Error>> = None;
+
+        while let Ok(frame) = self.core.recv() {
+            let pushed = match frame {
+                Frame::DataFrame(data_frame) => self.recv_queue.push(&data_frame),
+                Frame::MessageFrame(message) => self.recv_queue.push_message(message),
+                _ => continue,
+            };
+
+            if let Err(e) = pushed {
+                recv_error = Some(e);
+            }
+        }
+
+        self.fill();
+
+        if let Some(e) = recv_error {
+            return Err(e);
+        }
+
+        self.core.maint()?;
+
+        Ok(())
+    }
+
+    fn opt(&mut self) -> &mut SockOpt {
+        &mut self.opt
+    }
+
+    fn peers(&self) -> usize {
+        self.core.peers.len()
+    }
+}
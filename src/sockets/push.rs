@@ -0,0 +1,181 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+};
+
+use crate::{
+    core::{AsSocket, Core, SockOpt},
+    queue::SendQueue,
+    transport::Transport,
+};
+
+pub struct Push {
+    core: Core,
+    opt: SockOpt,
+
+    unique: u64,
+    peers: Vec<u64>,
+    peer_set: HashSet<u64>,
+
+    send_queues: HashMap<u64, SendQueue>,
+}
+
+impl Push {
+    fn new_from(core: Core, opt: SockOpt) -> Self {
+        Self {
+            core,
+            opt: opt.clone(),
+
+            unique: 0,
+            peers: vec![],
+            peer_set: HashSet::new(),
+
+            send_queues: HashMap::new(),
+        }
+    }
+
+    fn check_peer_update(&mut self) {
+        if let Some(peer_update) = self.core.update_peers() {
+            self.peers = peer_update;
+            self.peer_set = HashSet::new();
+            self.peer_set.extend(self.peers.iter());
+
+            self.send_queues.retain(|k, _| self.peer_set.contains(k));
+        }
+    }
+
+    /// The peer with the fewest frames waiting in its send queue. Push gets no acks, so that is
+    /// all the load it can see: frames queued since the last tick, or held back by
+    /// `max_tick_send` or a link refusing more, as TCP to a slow worker does. Queues empty every
+    /// tick otherwise, and ties go round robin, so most of the time this is plain round robin.
+    fn select_shallowest_peer(&self) -> Result<u64, Box<dyn Error>> {
+        let peer_ct = self.peers.len();
+
+        if peer_ct < 1 {
+            return Err("No peer".into());
+        }
+
+        let start = self.unique as usize % peer_ct;
+
+        (0..peer_ct)
+            .map(|i| self.peers[(start + i) % peer_ct])
+            .min_by_key(|session_id| {
+                self.send_queues
+                    .get(session_id)
+                    .map(|send_queue| send_queue.len())
+                    .unwrap_or(0)
+            })
+            .ok_or("No peer".into())
+    }
+}
+
+impl AsSocket for Push {
+    type Output = Push;
+
+    fn bind(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Push::new_from(Core::bind(addr, opt.clone())?, opt))
+    }
+
+    fn connect(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Push::new_from(Core::connect(addr, opt.clone())?, opt))
+    }
+
+    fn bind_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Push::new_from(
+            Core::bind_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
+    fn connect_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Push::new_from(
+            Core::connect_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
+    /// Queue a message to the peer with the shallowest send queue, see `select_shallowest_peer`.
+    fn send(&mut self, data: &[&[u8]]) -> Result<(), Box<dyn Error>> {
+        self.check_peer_update();
+        self.unique = self.unique.wrapping_add(1);
+
+        let peer = self.select_shallowest_peer()?;
+        let send_queue = self
+            .send_queues
+            .entry(peer)
+            .or_insert(SendQueue::new(self.opt.clone()));
+
+        if self.core.carries_messages() {
+            send_queue.push_message(peer, data, self.unique)?;
+        } else {
+            send_queue.push(peer, data, self.unique)?;
+        }
+
+        Ok(())
+    }
+
+    fn recv(&mut self) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+        Err("recv not available on Push socket".into())
+    }
+
+    fn tick(&mut self) -> Result<(), Box<dyn Error>> {
+        while self.core.recv().is_ok() {
+            continue;
+        }
+
+        self.check_peer_update();
+
+        let n_per = if !self.send_queues.is_empty() {
+            self.opt.max_tick_send / self.send_queues.len()
+        } else {
+            0
+        };
+
+        for (session_id, send_queue) in self.send_queues.iter_mut() {
+            let mut ct = 0;
+
+            while let Some(frame) = send_queue.pull() {
+                if self.core.send_peer(&frame, session_id).is_err() {
+                    break;
+                }
+
+                ct += 1;
+                if ct > n_per {
+                    break;
+                }
+            }
+
+            while let Some(message) = send_queue.pull_message() {
+                if self.core.send_message(message, session_id).is_err() {
+                    break;
+                }
+
+                ct += 1;
+                if ct > n_per {
+                    break;
+                }
+            }
+        }
+
+        self.core.maint()?;
+        self.check_peer_update();
+
+        Ok(())
+    }
+
+    fn opt(&mut self) -> &mut SockOpt {
+        &mut self.opt
+    }
+
+    fn peers(&self) -> usize {
+        self.peers.len()
+    }
+}
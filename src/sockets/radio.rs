@@ -5,7 +5,9 @@ use std::{
 
 use crate::{
     core::{AsSocket, Core, SockOpt},
+    frame::{ControlFrame, Frame},
     queue::SendQueue,
+    transport::Transport,
 };
 
 pub struct Radio {
@@ -16,12 +18,25 @@ pub struct Radio {
     peers: Vec<u64>,
     peer_set: HashSet<u64>,
 
+    /// Groups each Dish session has joined.
+    groups: HashMap<u64, HashSet<Vec<u8>>>,
     send_queues: HashMap<u64, SendQueue>,
+
+    /// In multicast mode, the single queue feeding the group in place of the per-peer queues.
+    multicast_queue: Option<SendQueue>,
 }
 
 impl Radio {
-    fn new_from(core: Core, opt: SockOpt) -> Self {
-        Self {
+    fn new_from(mut core: Core, opt: SockOpt) -> Result<Self, Box<dyn Error>> {
+        let multicast_queue = match opt.multicast_group {
+            Some(_) => {
+                core.publish_multicast()?;
+                Some(SendQueue::new(opt.clone()))
+            }
+            None => None,
+        };
+
+        Ok(Self {
             core,
             opt: opt.clone(),
 
@@ -29,8 +44,10 @@ impl Radio {
             peers: Vec::new(),
             peer_set: HashSet::new(),
 
+            groups: HashMap::new(),
             send_queues: HashMap::new(),
-        }
+            multicast_queue,
+        })
     }
 
     fn check_peer_update(&mut self) {
@@ -40,30 +57,96 @@ impl Radio {
             self.peer_set.extend(self.peers.iter());
 
             self.send_queues.retain(|k, _| self.peer_set.contains(k));
+            self.groups.retain(|k, _| self.peer_set.contains(k));
         }
     }
+
+    fn queue(&mut self, session_id: u64, data: &[&[u8]]) -> Result<(), Box<dyn Error>> {
+        let send_queue = self
+            .send_queues
+            .entry(session_id)
+            .or_insert(SendQueue::new(self.opt.clone()));
+
+        if self.core.carries_messages() {
+            send_queue.push_message(session_id, data, self.unique)?;
+        } else {
+            send_queue.push(session_id, data, self.unique)?;
+        }
+        self.unique = self.unique.wrapping_add(1);
+
+        Ok(())
+    }
+
+    /// Queue a message only for the peers that joined `group`. The group is delivered as the
+    /// message's first part. Not available in multicast mode, where every Dish receives the same
+    /// frame stream.
+    pub fn send_group(&mut self, group: &[u8], data: &[&[u8]]) -> Result<(), Box<dyn Error>> {
+        if self.multicast_queue.is_some() {
+            return Err("send_group not available in multicast mode".into());
+        }
+
+        let members = self
+            .groups
+            .iter()
+            .filter(|(_, groups)| groups.contains(group))
+            .map(|(session_id, _)| *session_id)
+            .collect::<Vec<u64>>();
+
+        if members.is_empty() {
+            return Ok(());
+        }
+
+        let mut parts = Vec::with_capacity(data.len() + 1);
+        parts.push(group);
+        parts.extend_from_slice(data);
+
+        for session_id in members {
+            self.queue(session_id, &parts)?;
+        }
+
+        Ok(())
+    }
 }
 
 impl AsSocket for Radio {
     type Output = Radio;
 
     fn bind(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
-        Ok(Radio::new_from(Core::bind(addr, opt.clone())?, opt))
+        Radio::new_from(Core::bind(addr, opt.clone())?, opt)
     }
 
     fn connect(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
-        Ok(Radio::new_from(Core::connect(addr, opt.clone())?, opt))
+        Radio::new_from(Core::connect(addr, opt.clone())?, opt)
+    }
+
+    fn bind_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Radio::new_from(Core::bind_with(transport, addr, opt.clone())?, opt)
+    }
+
+    fn connect_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Radio::new_from(Core::connect_with(transport, addr, opt.clone())?, opt)
     }
 
     fn send(&mut self, data: &[&[u8]]) -> Result<(), Box<dyn Error>> {
-        for session_id in self.peers.iter() {
-            let send_queue = self
-                .send_queues
-                .entry(*session_id)
-                .or_insert(SendQueue::new(self.opt.clone()));
-
-            send_queue.push(*session_id, data, self.unique)?;
-            self.unique = self.unique.wrapping_add(1);
+        if let Some(send_queue) = &mut self.multicast_queue {
+            if !self.peers.is_empty() {
+                send_queue.push(0, data, self.unique)?;
+                self.unique = self.unique.wrapping_add(1);
+            }
+
+            return Ok(());
+        }
+
+        for session_id in self.peers.clone() {
+            self.queue(session_id, data)?;
         }
 
         Ok(())
@@ -76,8 +159,28 @@ impl AsSocket for Radio {
     fn tick(&mut self) -> Result<(), Box<dyn Error>> {
         self.check_peer_update();
 
-        while let Ok(_) = self.core.recv() {
-            continue;
+        while let Ok(frame) = self.core.recv() {
+            let Frame::ControlFrame(control_frame) = frame else {
+                continue;
+            };
+
+            match control_frame {
+                ControlFrame::Join((session_id, groups)) => {
+                    if !self.core.peers.contains_key(&session_id) {
+                        continue;
+                    }
+
+                    self.groups.entry(session_id).or_default().extend(groups);
+                }
+                ControlFrame::Leave((session_id, groups)) => {
+                    if let Some(joined) = self.groups.get_mut(&session_id) {
+                        groups.iter().for_each(|group| {
+                            joined.remove(group);
+                        });
+                    }
+                }
+                _ => (),
+            }
         }
 
         let n_per = if self.send_queues.len() > 0 {
@@ -99,6 +202,32 @@ impl AsSocket for Radio {
                     }
                 }
             }
+
+            while let Some(message) = send_queue.pull_message() {
+                if self.core.send_message(message, session_id).is_err() {
+                    break;
+                } else {
+                    ct += 1;
+                    if ct > n_per {
+                        break;
+                    }
+                }
+            }
+        }
+
+        if let Some(send_queue) = &mut self.multicast_queue {
+            let mut ct = 0;
+
+            while let Some(frame) = send_queue.pull() {
+                if self.core.send_multicast(&frame).is_err() {
+                    break;
+                }
+
+                ct += 1;
+                if ct > self.opt.max_tick_send {
+                    break;
+                }
+            }
         }
 
         self.core.maint()?;
@@ -0,0 +1,208 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    io,
+};
+
+use crate::{
+    core::{AsSocket, Core, SockOpt},
+    frame::Frame,
+    queue::{RecvQueue, SendQueue},
+    transport::Transport,
+};
+
+pub struct Rep {
+    core: Core,
+    opt: SockOpt,
+
+    unique: u64,
+    peers: Vec<u64>,
+    peer_set: HashSet<u64>,
+
+    /// Session and request id envelope of the request awaiting a reply.
+    replying_to: Option<(u64, Vec<u8>)>,
+
+    send_queues: HashMap<u64, SendQueue>,
+    recv_queue: RecvQueue,
+}
+
+impl Rep {
+    fn new_from(core: Core, opt: SockOpt) -> Self {
+        Self {
+            core,
+            opt: opt.clone(),
+
+            unique: 0,
+            peers: vec![],
+            peer_set: HashSet::new(),
+
+            replying_to: None,
+
+            send_queues: HashMap::new(),
+            recv_queue: RecvQueue::new(opt),
+        }
+    }
+
+    fn check_peer_update(&mut self) {
+        if let Some(peer_update) = self.core.update_peers() {
+            self.peers = peer_update;
+            self.peer_set = HashSet::new();
+            self.peer_set.extend(self.peers.iter());
+
+            self.send_queues.retain(|k, _| self.peer_set.contains(k));
+        }
+    }
+}
+
+impl AsSocket for Rep {
+    type Output = Rep;
+
+    fn bind(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Rep::new_from(Core::bind(addr, opt.clone())?, opt))
+    }
+
+    fn connect(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Rep::new_from(Core::connect(addr, opt.clone())?, opt))
+    }
+
+    fn bind_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Rep::new_from(
+            Core::bind_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
+    fn connect_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Rep::new_from(
+            Core::connect_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
+    /// Reply to the last received request, on the session it came from.
+    fn send(&mut self, data: &[&[u8]]) -> Result<(), Box<dyn Error>> {
+        // Kept until the reply is queued, so a reply refused at `send_hwm` can be retried.
+        let Some((session_id, id)) = self.replying_to.clone() else {
+            return Err("Rep socket must receive a request before replying".into());
+        };
+
+        if !self.peer_set.contains(&session_id) {
+            // The requester is gone, it will retry elsewhere.
+            self.replying_to = None;
+            return Ok(());
+        }
+
+        let mut envelope = Vec::with_capacity(data.len() + 1);
+        envelope.push(id.as_slice());
+        envelope.extend_from_slice(data);
+
+        let send_queue = self
+            .send_queues
+            .entry(session_id)
+            .or_insert(SendQueue::new(self.opt.clone()));
+
+        if self.core.carries_messages() {
+            send_queue.push_message(session_id, &envelope, self.unique)?;
+        } else {
+            send_queue.push(session_id, &envelope, self.unique)?;
+        }
+        self.unique = self.unique.wrapping_add(1);
+        self.replying_to = None;
+
+        Ok(())
+    }
+
+    /// Receive the next request. Errors while the previous request is still waiting on a reply.
+    fn recv(&mut self) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+        if self.replying_to.is_some() {
+            return Err("Rep socket must reply before receiving again".into());
+        }
+
+        while let Some((mut message, (session_id, _))) = self.recv_queue.pull() {
+            if message.is_empty() || message[0].len() != 8 {
+                continue;
+            }
+
+            let id = message.remove(0);
+            self.replying_to = Some((session_id, id));
+            return Ok(message);
+        }
+
+        Err(Box::new(io::Error::from(io::ErrorKind::WouldBlock)))
+    }
+
+    fn tick(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut recv_error: Option<Box<dyn Error>> = None;
+
+        while let Ok(frame) = self.core.recv() {
+            let pushed = match frame {
+                Frame::DataFrame(data_frame) => self.recv_queue.push(&data_frame),
+                Frame::MessageFrame(message) => self.recv_queue.push_message(message),
+                _ => continue,
+            };
+
+            if let Err(e) = pushed {
+                recv_error = Some(e);
+            }
+        }
+
+        self.check_peer_update();
+
+        let n_per = if !self.send_queues.is_empty() {
+            self.opt.max_tick_send / self.send_queues.len()
+        } else {
+            0
+        };
+
+        for (session_id, send_queue) in self.send_queues.iter_mut() {
+            let mut ct = 0;
+
+            while let Some(frame) = send_queue.pull() {
+                if self.core.send_peer(&frame, session_id).is_err() {
+                    break;
+                }
+
+                ct += 1;
+                if ct > n_per {
+                    break;
+                }
+            }
+
+            while let Some(message) = send_queue.pull_message() {
+                if self.core.send_message(message, session_id).is_err() {
+                    break;
+                }
+
+                ct += 1;
+                if ct > n_per {
+                    break;
+                }
+            }
+        }
+
+        if let Some(err) = recv_error {
+            return Err(err);
+        }
+
+        self.core.maint()?;
+        self.check_peer_update();
+
+        Ok(())
+    }
+
+    fn opt(&mut self) -> &mut SockOpt {
+        &mut self.opt
+    }
+
+    fn peers(&self) -> usize {
+        self.peers.len()
+    }
+}
@@ -0,0 +1,272 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    io,
+    time::Instant,
+};
+
+use crate::{
+    core::{AsSocket, Core, SockOpt},
+    frame::Frame,
+    queue::{RecvQueue, SendQueue},
+    transport::Transport,
+};
+
+/// A request awaiting its reply.
+struct Request {
+    id: u64,
+    parts: Vec<Vec<u8>>,
+    sent_at: Instant,
+    retries: usize,
+}
+
+pub struct Req {
+    core: Core,
+    opt: SockOpt,
+
+    unique: u64,
+    peers: Vec<u64>,
+    peer_set: HashSet<u64>,
+
+    request: Option<Request>,
+    timed_out: bool,
+
+    send_queues: HashMap<u64, SendQueue>,
+    recv_queue: RecvQueue,
+}
+
+impl Req {
+    fn new_from(core: Core, opt: SockOpt) -> Self {
+        Self {
+            core,
+            opt: opt.clone(),
+
+            unique: 0,
+            peers: vec![],
+            peer_set: HashSet::new(),
+
+            request: None,
+            timed_out: false,
+
+            send_queues: HashMap::new(),
+            recv_queue: RecvQueue::new(opt),
+        }
+    }
+
+    fn check_peer_update(&mut self) {
+        if let Some(peer_update) = self.core.update_peers() {
+            self.peers = peer_update;
+            self.peer_set = HashSet::new();
+            self.peer_set.extend(self.peers.iter());
+
+            self.send_queues.retain(|k, _| self.peer_set.contains(k));
+        }
+    }
+
+    /// Queue the request, prefixed with its id, to the next peer in turn.
+    fn queue(&mut self, id: u64, parts: &[&[u8]]) -> Result<(), Box<dyn Error>> {
+        if self.peers.is_empty() {
+            return Err("No peer".into());
+        }
+
+        self.unique = self.unique.wrapping_add(1);
+        let peer = self.peers[self.unique as usize % self.peers.len()];
+
+        let id = id.to_be_bytes();
+        let mut envelope = Vec::with_capacity(parts.len() + 1);
+        envelope.push(id.as_slice());
+        envelope.extend_from_slice(parts);
+
+        let send_queue = self
+            .send_queues
+            .entry(peer)
+            .or_insert(SendQueue::new(self.opt.clone()));
+
+        if self.core.carries_messages() {
+            send_queue.push_message(peer, &envelope, self.unique)?;
+        } else {
+            send_queue.push(peer, &envelope, self.unique)?;
+        }
+
+        Ok(())
+    }
+
+    /// Resend a request that went unanswered for `req_timeout`, or give up on it after
+    /// `req_retries` resends.
+    fn check_timeout(&mut self) {
+        let Some(request) = &self.request else {
+            return;
+        };
+
+        if Instant::now().duration_since(request.sent_at) < self.opt.req_timeout {
+            return;
+        }
+
+        if request.retries >= self.opt.req_retries {
+            self.request = None;
+            self.timed_out = true;
+            return;
+        }
+
+        let id = request.id;
+        let parts = request.parts.clone();
+        let parts = parts.iter().map(|p| p.as_slice()).collect::<Vec<&[u8]>>();
+
+        // Without a peer the attempt still counts, so a request can't outlive its retries.
+        let _ = self.queue(id, &parts);
+
+        if let Some(request) = &mut self.request {
+            request.sent_at = Instant::now();
+            request.retries += 1;
+        }
+    }
+}
+
+impl AsSocket for Req {
+    type Output = Req;
+
+    fn bind(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Req::new_from(Core::bind(addr, opt.clone())?, opt))
+    }
+
+    fn connect(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Req::new_from(Core::connect(addr, opt.clone())?, opt))
+    }
+
+    fn bind_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Req::new_from(
+            Core::bind_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
+    fn connect_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Req::new_from(
+            Core::connect_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
+    /// Send a request. Errors while a previous request is still waiting on its reply.
+    fn send(&mut self, data: &[&[u8]]) -> Result<(), Box<dyn Error>> {
+        if self.request.is_some() {
+            return Err("Req socket must receive a reply before sending again".into());
+        }
+
+        self.check_peer_update();
+
+        let id = self.unique;
+        self.queue(id, data)?;
+
+        self.request = Some(Request {
+            id,
+            parts: data.iter().map(|part| part.to_vec()).collect(),
+            sent_at: Instant::now(),
+            retries: 0,
+        });
+        self.timed_out = false;
+
+        Ok(())
+    }
+
+    /// Receive the reply to the outstanding request. Replies to earlier requests are dropped.
+    /// Once every retry of a request has timed out, returns an error and allows a new send.
+    fn recv(&mut self) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+        if self.timed_out {
+            self.timed_out = false;
+            return Err(Box::new(io::Error::from(io::ErrorKind::TimedOut)));
+        }
+
+        let Some(id) = self.request.as_ref().map(|request| request.id) else {
+            return Err("Req socket must send a request before receiving".into());
+        };
+
+        while let Some((mut message, ..)) = self.recv_queue.pull() {
+            if message.is_empty() || message[0] != id.to_be_bytes() {
+                continue;
+            }
+
+            self.request = None;
+            message.remove(0);
+            return Ok(message);
+        }
+
+        Err(Box::new(io::Error::from(io::ErrorKind::WouldBlock)))
+    }
+
+    fn tick(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut recv_error: Option<Box<dyn Error>> = None;
+
+        while let Ok(frame) = self.core.recv() {
+            let pushed = match frame {
+                Frame::DataFrame(data_frame) => self.recv_queue.push(&data_frame),
+                Frame::MessageFrame(message) => self.recv_queue.push_message(message),
+                _ => continue,
+            };
+
+            if let Err(e) = pushed {
+                recv_error = Some(e);
+            }
+        }
+
+        self.check_peer_update();
+        self.check_timeout();
+
+        let n_per = if !self.send_queues.is_empty() {
+            self.opt.max_tick_send / self.send_queues.len()
+        } else {
+            0
+        };
+
+        for (session_id, send_queue) in self.send_queues.iter_mut() {
+            let mut ct = 0;
+
+            while let Some(frame) = send_queue.pull() {
+                if self.core.send_peer(&frame, session_id).is_err() {
+                    break;
+                }
+
+                ct += 1;
+                if ct > n_per {
+                    break;
+                }
+            }
+
+            while let Some(message) = send_queue.pull_message() {
+                if self.core.send_message(message, session_id).is_err() {
+                    break;
+                }
+
+                ct += 1;
+                if ct > n_per {
+                    break;
+                }
+            }
+        }
+
+        if let Some(err) = recv_error {
+            return Err(err);
+        }
+
+        self.core.maint()?;
+        self.check_peer_update();
+
+        Ok(())
+    }
+
+    fn opt(&mut self) -> &mut SockOpt {
+        &mut self.opt
+    }
+
+    fn peers(&self) -> usize {
+        self.peers.len()
+    }
+}
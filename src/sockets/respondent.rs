@@ -0,0 +1,191 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    io,
+};
+
+use crate::{
+    core::{AsSocket, Core, SockOpt},
+    frame::Frame,
+    queue::{RecvQueue, SendQueue},
+    transport::Transport,
+};
+
+pub struct Respondent {
+    core: Core,
+    opt: SockOpt,
+
+    peers: Vec<u64>,
+    peer_set: HashSet<u64>,
+
+    /// Session and survey id of the survey awaiting a response.
+    answering: Option<(u64, u64)>,
+
+    send_queues: HashMap<u64, SendQueue>,
+    recv_queue: RecvQueue,
+}
+
+impl Respondent {
+    fn new_from(core: Core, opt: SockOpt) -> Self {
+        Self {
+            core,
+            opt: opt.clone(),
+
+            peers: vec![],
+            peer_set: HashSet::new(),
+
+            answering: None,
+
+            send_queues: HashMap::new(),
+            recv_queue: RecvQueue::new(opt),
+        }
+    }
+
+    fn check_peer_update(&mut self) {
+        if let Some(peer_update) = self.core.update_peers() {
+            self.peers = peer_update;
+            self.peer_set = HashSet::new();
+            self.peer_set.extend(self.peers.iter());
+
+            self.send_queues.retain(|k, _| self.peer_set.contains(k));
+        }
+    }
+}
+
+impl AsSocket for Respondent {
+    type Output = Respondent;
+
+    fn bind(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Respondent::new_from(Core::bind(addr, opt.clone())?, opt))
+    }
+
+    fn connect(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Respondent::new_from(Core::connect(addr, opt.clone())?, opt))
+    }
+
+    fn bind_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Respondent::new_from(
+            Core::bind_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
+    fn connect_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Respondent::new_from(
+            Core::connect_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
+    /// Respond to the last received survey, on the session it came from, under its survey id.
+    fn send(&mut self, data: &[&[u8]]) -> Result<(), Box<dyn Error>> {
+        // Kept until the response is queued, a response refused at `send_hwm` can be retried.
+        let Some((session_id, survey_id)) = self.answering else {
+            return Err("Respondent socket must receive a survey before responding".into());
+        };
+
+        if !self.peer_set.contains(&session_id) {
+            self.answering = None;
+            return Ok(());
+        }
+
+        let send_queue = self
+            .send_queues
+            .entry(session_id)
+            .or_insert(SendQueue::new(self.opt.clone()));
+
+        if self.core.carries_messages() {
+            send_queue.push_message_with_id(session_id, data, survey_id)?;
+        } else {
+            send_queue.push_with_id(session_id, data, survey_id)?;
+        }
+        self.answering = None;
+
+        Ok(())
+    }
+
+    /// Receive the next survey. A survey left unanswered is abandoned.
+    fn recv(&mut self) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+        if let Some((message, key)) = self.recv_queue.pull() {
+            self.answering = Some(key);
+            return Ok(message);
+        }
+
+        Err(Box::new(io::Error::from(io::ErrorKind::WouldBlock)))
+    }
+
+    fn tick(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut recv_error: Option<Box<dyn Error>> = None;
+
+        while let Ok(frame) = self.core.recv() {
+            let pushed = match frame {
+                Frame::DataFrame(data_frame) => self.recv_queue.push(&data_frame),
+                Frame::MessageFrame(message) => self.recv_queue.push_message(message),
+                _ => continue,
+            };
+
+            if let Err(e) = pushed {
+                recv_error = Some(e);
+            }
+        }
+
+        self.check_peer_update();
+
+        let n_per = if !self.send_queues.is_empty() {
+            self.opt.max_tick_send / self.send_queues.len()
+        } else {
+            0
+        };
+
+        for (session_id, send_queue) in self.send_queues.iter_mut() {
+            let mut ct = 0;
+
+            while let Some(frame) = send_queue.pull() {
+                if self.core.send_peer(&frame, session_id).is_err() {
+                    break;
+                }
+
+                ct += 1;
+                if ct > n_per {
+                    break;
+                }
+            }
+
+            while let Some(message) = send_queue.pull_message() {
+                if self.core.send_message(message, session_id).is_err() {
+                    break;
+                }
+
+                ct += 1;
+                if ct > n_per {
+                    break;
+                }
+            }
+        }
+
+        if let Some(err) = recv_error {
+            return Err(err);
+        }
+
+        self.core.maint()?;
+        self.check_peer_update();
+
+        Ok(())
+    }
+
+    fn opt(&mut self) -> &mut SockOpt {
+        &mut self.opt
+    }
+
+    fn peers(&self) -> usize {
+        self.peers.len()
+    }
+}
@@ -6,8 +6,9 @@ use std::{
 
 use crate::{
     core::{AsSocket, Core, SockOpt},
-    frame::{ControlFrame, Frame},
-    queue::{RecvQueue, SendQueue},
+    frame::{ControlFrame, Frame, Nack, Sack},
+    queue::{RecvQueue, SendQueue, SendStats},
+    transport::Transport,
 };
 
 pub struct SafeDealer {
@@ -54,8 +55,22 @@ impl SafeDealer {
             self.peer_set.extend(self.peers.iter());
 
             self.send_queues.retain(|k, _| self.peer_set.contains(k));
+            self.recv_queue
+                .streams
+                .retain(|k, _| self.peer_set.contains(k));
+            self.recv_queue
+                .sacks
+                .retain(|k, _| self.peer_set.contains(k));
         }
     }
+
+    /// The congestion window, frames in flight and round trip estimate per peer session.
+    pub fn stats(&self) -> HashMap<u64, SendStats> {
+        self.send_queues
+            .iter()
+            .map(|(session_id, send_queue)| (*session_id, send_queue.stats()))
+            .collect()
+    }
 }
 
 impl AsSocket for SafeDealer {
@@ -69,6 +84,28 @@ impl AsSocket for SafeDealer {
         Ok(SafeDealer::new_from(Core::connect(addr, opt.clone())?, opt))
     }
 
+    fn bind_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(SafeDealer::new_from(
+            Core::bind_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
+    fn connect_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(SafeDealer::new_from(
+            Core::connect_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
     fn send(&mut self, data: &[&[u8]]) -> Result<(), Box<dyn Error>> {
         self.check_peer_update();
 
@@ -78,7 +115,24 @@ impl AsSocket for SafeDealer {
             .entry(peer)
             .or_insert(SendQueue::new(self.opt.clone()));
 
-        send_queue.push(peer, data, self.unique)?;
+        // In ordered mode the session's sequence number goes first, and is only used up once the
+        // message is queued.
+        let sequence = send_queue.sequence.to_be_bytes();
+        let mut parts = Vec::with_capacity(data.len() + 1);
+        if self.opt.safe_ordered {
+            parts.push(sequence.as_slice());
+        }
+        parts.extend_from_slice(data);
+
+        if self.core.carries_messages() {
+            send_queue.push_message(peer, &parts, self.unique)?;
+        } else {
+            send_queue.push(peer, &parts, self.unique)?;
+        }
+
+        if self.opt.safe_ordered {
+            send_queue.sequence = send_queue.sequence.wrapping_add(1);
+        }
         println!("send q len: {}", send_queue.len());
         self.unique = self.unique.wrapping_add(1);
 
@@ -86,7 +140,13 @@ impl AsSocket for SafeDealer {
     }
 
     fn recv(&mut self) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
-        if let Some((message, ..)) = self.recv_queue.pull_safe() {
+        let pulled = if self.opt.safe_ordered {
+            self.recv_queue.pull_ordered()
+        } else {
+            self.recv_queue.pull_safe()
+        };
+
+        if let Some((message, ..)) = pulled {
             return Ok(message);
         }
 
@@ -116,14 +176,54 @@ impl AsSocket for SafeDealer {
 
                             send_queue.confirm_safe(hash);
                         }
+                        ControlFrame::Sack(sack) => {
+                            if let Some(send_queue) = self.send_queues.get_mut(&sack.session_id) {
+                                send_queue.confirm_sack(sack.cumulative, &sack.ranges);
+
+                                // Let the peer past gaps that won't be filled anymore.
+                                let below = send_queue.settled_below();
+                                if below > sack.cumulative {
+                                    let _ = self.core.send_peer(
+                                        &ControlFrame::Settled((sack.session_id, below)).encode(),
+                                        &sack.session_id,
+                                    );
+                                }
+                            }
+                        }
+                        ControlFrame::Settled((session_id, below)) => {
+                            self.recv_queue.settle(session_id, below);
+                        }
+                        ControlFrame::Nack(nack) => {
+                            if let Some(send_queue) = self.send_queues.get_mut(&nack.session_id) {
+                                send_queue.nack(nack.message_id, &nack.ranges);
+                            }
+                        }
                         _ => (),
                     };
 
                     continue;
                 }
                 Frame::DataFrame(data_frame) => {
+                    // A repeat is only acknowledged again, the sender missed the first one.
+                    if let Some(sequence) = data_frame.sequence
+                        && self.recv_queue.taken(data_frame.session_id, sequence)
+                    {
+                        self.recv_queue
+                            .record_sequence(data_frame.session_id, sequence);
+                        continue;
+                    }
+
+                    // Left unacknowledged, for the sender to retransmit once there's room.
+                    if self.opt.safe_ordered && !self.recv_queue.admits_ordered(&data_frame) {
+                        continue;
+                    }
+
                     if let Err(e) = self.recv_queue.push(&data_frame) {
                         recv_error = Some(e);
+                    } else if let Some(sequence) = data_frame.sequence {
+                        // Acknowledged together with the rest of this tick's frames below.
+                        self.recv_queue
+                            .record_sequence(data_frame.session_id, sequence);
                     } else {
                         let hash = data_frame.hash();
 
@@ -137,9 +237,48 @@ impl AsSocket for SafeDealer {
                         );
                     }
                 }
+                // Whole messages are acked by message id.
+                Frame::MessageFrame(message) => {
+                    let session_id = message.session_id;
+                    let message_id = message.message_id;
+
+                    if self.opt.safe_ordered && !self.recv_queue.admits_ordered_message(&message) {
+                        continue;
+                    }
+
+                    if let Err(e) = self.recv_queue.push_message(message) {
+                        recv_error = Some(e);
+                    } else {
+                        let _ = self.core.send_peer(
+                            &ControlFrame::Ack((session_id, message_id.to_be_bytes().to_vec()))
+                                .encode(),
+                            &session_id,
+                        );
+                    }
+                }
             }
         }
 
+        for sack in self
+            .recv_queue
+            .take_sacks(Sack::max_ranges(self.opt.max_frame_size))
+        {
+            let session_id = sack.session_id;
+            let _ = self
+                .core
+                .send_peer(&ControlFrame::Sack(sack).encode(), &session_id);
+        }
+
+        for nack in self
+            .recv_queue
+            .take_nacks(Nack::max_ranges(self.opt.max_frame_size))
+        {
+            let session_id = nack.session_id;
+            let _ = self
+                .core
+                .send_peer(&ControlFrame::Nack(nack).encode(), &session_id);
+        }
+
         let n_per = if self.send_queues.len() > 0 {
             self.opt.max_tick_send / self.send_queues.len()
         } else {
@@ -147,6 +286,10 @@ impl AsSocket for SafeDealer {
         };
 
         for (session_id, send_queue) in self.send_queues.iter_mut() {
+            if let Some(rtt) = self.core.take_rtt_sample(session_id) {
+                send_queue.rtt.sample(rtt);
+            }
+
             let mut ct = 0;
 
             while let Some(frame) = send_queue.pull_safe() {
@@ -159,6 +302,17 @@ impl AsSocket for SafeDealer {
                     }
                 }
             }
+
+            while let Some(message) = send_queue.pull_message_safe() {
+                if self.core.send_message(message, session_id).is_err() {
+                    break;
+                } else {
+                    ct += 1;
+                    if ct > n_per {
+                        break;
+                    }
+                }
+            }
         }
 
         if let Some(e) = recv_error {
@@ -0,0 +1,179 @@
+use std::{collections::HashSet, error::Error, io};
+
+use crate::{
+    core::{AsSocket, Core, SockOpt},
+    frame::{ControlFrame, Frame, Nack, Sack},
+    queue::RecvQueue,
+    transport::Transport,
+};
+
+pub struct SafeDish {
+    core: Core,
+    opt: SockOpt,
+
+    recv_queue: RecvQueue,
+}
+
+impl SafeDish {
+    fn new_from(core: Core, opt: SockOpt) -> Self {
+        Self {
+            core,
+            opt: opt.clone(),
+
+            recv_queue: RecvQueue::new(opt),
+        }
+    }
+
+    fn check_peer_update(&mut self) {
+        if let Some(peer_update) = self.core.update_peers() {
+            let peer_set = peer_update.into_iter().collect::<HashSet<u64>>();
+
+            self.recv_queue.sacks.retain(|k, _| peer_set.contains(k));
+        }
+    }
+}
+
+impl AsSocket for SafeDish {
+    type Output = SafeDish;
+
+    fn bind(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(SafeDish::new_from(Core::bind(addr, opt.clone())?, opt))
+    }
+
+    fn connect(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(SafeDish::new_from(Core::connect(addr, opt.clone())?, opt))
+    }
+
+    fn bind_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(SafeDish::new_from(
+            Core::bind_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
+    fn connect_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(SafeDish::new_from(
+            Core::connect_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
+    fn send(&mut self, _data: &[&[u8]]) -> Result<(), Box<dyn Error>> {
+        Err("send not available on SafeDish socket".into())
+    }
+
+    fn recv(&mut self) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+        if let Some((message, ..)) = self.recv_queue.pull_safe() {
+            return Ok(message);
+        }
+
+        Err(Box::new(io::Error::from(io::ErrorKind::WouldBlock)))
+    }
+
+    /// Receive frames, acking each one taken into the receive queue. Sequenced frames are acked
+    /// together, once per tick, and the gaps of stalled messages are asked for.
+    fn tick(&mut self) -> Result<(), Box<dyn Error>> {
+        self.check_peer_update();
+
+        let mut recv_error: Option<Box<dyn Error>> = None;
+
+        while let Ok(frame) = self.core.recv() {
+            let (pushed, session_id, hash) = match frame {
+                Frame::DataFrame(data_frame) => {
+                    // A repeat is only acknowledged again, the sender missed the first one.
+                    if let Some(sequence) = data_frame.sequence
+                        && self.recv_queue.taken(data_frame.session_id, sequence)
+                    {
+                        self.recv_queue
+                            .record_sequence(data_frame.session_id, sequence);
+                        continue;
+                    }
+
+                    let pushed = self.recv_queue.push(&data_frame);
+
+                    if let Some(sequence) = data_frame.sequence {
+                        if let Err(e) = pushed {
+                            recv_error = Some(e);
+                        } else {
+                            self.recv_queue
+                                .record_sequence(data_frame.session_id, sequence);
+                        }
+                        continue;
+                    }
+
+                    (pushed, data_frame.session_id, data_frame.hash())
+                }
+                // Whole messages are acked by message id.
+                Frame::MessageFrame(message) => {
+                    let session_id = message.session_id;
+                    let message_id = message.message_id;
+
+                    (
+                        self.recv_queue.push_message(message),
+                        session_id,
+                        message_id,
+                    )
+                }
+                Frame::ControlFrame(ControlFrame::Settled((session_id, below))) => {
+                    self.recv_queue.settle(session_id, below);
+                    continue;
+                }
+                _ => continue,
+            };
+
+            if let Err(e) = pushed {
+                recv_error = Some(e);
+                continue;
+            }
+
+            let _ = self.core.send_peer(
+                &ControlFrame::Ack((session_id, hash.to_be_bytes().to_vec())).encode(),
+                &session_id,
+            );
+        }
+
+        for sack in self
+            .recv_queue
+            .take_sacks(Sack::max_ranges(self.opt.max_frame_size))
+        {
+            let session_id = sack.session_id;
+            let _ = self
+                .core
+                .send_peer(&ControlFrame::Sack(sack).encode(), &session_id);
+        }
+
+        for nack in self
+            .recv_queue
+            .take_nacks(Nack::max_ranges(self.opt.max_frame_size))
+        {
+            let session_id = nack.session_id;
+            let _ = self
+                .core
+                .send_peer(&ControlFrame::Nack(nack).encode(), &session_id);
+        }
+
+        if let Some(e) = recv_error {
+            return Err(e);
+        }
+
+        self.core.maint()?;
+
+        Ok(())
+    }
+
+    fn opt(&mut self) -> &mut SockOpt {
+        &mut self.opt
+    }
+
+    fn peers(&self) -> usize {
+        self.core.peers.len()
+    }
+}
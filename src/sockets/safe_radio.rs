@@ -0,0 +1,247 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    io,
+};
+
+use crate::{
+    core::{AsSocket, Core, SockOpt},
+    frame::{ControlFrame, Frame},
+    queue::{SendQueue, SendStats},
+    transport::Transport,
+};
+
+pub struct SafeRadio {
+    core: Core,
+    opt: SockOpt,
+
+    unique: u64,
+    peers: Vec<u64>,
+    peer_set: HashSet<u64>,
+
+    last_message_id: Option<u64>,
+    /// Message ids given up on per subscriber session, until taken with `dropped`. Holds the
+    /// latest `send_hwm` ids per session.
+    dropped: HashMap<u64, Vec<u64>>,
+
+    send_queues: HashMap<u64, SendQueue>,
+}
+
+impl SafeRadio {
+    fn new_from(core: Core, opt: SockOpt) -> Self {
+        Self {
+            core,
+            opt: opt.clone(),
+
+            unique: 0,
+            peers: Vec::new(),
+            peer_set: HashSet::new(),
+
+            last_message_id: None,
+            dropped: HashMap::new(),
+
+            send_queues: HashMap::new(),
+        }
+    }
+
+    fn check_peer_update(&mut self) {
+        if let Some(peer_update) = self.core.update_peers() {
+            self.peers = peer_update;
+            self.peer_set = HashSet::new();
+            self.peer_set.extend(self.peers.iter());
+
+            self.send_queues.retain(|k, _| self.peer_set.contains(k));
+        }
+    }
+
+    /// The message id the last sent message went out under, to every subscriber. Match it
+    /// against the ids reported by `dropped`.
+    pub fn last_message_id(&self) -> Option<u64> {
+        self.last_message_id
+    }
+
+    /// Take the ids of messages each subscriber session never acknowledged within
+    /// `safe_resend_limit` resends, since the last call.
+    pub fn dropped(&mut self) -> HashMap<u64, Vec<u64>> {
+        std::mem::take(&mut self.dropped)
+    }
+
+    /// The congestion window, frames in flight and round trip estimate per peer session.
+    pub fn stats(&self) -> HashMap<u64, SendStats> {
+        self.send_queues
+            .iter()
+            .map(|(session_id, send_queue)| (*session_id, send_queue.stats()))
+            .collect()
+    }
+}
+
+impl AsSocket for SafeRadio {
+    type Output = SafeRadio;
+
+    fn bind(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(SafeRadio::new_from(Core::bind(addr, opt.clone())?, opt))
+    }
+
+    fn connect(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(SafeRadio::new_from(Core::connect(addr, opt.clone())?, opt))
+    }
+
+    fn bind_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(SafeRadio::new_from(
+            Core::bind_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
+    fn connect_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(SafeRadio::new_from(
+            Core::connect_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
+    /// Queue a message to every peer under one message id.
+    fn send(&mut self, data: &[&[u8]]) -> Result<(), Box<dyn Error>> {
+        self.check_peer_update();
+
+        // One message id goes to every subscriber, so a subscriber at `send_hwm` holds it back
+        // from all of them.
+        if self.peers.iter().any(|session_id| {
+            self.send_queues
+                .get(session_id)
+                .is_some_and(SendQueue::full)
+        }) {
+            return Err(Box::new(io::Error::from(io::ErrorKind::WouldBlock)));
+        }
+
+        self.unique = self.unique.wrapping_add(1);
+        let message_id = SendQueue::hash(data, self.unique);
+
+        for session_id in self.peers.iter() {
+            let send_queue = self
+                .send_queues
+                .entry(*session_id)
+                .or_insert(SendQueue::new(self.opt.clone()));
+
+            if self.core.carries_messages() {
+                send_queue.push_message_with_id(*session_id, data, message_id)?;
+            } else {
+                send_queue.push_with_id(*session_id, data, message_id)?;
+            }
+        }
+
+        self.last_message_id = Some(message_id);
+
+        Ok(())
+    }
+
+    fn recv(&mut self) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+        Err("recv not available on SafeRadio socket".into())
+    }
+
+    fn tick(&mut self) -> Result<(), Box<dyn Error>> {
+        self.check_peer_update();
+
+        while let Ok(frame) = self.core.recv() {
+            let (session_id, chunk) = match frame {
+                Frame::ControlFrame(ControlFrame::Ack(ack)) => ack,
+                Frame::ControlFrame(ControlFrame::Sack(sack)) => {
+                    if let Some(send_queue) = self.send_queues.get_mut(&sack.session_id) {
+                        send_queue.confirm_sack(sack.cumulative, &sack.ranges);
+
+                        // Let the peer past gaps that won't be filled anymore.
+                        let below = send_queue.settled_below();
+                        if below > sack.cumulative {
+                            let _ = self.core.send_peer(
+                                &ControlFrame::Settled((sack.session_id, below)).encode(),
+                                &sack.session_id,
+                            );
+                        }
+                    }
+                    continue;
+                }
+                Frame::ControlFrame(ControlFrame::Nack(nack)) => {
+                    if let Some(send_queue) = self.send_queues.get_mut(&nack.session_id) {
+                        send_queue.nack(nack.message_id, &nack.ranges);
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+
+            let Ok(hash) = <[u8; 8]>::try_from(chunk.as_slice()) else {
+                continue;
+            };
+
+            if let Some(send_queue) = self.send_queues.get_mut(&session_id) {
+                send_queue.confirm_safe(u64::from_be_bytes(hash));
+            }
+        }
+
+        let n_per = if !self.send_queues.is_empty() {
+            self.opt.max_tick_send / self.send_queues.len()
+        } else {
+            0
+        };
+
+        for (session_id, send_queue) in self.send_queues.iter_mut() {
+            if let Some(rtt) = self.core.take_rtt_sample(session_id) {
+                send_queue.rtt.sample(rtt);
+            }
+
+            let mut ct = 0;
+
+            while let Some(frame) = send_queue.pull_safe() {
+                if self.core.send_peer(&frame, session_id).is_err() {
+                    break;
+                }
+
+                ct += 1;
+                if ct > n_per {
+                    break;
+                }
+            }
+
+            while let Some(message) = send_queue.pull_message_safe() {
+                if self.core.send_message(message, session_id).is_err() {
+                    break;
+                }
+
+                ct += 1;
+                if ct > n_per {
+                    break;
+                }
+            }
+
+            let dropped = send_queue.take_dropped();
+            if !dropped.is_empty() {
+                let report = self.dropped.entry(*session_id).or_default();
+                report.extend(dropped);
+
+                let excess = report.len().saturating_sub(self.opt.send_hwm);
+                report.drain(..excess);
+            }
+        }
+
+        self.core.maint()?;
+        self.check_peer_update();
+
+        Ok(())
+    }
+
+    fn opt(&mut self) -> &mut SockOpt {
+        &mut self.opt
+    }
+
+    fn peers(&self) -> usize {
+        self.core.peers.len()
+    }
+}
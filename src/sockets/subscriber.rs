@@ -0,0 +1,144 @@
+use std::{error::Error, io};
+
+use super::membership::Membership;
+use crate::{
+    core::{AsSocket, Core, SockOpt},
+    frame::{ControlFrame, Frame},
+    queue::RecvQueue,
+    transport::Transport,
+};
+
+pub struct Sub {
+    core: Core,
+    opt: SockOpt,
+
+    topics: Membership,
+
+    recv_queue: RecvQueue,
+}
+
+impl Sub {
+    fn new_from(core: Core, opt: SockOpt) -> Self {
+        Self {
+            core,
+            opt: opt.clone(),
+
+            topics: Membership::new(&opt, ControlFrame::Subscribe, ControlFrame::Unsubscribe),
+
+            recv_queue: RecvQueue::new(opt),
+        }
+    }
+
+    /// Receive messages whose first part starts with `topic`. An empty topic matches everything.
+    pub fn subscribe(&mut self, topic: &[u8]) -> Result<(), Box<dyn Error>> {
+        if topic.len() > u16::MAX as usize {
+            return Err("Topic too long, exceeds 65535 bytes".into());
+        }
+
+        self.topics.insert(&mut self.core, topic);
+
+        Ok(())
+    }
+
+    /// Stop receiving messages for a topic prefix passed to `subscribe`.
+    pub fn unsubscribe(&mut self, topic: &[u8]) -> Result<(), Box<dyn Error>> {
+        self.topics.remove(&mut self.core, topic);
+
+        Ok(())
+    }
+
+    fn wants(&self, topic: &[u8]) -> bool {
+        self.topics.names().any(|prefix| topic.starts_with(prefix))
+    }
+}
+
+impl AsSocket for Sub {
+    type Output = Sub;
+
+    fn bind(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Sub::new_from(Core::bind(addr, opt.clone())?, opt))
+    }
+
+    fn connect(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Sub::new_from(Core::connect(addr, opt.clone())?, opt))
+    }
+
+    fn bind_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Sub::new_from(
+            Core::bind_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
+    fn connect_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Sub::new_from(
+            Core::connect_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
+    fn send(&mut self, _data: &[&[u8]]) -> Result<(), Box<dyn Error>> {
+        Err("send not available on Sub socket".into())
+    }
+
+    /// Pull the next message matching a subscription. Messages published before an
+    /// unsubscribe reached the publisher are dropped here.
+    fn recv(&mut self) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+        while let Some((message, ..)) = self.recv_queue.pull() {
+            let topic = message
+                .first()
+                .map(|part| part.as_slice())
+                .unwrap_or_default();
+
+            if self.wants(topic) {
+                return Ok(message);
+            }
+        }
+
+        Err(Box::new(io::Error::from(io::ErrorKind::WouldBlock)))
+    }
+
+    fn tick(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut recv_error: Option<Box<dyn Error>> = None;
+
+        while let Ok(frame) = self.core.recv() {
+            let pushed = match frame {
+                Frame::DataFrame(data_frame) => self.recv_queue.push(&data_frame),
+                Frame::MessageFrame(message) => self.recv_queue.push_message(message),
+                _ => continue,
+            };
+
+            if let Err(e) = pushed {
+                recv_error = Some(e);
+            }
+        }
+
+        self.topics.check_peer_update(&mut self.core);
+        self.topics.maint(&mut self.core);
+
+        if let Some(e) = recv_error {
+            return Err(e);
+        }
+
+        self.core.maint()?;
+        self.topics.check_peer_update(&mut self.core);
+
+        Ok(())
+    }
+
+    fn opt(&mut self) -> &mut SockOpt {
+        &mut self.opt
+    }
+
+    fn peers(&self) -> usize {
+        self.core.peers.len()
+    }
+}
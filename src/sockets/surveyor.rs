@@ -0,0 +1,242 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    io,
+    time::Instant,
+};
+
+use crate::{
+    core::{AsSocket, Core, SockOpt},
+    frame::Frame,
+    queue::{RecvQueue, SendQueue},
+    transport::Transport,
+};
+
+/// The survey collecting responses, identified by the message id it went out under.
+struct Survey {
+    id: u64,
+    deadline: Instant,
+}
+
+pub struct Surveyor {
+    core: Core,
+    opt: SockOpt,
+
+    unique: u64,
+    peers: Vec<u64>,
+    peer_set: HashSet<u64>,
+
+    survey: Option<Survey>,
+    expired: bool,
+
+    send_queues: HashMap<u64, SendQueue>,
+    recv_queue: RecvQueue,
+}
+
+impl Surveyor {
+    fn new_from(core: Core, opt: SockOpt) -> Self {
+        Self {
+            core,
+            opt: opt.clone(),
+
+            unique: 0,
+            peers: vec![],
+            peer_set: HashSet::new(),
+
+            survey: None,
+            expired: false,
+
+            send_queues: HashMap::new(),
+            recv_queue: RecvQueue::new(opt),
+        }
+    }
+
+    fn check_peer_update(&mut self) {
+        if let Some(peer_update) = self.core.update_peers() {
+            self.peers = peer_update;
+            self.peer_set = HashSet::new();
+            self.peer_set.extend(self.peers.iter());
+
+            self.send_queues.retain(|k, _| self.peer_set.contains(k));
+        }
+    }
+
+    /// Close the survey once its deadline passes, dropping responses not yet received.
+    fn check_deadline(&mut self) {
+        let Some(survey) = &self.survey else {
+            return;
+        };
+
+        if Instant::now() < survey.deadline {
+            return;
+        }
+
+        self.survey = None;
+        self.expired = true;
+        while self.recv_queue.pull().is_some() {}
+    }
+}
+
+impl AsSocket for Surveyor {
+    type Output = Surveyor;
+
+    fn bind(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Surveyor::new_from(Core::bind(addr, opt.clone())?, opt))
+    }
+
+    fn connect(addr: &str, opt: SockOpt) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Surveyor::new_from(Core::connect(addr, opt.clone())?, opt))
+    }
+
+    fn bind_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Surveyor::new_from(
+            Core::bind_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
+    fn connect_with(
+        transport: Box<dyn Transport>,
+        addr: &str,
+        opt: SockOpt,
+    ) -> Result<Self::Output, Box<dyn Error>> {
+        Ok(Surveyor::new_from(
+            Core::connect_with(transport, addr, opt.clone())?,
+            opt,
+        ))
+    }
+
+    /// Start a survey, queueing it to every peer under one message id. Responses to any
+    /// previous survey are dropped from here on.
+    fn send(&mut self, data: &[&[u8]]) -> Result<(), Box<dyn Error>> {
+        self.check_peer_update();
+
+        // A survey queued to only some peers would go out without being recorded, so refuse it
+        // whole while any peer is at `send_hwm`.
+        if self.peers.iter().any(|session_id| {
+            self.send_queues
+                .get(session_id)
+                .is_some_and(SendQueue::full)
+        }) {
+            return Err(Box::new(io::Error::from(io::ErrorKind::WouldBlock)));
+        }
+
+        self.unique = self.unique.wrapping_add(1);
+        let id = SendQueue::hash(data, self.unique);
+
+        for session_id in self.peers.iter() {
+            let send_queue = self
+                .send_queues
+                .entry(*session_id)
+                .or_insert(SendQueue::new(self.opt.clone()));
+
+            if self.core.carries_messages() {
+                send_queue.push_message_with_id(*session_id, data, id)?;
+            } else {
+                send_queue.push_with_id(*session_id, data, id)?;
+            }
+        }
+
+        self.survey = Some(Survey {
+            id,
+            deadline: Instant::now() + self.opt.survey_deadline,
+        });
+        self.expired = false;
+
+        Ok(())
+    }
+
+    /// Receive the next response to the running survey. Once the deadline has passed, returns
+    /// a `TimedOut` error until the next survey is sent.
+    fn recv(&mut self) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+        self.check_deadline();
+
+        if self.expired {
+            return Err(Box::new(io::Error::from(io::ErrorKind::TimedOut)));
+        }
+
+        let Some(id) = self.survey.as_ref().map(|survey| survey.id) else {
+            return Err("Surveyor must send a survey before receiving".into());
+        };
+
+        while let Some((message, (_, message_id))) = self.recv_queue.pull() {
+            if message_id == id {
+                return Ok(message);
+            }
+        }
+
+        Err(Box::new(io::Error::from(io::ErrorKind::WouldBlock)))
+    }
+
+    fn tick(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut recv_error: Option<Box<dyn Error>> = None;
+
+        while let Ok(frame) = self.core.recv() {
+            let pushed = match frame {
+                Frame::DataFrame(data_frame) => self.recv_queue.push(&data_frame),
+                Frame::MessageFrame(message) => self.recv_queue.push_message(message),
+                _ => continue,
+            };
+
+            if let Err(e) = pushed {
+                recv_error = Some(e);
+            }
+        }
+
+        self.check_deadline();
+        self.check_peer_update();
+
+        let n_per = if !self.send_queues.is_empty() {
+            self.opt.max_tick_send / self.send_queues.len()
+        } else {
+            0
+        };
+
+        for (session_id, send_queue) in self.send_queues.iter_mut() {
+            let mut ct = 0;
+
+            while let Some(frame) = send_queue.pull() {
+                if self.core.send_peer(&frame, session_id).is_err() {
+                    break;
+                }
+
+                ct += 1;
+                if ct > n_per {
+                    break;
+                }
+            }
+
+            while let Some(message) = send_queue.pull_message() {
+                if self.core.send_message(message, session_id).is_err() {
+                    break;
+                }
+
+                ct += 1;
+                if ct > n_per {
+                    break;
+                }
+            }
+        }
+
+        if let Some(err) = recv_error {
+            return Err(err);
+        }
+
+        self.core.maint()?;
+        self.check_peer_update();
+
+        Ok(())
+    }
+
+    fn opt(&mut self) -> &mut SockOpt {
+        &mut self.opt
+    }
+
+    fn peers(&self) -> usize {
+        self.peers.len()
+    }
+}
@@ -0,0 +1,29 @@
+use std::{fmt, net::SocketAddr, path::PathBuf};
+
+/// Address of a peer on some transport.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Addr {
+    Inet(SocketAddr),
+    Unix(PathBuf),
+    Inproc(String),
+
+    /// A peer on a user supplied transport that doesn't use socket addresses.
+    Name(String),
+}
+
+impl From<SocketAddr> for Addr {
+    fn from(addr: SocketAddr) -> Self {
+        Addr::Inet(addr)
+    }
+}
+
+impl fmt::Display for Addr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Addr::Inet(addr) => write!(f, "{}", addr),
+            Addr::Unix(path) => write!(f, "unix://{}", path.display()),
+            Addr::Inproc(name) => write!(f, "inproc://{}", name),
+            Addr::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
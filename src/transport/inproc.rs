@@ -0,0 +1,171 @@
+use std::{
+    collections::HashMap,
+    io,
+    sync::{
+        Mutex, OnceLock,
+        mpsc::{self, Receiver, SyncSender, TryRecvError, TrySendError},
+    },
+};
+
+use super::{Addr, Transport};
+use crate::{frame::MessageFrame, random};
+
+// Packets an endpoint holds before further sends to it are dropped, like a full socket buffer.
+const INPROC_CAPACITY: usize = 4096;
+
+// Inproc frames never touch a wire, this only bounds the receive buffer in Core.
+const MAX_INPROC_FRAME_SIZE: usize = 1 << 17;
+
+#[derive(Clone)]
+struct Inbox {
+    frames: SyncSender<(Vec<u8>, Addr)>,
+    messages: SyncSender<(MessageFrame, Addr)>,
+}
+
+fn registry() -> &'static Mutex<HashMap<String, Inbox>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<String, Inbox>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn lookup(addr: &Addr) -> io::Result<Inbox> {
+    let Addr::Inproc(name) = addr else {
+        return Err(io::Error::from(io::ErrorKind::InvalidInput));
+    };
+
+    let registry = registry()
+        .lock()
+        .map_err(|_| io::Error::other("Inproc registry poisoned"))?;
+
+    match registry.get(name) {
+        Some(inbox) => Ok(inbox.clone()),
+        None => Err(io::Error::from(io::ErrorKind::ConnectionRefused)),
+    }
+}
+
+fn try_send<T>(sender: &SyncSender<T>, packet: T) -> io::Result<()> {
+    match sender.try_send(packet) {
+        Ok(()) => Ok(()),
+        Err(TrySendError::Full(_)) => Err(io::Error::from(io::ErrorKind::WouldBlock)),
+        Err(TrySendError::Disconnected(_)) => {
+            Err(io::Error::from(io::ErrorKind::ConnectionRefused))
+        }
+    }
+}
+
+fn try_recv<T>(receiver: &Receiver<T>) -> io::Result<T> {
+    match receiver.try_recv() {
+        Ok(packet) => Ok(packet),
+        Err(TryRecvError::Empty) => Err(io::Error::from(io::ErrorKind::WouldBlock)),
+        Err(TryRecvError::Disconnected) => Err(io::Error::from(io::ErrorKind::NotConnected)),
+    }
+}
+
+/// In-memory channels between sockets of the same process, registered by name. Control frames
+/// travel encoded as usual, messages are moved whole.
+pub struct InprocTransport {
+    name: String,
+    frames: Receiver<(Vec<u8>, Addr)>,
+    messages: Receiver<(MessageFrame, Addr)>,
+}
+
+impl InprocTransport {
+    /// Register an endpoint under `name`. Fails if the name is already bound.
+    pub fn bind(name: &str) -> io::Result<Self> {
+        let mut registry = registry()
+            .lock()
+            .map_err(|_| io::Error::other("Inproc registry poisoned"))?;
+
+        if registry.contains_key(name) {
+            return Err(io::Error::from(io::ErrorKind::AddrInUse));
+        }
+
+        let (frames_tx, frames) = mpsc::sync_channel(INPROC_CAPACITY);
+        let (messages_tx, messages) = mpsc::sync_channel(INPROC_CAPACITY);
+
+        registry.insert(
+            name.to_string(),
+            Inbox {
+                frames: frames_tx,
+                messages: messages_tx,
+            },
+        );
+
+        Ok(Self {
+            name: name.to_string(),
+            frames,
+            messages,
+        })
+    }
+
+    /// Register an endpoint under a fresh name, so the peer has somewhere to reply to.
+    pub fn bind_ephemeral() -> io::Result<Self> {
+        loop {
+            match InprocTransport::bind(&format!("~{:016x}", random::entropy())) {
+                Err(e) if e.kind() == io::ErrorKind::AddrInUse => continue,
+                result => return result,
+            }
+        }
+    }
+
+    fn addr(&self) -> Addr {
+        Addr::Inproc(self.name.clone())
+    }
+}
+
+impl Transport for InprocTransport {
+    fn bind(&mut self, addr: &str) -> io::Result<()> {
+        *self = InprocTransport::bind(addr)?;
+        Ok(())
+    }
+
+    fn resolve(&self, addr: &str) -> io::Result<Vec<Addr>> {
+        Ok(vec![Addr::Inproc(addr.to_string())])
+    }
+
+    fn connect(&mut self, addr: &Addr) -> io::Result<()> {
+        lookup(addr)?;
+        Ok(())
+    }
+
+    fn send_to(&mut self, data: &[u8], addr: &Addr) -> io::Result<usize> {
+        try_send(&lookup(addr)?.frames, (data.to_vec(), self.addr()))?;
+        Ok(data.len())
+    }
+
+    fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, Addr)> {
+        let (data, addr) = try_recv(&self.frames)?;
+
+        let n = data.len().min(buf.len());
+        buf[..n].copy_from_slice(&data[..n]);
+
+        Ok((n, addr))
+    }
+
+    fn local_addr(&self) -> io::Result<Addr> {
+        Ok(self.addr())
+    }
+
+    fn max_frame_size(&self) -> usize {
+        MAX_INPROC_FRAME_SIZE
+    }
+
+    fn carries_messages(&self) -> bool {
+        true
+    }
+
+    fn send_message(&mut self, message: MessageFrame, addr: &Addr) -> io::Result<()> {
+        try_send(&lookup(addr)?.messages, (message, self.addr()))
+    }
+
+    fn recv_message(&mut self) -> io::Result<(MessageFrame, Addr)> {
+        try_recv(&self.messages)
+    }
+}
+
+impl Drop for InprocTransport {
+    fn drop(&mut self) {
+        if let Ok(mut registry) = registry().lock() {
+            registry.remove(&self.name);
+        }
+    }
+}
@@ -0,0 +1,283 @@
+mod addr;
+mod inproc;
+mod multicast;
+mod tcp;
+mod udp;
+#[cfg(unix)]
+mod unix;
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    io,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs},
+    path::PathBuf,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use crate::{
+    SockOpt,
+    frame::{self, MessageFrame},
+};
+
+pub use addr::Addr;
+pub use inproc::InprocTransport;
+pub use multicast::Multicast;
+pub use tcp::TcpTransport;
+pub use udp::UdpTransport;
+#[cfg(unix)]
+pub use unix::UnixTransport;
+
+/// Datagram-style carrier for encoded frames underneath `Core`.
+///
+/// Implement this to run nbmq's sessions, fragmentation and reliability on top of your own link,
+/// and hand it to a socket with `Socket::bind_with` or `Socket::connect_with`. Frames may be lost,
+/// duplicated or reordered, the layers above cope with that just as they do over UDP.
+///
+/// All calls must be non-blocking, returning `io::ErrorKind::WouldBlock` when there is nothing to
+/// receive, so that the connection layer stays driven purely by `tick()`.
+pub trait Transport: Send {
+    /// Take up a local address. Called once on transports handed to a bound socket, before any
+    /// other call.
+    fn bind(&mut self, addr: &str) -> io::Result<()>;
+
+    /// Map the address a socket is asked to connect to onto the peer addresses it may be reached
+    /// at, which are tried in turn. Socket addresses become `Addr::Inet`, anything else
+    /// `Addr::Name`.
+    fn resolve(&self, addr: &str) -> io::Result<Vec<Addr>> {
+        Ok(match SocketAddr::from_str(addr) {
+            Ok(addr) => vec![Addr::Inet(addr)],
+            Err(_) => vec![Addr::Name(addr.to_string())],
+        })
+    }
+
+    /// `resolve` again for a reconnect, from within `tick()`. Transports whose lookups can block
+    /// start one in the background and return `WouldBlock` until a later call finds it done.
+    fn reresolve(&mut self, addr: &str) -> io::Result<Vec<Addr>> {
+        self.resolve(addr)
+    }
+
+    /// Direct the transport at a remote peer. Called again on every reconnect attempt.
+    fn connect(&mut self, addr: &Addr) -> io::Result<()>;
+
+    /// Send one encoded frame to a peer.
+    fn send_to(&mut self, data: &[u8], addr: &Addr) -> io::Result<usize>;
+
+    /// Receive one encoded frame, along with the address of the peer it came from.
+    fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, Addr)>;
+
+    /// The local address the transport is bound to.
+    fn local_addr(&self) -> io::Result<Addr>;
+
+    /// Largest frame the transport can carry in one piece.
+    fn max_frame_size(&self) -> usize {
+        frame::MAX_FRAME_SIZE
+    }
+
+    /// Whether the transport moves whole messages between peers. Sockets then hand messages to
+    /// `send_message` instead of chunking them into `DataFrame`s.
+    fn carries_messages(&self) -> bool {
+        false
+    }
+
+    /// Hand a whole message to a peer.
+    fn send_message(&mut self, _message: MessageFrame, _addr: &Addr) -> io::Result<()> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+
+    /// Receive a whole message, along with the address of the peer it came from.
+    fn recv_message(&mut self) -> io::Result<(MessageFrame, Addr)> {
+        Err(io::Error::from(io::ErrorKind::WouldBlock))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Scheme {
+    Udp,
+    Tcp,
+    #[cfg(unix)]
+    Unix,
+    Inproc,
+}
+
+fn parse(addr: &str) -> Result<(Scheme, &str), Box<dyn Error>> {
+    let Some((scheme, rest)) = addr.split_once("://") else {
+        return Ok((Scheme::Udp, addr));
+    };
+
+    match scheme {
+        "udp" => Ok((Scheme::Udp, rest)),
+        "tcp" => Ok((Scheme::Tcp, rest)),
+        #[cfg(unix)]
+        "unix" => Ok((Scheme::Unix, rest)),
+        "inproc" => Ok((Scheme::Inproc, rest)),
+        _ => Err(format!("Unsupported transport {}", scheme).into()),
+    }
+}
+
+/// Map an address to the socket address an inet transport can use.
+fn inet(addr: &Addr) -> io::Result<SocketAddr> {
+    match addr {
+        Addr::Inet(addr) => Ok(*addr),
+        _ => Err(io::Error::from(io::ErrorKind::InvalidInput)),
+    }
+}
+
+/// Resolve a host and port through `ToSocketAddrs`, which may query DNS.
+fn resolve_inet(addr: &str) -> io::Result<Vec<Addr>> {
+    let addrs = addr
+        .to_socket_addrs()?
+        .map(Addr::Inet)
+        .collect::<Vec<Addr>>();
+
+    if addrs.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} did not resolve to any address", addr),
+        ));
+    }
+
+    Ok(addrs)
+}
+
+/// Result of a background lookup, filled in by its thread once it finishes.
+type Lookup = Arc<Mutex<Option<io::Result<Vec<Addr>>>>>;
+
+/// Name lookups running in the background, so re-resolving a peer never holds up `tick()`.
+#[derive(Default)]
+pub(crate) struct Lookups {
+    running: HashMap<String, Lookup>,
+}
+
+impl Lookups {
+    /// Take the result of the lookup of `addr` an earlier call started, or start one. Returns
+    /// `WouldBlock` while it runs. Socket addresses need no lookup and come back straight away.
+    pub(crate) fn poll(&mut self, addr: &str) -> io::Result<Vec<Addr>> {
+        if let Ok(addr) = SocketAddr::from_str(addr) {
+            return Ok(vec![Addr::Inet(addr)]);
+        }
+
+        if let Some(lookup) = self.running.get(addr) {
+            let result = match lookup.try_lock() {
+                Ok(mut result) => result.take(),
+                Err(_) => None,
+            };
+
+            return match result {
+                Some(result) => {
+                    self.running.remove(addr);
+                    result
+                }
+                None => Err(io::Error::from(io::ErrorKind::WouldBlock)),
+            };
+        }
+
+        let lookup: Lookup = Arc::new(Mutex::new(None));
+        self.running.insert(addr.to_string(), lookup.clone());
+
+        let addr = addr.to_string();
+        thread::spawn(move || {
+            let result = resolve_inet(&addr);
+
+            if let Ok(mut lookup) = lookup.lock() {
+                *lookup = Some(result);
+            }
+        });
+
+        Err(io::Error::from(io::ErrorKind::WouldBlock))
+    }
+}
+
+/// Wildcard address of the same family as `addr`, to bind the local end of a link to it.
+fn unspecified_for(addr: &SocketAddr) -> SocketAddr {
+    match addr {
+        SocketAddr::V4(_) => SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+        SocketAddr::V6(_) => SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)),
+    }
+}
+
+/// Open a transport bound to `addr`, picking the backend from the address scheme. Addresses
+/// without a scheme are UDP.
+pub(crate) fn bind(addr: &str, opt: &SockOpt) -> Result<Box<dyn Transport>, Box<dyn Error>> {
+    let (scheme, addr) = parse(addr)?;
+
+    Ok(match scheme {
+        Scheme::Udp => Box::new(UdpTransport::bind(addr)?),
+        Scheme::Tcp => Box::new(TcpTransport::bind(addr, opt)?),
+        #[cfg(unix)]
+        Scheme::Unix => Box::new(UnixTransport::bind(&PathBuf::from(addr))?),
+        Scheme::Inproc => Box::new(InprocTransport::bind(addr)?),
+    })
+}
+
+/// Open an unconnected transport suitable for reaching `addr`. Returns the address with the
+/// scheme stripped, for the transport to resolve.
+pub(crate) fn connect(
+    addr: &str,
+    opt: &SockOpt,
+) -> Result<(Box<dyn Transport>, String), Box<dyn Error>> {
+    let (scheme, addr) = parse(addr)?;
+
+    let transport: Box<dyn Transport> = match scheme {
+        // Rebound in the family of the peer on connect.
+        Scheme::Udp => Box::new(UdpTransport::ephemeral()?),
+        Scheme::Tcp => Box::new(TcpTransport::new(opt)),
+        #[cfg(unix)]
+        Scheme::Unix => Box::new(UnixTransport::bind_ephemeral()?),
+        Scheme::Inproc => Box::new(InprocTransport::bind_ephemeral()?),
+    };
+
+    Ok((transport, addr.to_string()))
+}
+
+/// Open a transport at an ephemeral address that others can also reach, able to hold
+/// connections to several peers. Returns the address with the scheme stripped.
+pub(crate) fn connect_multi(
+    addr: &str,
+    opt: &SockOpt,
+) -> Result<(Box<dyn Transport>, String), Box<dyn Error>> {
+    let (scheme, addr) = parse(addr)?;
+
+    let transport: Box<dyn Transport> = match scheme {
+        Scheme::Udp => Box::new(UdpTransport::bind(local_for(addr))?),
+        Scheme::Tcp => Box::new(TcpTransport::bind(&local_for(addr).to_string(), opt)?),
+        #[cfg(unix)]
+        Scheme::Unix => Box::new(UnixTransport::bind_ephemeral()?),
+        Scheme::Inproc => Box::new(InprocTransport::bind_ephemeral()?),
+    };
+
+    Ok((transport, addr.to_string()))
+}
+
+/// Wildcard address in the family `addr` resolves to, IPv4 if it doesn't.
+fn local_for(addr: &str) -> SocketAddr {
+    let family = resolve_inet(addr)
+        .ok()
+        .and_then(|addrs| addrs.first().and_then(|addr| inet(addr).ok()));
+
+    match family {
+        Some(peer) => unspecified_for(&peer),
+        None => SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+    }
+}
+
+/// The scheme of an address, UDP if it has none.
+pub(crate) fn scheme(addr: &str) -> Result<Scheme, Box<dyn Error>> {
+    Ok(parse(addr)?.0)
+}
+
+/// Strip the scheme from an address, for a transport that is already open to resolve. When the
+/// scheme the transport was opened with is known, the address must be of the same one.
+pub(crate) fn target(addr: &str, scheme: Option<Scheme>) -> Result<String, Box<dyn Error>> {
+    let (given, target) = parse(addr)?;
+
+    if let Some(scheme) = scheme
+        && given != scheme
+    {
+        return Err(format!("{} doesn't match the transport in use", addr).into());
+    }
+
+    Ok(target.to_string())
+}
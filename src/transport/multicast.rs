@@ -0,0 +1,42 @@
+use std::{
+    io,
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket},
+};
+
+/// A UDP socket publishing to, or subscribed to, an IPv4 multicast group. Carries data frames
+/// only, sessions stay on the unicast transport.
+pub struct Multicast {
+    sock: UdpSocket,
+    group: SocketAddrV4,
+}
+
+impl Multicast {
+    /// Open a socket sending to `group` out of the interface with address `interface`.
+    pub fn publisher(group: SocketAddrV4, interface: Ipv4Addr) -> io::Result<Self> {
+        let sock = UdpSocket::bind((interface, 0))?;
+        sock.set_nonblocking(true)?;
+        sock.set_multicast_loop_v4(true)?;
+
+        Ok(Self { sock, group })
+    }
+
+    /// Bind the group port and join `group` on the interface with address `interface`.
+    ///
+    /// Without `SO_REUSEADDR`, which std doesn't expose, only one subscriber per host can hold
+    /// the group port.
+    pub fn subscriber(group: SocketAddrV4, interface: Ipv4Addr) -> io::Result<Self> {
+        let sock = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, group.port()))?;
+        sock.set_nonblocking(true)?;
+        sock.join_multicast_v4(group.ip(), &interface)?;
+
+        Ok(Self { sock, group })
+    }
+
+    pub fn send(&self, data: &[u8]) -> io::Result<usize> {
+        self.sock.send_to(data, self.group)
+    }
+
+    pub fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.sock.recv_from(buf)
+    }
+}
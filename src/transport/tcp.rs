@@ -0,0 +1,327 @@
+use std::{
+    collections::HashMap,
+    io::{self, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use super::{Addr, Lookups, Transport, inet, resolve_inet};
+use crate::SockOpt;
+
+// Frames travel over a stream as a 4 byte big-endian length followed by the encoded frame.
+const LENGTH_PREFIX_SIZE: usize = 4;
+
+// A stream announcing a frame larger than this is out of sync or hostile, and gets dropped.
+const MAX_STREAM_FRAME_SIZE: usize = 1 << 17;
+
+// Bytes buffered for a slow reader before sends to it start returning WouldBlock.
+const MAX_PENDING_WRITE: usize = 1 << 20;
+
+struct Stream {
+    sock: TcpStream,
+    read_buf: Vec<u8>,
+    read_pos: usize,
+    write_buf: Vec<u8>,
+    closed: bool,
+}
+
+impl Stream {
+    fn new(sock: TcpStream) -> io::Result<Self> {
+        sock.set_nonblocking(true)?;
+        sock.set_nodelay(true)?;
+
+        Ok(Self {
+            sock,
+            read_buf: Vec::new(),
+            read_pos: 0,
+            write_buf: Vec::new(),
+            closed: false,
+        })
+    }
+
+    /// Pull everything the kernel has buffered for this stream.
+    fn fill(&mut self) {
+        self.read_buf.drain(..self.read_pos);
+        self.read_pos = 0;
+
+        let mut chunk = [0u8; 4096];
+
+        loop {
+            match self.sock.read(&mut chunk) {
+                Ok(0) => {
+                    self.closed = true;
+                    return;
+                }
+                Ok(n) => self.read_buf.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(_) => {
+                    self.closed = true;
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Copy the next complete frame into `buf`, truncating it like a datagram socket would.
+    fn next_frame(&mut self, buf: &mut [u8]) -> Option<usize> {
+        let pending = &self.read_buf[self.read_pos..];
+
+        if pending.len() < LENGTH_PREFIX_SIZE {
+            return None;
+        }
+
+        let len = u32::from_be_bytes(pending[..LENGTH_PREFIX_SIZE].try_into().ok()?) as usize;
+
+        if len > MAX_STREAM_FRAME_SIZE {
+            self.closed = true;
+            return None;
+        }
+
+        if pending.len() < LENGTH_PREFIX_SIZE + len {
+            return None;
+        }
+
+        let n = len.min(buf.len());
+        buf[..n].copy_from_slice(&pending[LENGTH_PREFIX_SIZE..LENGTH_PREFIX_SIZE + n]);
+        self.read_pos += LENGTH_PREFIX_SIZE + len;
+
+        Some(n)
+    }
+
+    /// Write as much of the pending output as the kernel will take without blocking.
+    fn flush(&mut self) -> io::Result<()> {
+        while !self.write_buf.is_empty() {
+            match self.sock.write(&self.write_buf) {
+                Ok(0) => {
+                    self.closed = true;
+                    return Err(io::Error::from(io::ErrorKind::WriteZero));
+                }
+                Ok(n) => {
+                    self.write_buf.drain(..n);
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    self.closed = true;
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// An outgoing connection still being established in the background. Frames sent meanwhile wait
+/// in `write_buf` and go out as soon as it's up.
+#[derive(Default)]
+struct Connecting {
+    write_buf: Vec<u8>,
+    result: Option<io::Result<Stream>>,
+}
+
+/// Length-prefixed framing over non-blocking `TcpStream`s, one stream per peer address.
+pub struct TcpTransport {
+    listener: Option<TcpListener>,
+    streams: HashMap<SocketAddr, Stream>,
+    connecting: HashMap<SocketAddr, Arc<Mutex<Connecting>>>,
+    connect_timeout: Duration,
+    lookups: Lookups,
+}
+
+impl TcpTransport {
+    pub fn new(opt: &SockOpt) -> Self {
+        Self {
+            listener: None,
+            streams: HashMap::new(),
+            connecting: HashMap::new(),
+            connect_timeout: opt.connect_timeout,
+            lookups: Lookups::default(),
+        }
+    }
+
+    pub fn bind(addr: &str, opt: &SockOpt) -> io::Result<Self> {
+        let mut transport = TcpTransport::new(opt);
+        Transport::bind(&mut transport, addr)?;
+
+        Ok(transport)
+    }
+
+    /// Take up the connections that finished establishing since the last call, and drop those
+    /// that failed.
+    fn poll_connecting(&mut self) {
+        let mut done = vec![];
+
+        for (addr, connecting) in self.connecting.iter() {
+            if let Ok(mut connecting) = connecting.try_lock()
+                && let Some(result) = connecting.result.take()
+            {
+                done.push((*addr, result));
+            }
+        }
+
+        for (addr, result) in done {
+            self.connecting.remove(&addr);
+
+            if let Ok(stream) = result {
+                self.streams.insert(addr, stream);
+            }
+        }
+    }
+
+    fn accept(&mut self) {
+        let Some(listener) = &self.listener else {
+            return;
+        };
+
+        while let Ok((sock, addr)) = listener.accept() {
+            if let Ok(stream) = Stream::new(sock) {
+                self.streams.insert(addr, stream);
+            }
+        }
+    }
+}
+
+impl Transport for TcpTransport {
+    fn bind(&mut self, addr: &str) -> io::Result<()> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+
+        self.listener = Some(listener);
+        Ok(())
+    }
+
+    fn resolve(&self, addr: &str) -> io::Result<Vec<Addr>> {
+        resolve_inet(addr)
+    }
+
+    fn reresolve(&mut self, addr: &str) -> io::Result<Vec<Addr>> {
+        self.lookups.poll(addr)
+    }
+
+    /// Start connecting in the background and return straight away, so a peer that doesn't
+    /// answer never holds up `tick()`. The attempt gives up after `connect_timeout`.
+    fn connect(&mut self, addr: &Addr) -> io::Result<()> {
+        let addr = inet(addr)?;
+        self.streams.remove(&addr);
+
+        let connecting = Arc::new(Mutex::new(Connecting::default()));
+        self.connecting.insert(addr, connecting.clone());
+
+        let connect_timeout = self.connect_timeout;
+        thread::spawn(move || {
+            let result = TcpStream::connect_timeout(&addr, connect_timeout).and_then(Stream::new);
+
+            let Ok(mut connecting) = connecting.lock() else {
+                return;
+            };
+
+            connecting.result = Some(result.map(|mut stream| {
+                stream.write_buf = std::mem::take(&mut connecting.write_buf);
+                let _ = stream.flush();
+                stream
+            }));
+        });
+
+        Ok(())
+    }
+
+    fn send_to(&mut self, data: &[u8], addr: &Addr) -> io::Result<usize> {
+        let addr = inet(addr)?;
+        self.poll_connecting();
+
+        let Some(stream) = self.streams.get_mut(&addr) else {
+            let Some(connecting) = self.connecting.get(&addr) else {
+                return Err(io::Error::from(io::ErrorKind::NotConnected));
+            };
+
+            let Ok(mut connecting) = connecting.lock() else {
+                return Err(io::Error::from(io::ErrorKind::NotConnected));
+            };
+
+            if connecting.write_buf.len() + LENGTH_PREFIX_SIZE + data.len() > MAX_PENDING_WRITE {
+                return Err(io::Error::from(io::ErrorKind::WouldBlock));
+            }
+
+            // Finished between the poll above and taking the lock, picked up next call.
+            if let Some(Ok(stream)) = connecting.result.as_mut() {
+                stream
+                    .write_buf
+                    .extend_from_slice(&(data.len() as u32).to_be_bytes());
+                stream.write_buf.extend_from_slice(data);
+                return Ok(data.len());
+            }
+
+            connecting
+                .write_buf
+                .extend_from_slice(&(data.len() as u32).to_be_bytes());
+            connecting.write_buf.extend_from_slice(data);
+
+            return Ok(data.len());
+        };
+
+        if stream.closed {
+            self.streams.remove(&addr);
+            return Err(io::Error::from(io::ErrorKind::NotConnected));
+        }
+
+        if stream.write_buf.len() + LENGTH_PREFIX_SIZE + data.len() > MAX_PENDING_WRITE {
+            return Err(io::Error::from(io::ErrorKind::WouldBlock));
+        }
+
+        stream
+            .write_buf
+            .extend_from_slice(&(data.len() as u32).to_be_bytes());
+        stream.write_buf.extend_from_slice(data);
+
+        if let Err(e) = stream.flush() {
+            self.streams.remove(&addr);
+            return Err(e);
+        }
+
+        Ok(data.len())
+    }
+
+    fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, Addr)> {
+        self.poll_connecting();
+        self.accept();
+
+        for (addr, stream) in self.streams.iter_mut() {
+            let _ = stream.flush();
+
+            if let Some(n) = stream.next_frame(buf) {
+                return Ok((n, Addr::Inet(*addr)));
+            }
+
+            if !stream.closed {
+                stream.fill();
+            }
+
+            if let Some(n) = stream.next_frame(buf) {
+                return Ok((n, Addr::Inet(*addr)));
+            }
+        }
+
+        self.streams.retain(|_, stream| !stream.closed);
+
+        Err(io::Error::from(io::ErrorKind::WouldBlock))
+    }
+
+    fn local_addr(&self) -> io::Result<Addr> {
+        if let Some(listener) = &self.listener {
+            return Ok(Addr::Inet(listener.local_addr()?));
+        }
+
+        match self.streams.values().next() {
+            Some(stream) => Ok(Addr::Inet(stream.sock.local_addr()?)),
+            None => Err(io::Error::from(io::ErrorKind::NotConnected)),
+        }
+    }
+
+    fn max_frame_size(&self) -> usize {
+        MAX_STREAM_FRAME_SIZE
+    }
+}
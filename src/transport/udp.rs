@@ -0,0 +1,114 @@
+use std::{
+    io,
+    net::{Ipv4Addr, SocketAddr, ToSocketAddrs, UdpSocket},
+};
+
+use super::{Addr, Lookups, Transport, inet, resolve_inet, unspecified_for};
+
+// Largest payload an IPv4 UDP datagram can carry.
+const MAX_UDP_FRAME_SIZE: usize = 65507;
+
+pub struct UdpTransport {
+    sock: UdpSocket,
+    connected: Option<SocketAddr>,
+    /// Bound to be reached at, so `connect` never pins the socket to a single peer.
+    listening: bool,
+    lookups: Lookups,
+}
+
+impl UdpTransport {
+    /// Bind a UDP socket. Binding `[::]` gives a dual-stack socket where the OS allows it, with
+    /// IPv4 peers showing up as IPv4-mapped IPv6 addresses.
+    ///
+    /// A bound socket keeps taking datagrams from anyone after `connect`, so it can hold
+    /// several connections at once.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        let sock = UdpSocket::bind(addr)?;
+        sock.set_nonblocking(true)?;
+
+        Ok(Self {
+            sock,
+            connected: None,
+            listening: true,
+            lookups: Lookups::default(),
+        })
+    }
+
+    /// Open a socket at an ephemeral port for reaching a single peer. `connect` moves it to the
+    /// peer's address family and connects it at the OS level.
+    pub fn ephemeral() -> io::Result<Self> {
+        Ok(Self {
+            listening: false,
+            ..UdpTransport::bind((Ipv4Addr::UNSPECIFIED, 0))?
+        })
+    }
+}
+
+impl Transport for UdpTransport {
+    fn bind(&mut self, addr: &str) -> io::Result<()> {
+        *self = UdpTransport::bind(addr)?;
+        Ok(())
+    }
+
+    fn resolve(&self, addr: &str) -> io::Result<Vec<Addr>> {
+        resolve_inet(addr)
+    }
+
+    fn reresolve(&mut self, addr: &str) -> io::Result<Vec<Addr>> {
+        self.lookups.poll(addr)
+    }
+
+    fn connect(&mut self, addr: &Addr) -> io::Result<()> {
+        let addr = inet(addr)?;
+
+        if self.listening {
+            if self.sock.local_addr()?.is_ipv4() != addr.is_ipv4() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "address family differs from the bound socket",
+                ));
+            }
+
+            return Ok(());
+        }
+
+        // Hostnames can resolve to either family, move the socket over to the one being tried.
+        if self.sock.local_addr()?.is_ipv4() != addr.is_ipv4() {
+            *self = UdpTransport {
+                listening: false,
+                ..UdpTransport::bind(unspecified_for(&addr))?
+            };
+        }
+
+        self.sock.connect(addr)?;
+        self.connected = Some(addr);
+
+        Ok(())
+    }
+
+    fn send_to(&mut self, data: &[u8], addr: &Addr) -> io::Result<usize> {
+        let addr = inet(addr)?;
+
+        match self.connected {
+            Some(connected) if connected == addr => self.sock.send(data),
+            _ => self.sock.send_to(data, addr),
+        }
+    }
+
+    fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, Addr)> {
+        let (n, addr) = match self.connected {
+            Some(connected) => (self.sock.recv(buf)?, connected),
+            None => self.sock.recv_from(buf)?,
+        };
+
+        Ok((n, Addr::Inet(addr)))
+    }
+
+    fn local_addr(&self) -> io::Result<Addr> {
+        Ok(Addr::Inet(self.sock.local_addr()?))
+    }
+
+    fn max_frame_size(&self) -> usize {
+        MAX_UDP_FRAME_SIZE
+    }
+}
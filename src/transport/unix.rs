@@ -0,0 +1,127 @@
+use std::{
+    env, fs, io,
+    os::unix::{fs::FileTypeExt, net::UnixDatagram},
+    path::{Path, PathBuf},
+    process,
+};
+
+use super::{Addr, Transport};
+use crate::random;
+
+// Unix datagrams aren't bound by an MTU, only by the socket send buffer.
+const MAX_UNIX_FRAME_SIZE: usize = 1 << 17;
+
+/// Datagrams over a `UnixDatagram` socket, for processes on the same host.
+pub struct UnixTransport {
+    sock: UnixDatagram,
+    path: PathBuf,
+    connected: Option<PathBuf>,
+}
+
+impl UnixTransport {
+    /// Bind at `path`, replacing any socket file left behind by a previous process. Anything else
+    /// at the path, a socket still bound by a live process included, is left alone and the bind
+    /// fails with `AddrInUse`.
+    pub fn bind(path: &Path) -> io::Result<Self> {
+        if let Ok(meta) = fs::symlink_metadata(path) {
+            if !meta.file_type().is_socket() || !is_stale(path) {
+                return Err(io::Error::from(io::ErrorKind::AddrInUse));
+            }
+
+            fs::remove_file(path)?;
+        }
+
+        let sock = UnixDatagram::bind(path)?;
+        sock.set_nonblocking(true)?;
+
+        Ok(Self {
+            sock,
+            path: path.to_path_buf(),
+            connected: None,
+        })
+    }
+
+    /// Bind at a fresh path in the temp directory, so the peer has somewhere to reply to.
+    pub fn bind_ephemeral() -> io::Result<Self> {
+        let path = env::temp_dir().join(format!(
+            "nbmq-{}-{:016x}.sock",
+            process::id(),
+            random::entropy()
+        ));
+
+        UnixTransport::bind(&path)
+    }
+}
+
+/// Whether the socket file at `path` was left behind, nobody receiving on it anymore.
+fn is_stale(path: &Path) -> bool {
+    match UnixDatagram::unbound().and_then(|probe| probe.connect(path)) {
+        Err(e) => e.kind() == io::ErrorKind::ConnectionRefused,
+        Ok(_) => false,
+    }
+}
+
+fn unix_path(addr: &Addr) -> io::Result<&Path> {
+    match addr {
+        Addr::Unix(path) => Ok(path),
+        _ => Err(io::Error::from(io::ErrorKind::InvalidInput)),
+    }
+}
+
+impl Transport for UnixTransport {
+    fn bind(&mut self, addr: &str) -> io::Result<()> {
+        *self = UnixTransport::bind(Path::new(addr))?;
+        Ok(())
+    }
+
+    fn resolve(&self, addr: &str) -> io::Result<Vec<Addr>> {
+        Ok(vec![Addr::Unix(PathBuf::from(addr))])
+    }
+
+    fn connect(&mut self, addr: &Addr) -> io::Result<()> {
+        let path = unix_path(addr)?;
+
+        self.sock.connect(path)?;
+        self.connected = Some(path.to_path_buf());
+
+        Ok(())
+    }
+
+    fn send_to(&mut self, data: &[u8], addr: &Addr) -> io::Result<usize> {
+        let path = unix_path(addr)?;
+
+        match &self.connected {
+            Some(connected) if connected == path => self.sock.send(data),
+            _ => self.sock.send_to(data, path),
+        }
+    }
+
+    fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, Addr)> {
+        if let Some(connected) = &self.connected {
+            return Ok((self.sock.recv(buf)?, Addr::Unix(connected.clone())));
+        }
+
+        loop {
+            let (n, addr) = self.sock.recv_from(buf)?;
+
+            // Unbound senders can't be replied to, so there's no session to be had with them.
+            if let Some(path) = addr.as_pathname() {
+                return Ok((n, Addr::Unix(path.to_path_buf())));
+            }
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Addr> {
+        Ok(Addr::Unix(self.path.clone()))
+    }
+
+    fn max_frame_size(&self) -> usize {
+        MAX_UNIX_FRAME_SIZE
+    }
+}
+
+impl Drop for UnixTransport {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
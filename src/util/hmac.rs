@@ -0,0 +1,61 @@
+use crate::sha256::{BLOCK_SIZE, DIGEST_SIZE, Sha256};
+
+/// HMAC-SHA256 (RFC 2104).
+#[derive(Clone)]
+pub struct HmacSha256 {
+    inner: Sha256,
+    outer: Sha256,
+}
+
+impl HmacSha256 {
+    pub fn new(key: &[u8]) -> Self {
+        let mut block_key = [0u8; BLOCK_SIZE];
+
+        if key.len() > BLOCK_SIZE {
+            block_key[..DIGEST_SIZE].copy_from_slice(&Sha256::digest(key));
+        } else {
+            block_key[..key.len()].copy_from_slice(key);
+        }
+
+        let mut inner = Sha256::new();
+        inner.update(&block_key.map(|b| b ^ 0x36));
+
+        let mut outer = Sha256::new();
+        outer.update(&block_key.map(|b| b ^ 0x5c));
+
+        Self { inner, outer }
+    }
+
+    pub fn mac(key: &[u8], data: &[u8]) -> [u8; DIGEST_SIZE] {
+        let mut hmac = HmacSha256::new(key);
+        hmac.update(data);
+        hmac.finalize()
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    pub fn finalize(self) -> [u8; DIGEST_SIZE] {
+        let mut outer = self.outer;
+        outer.update(&self.inner.finalize());
+        outer.finalize()
+    }
+
+    /// Check `tag` against the mac of `data` in constant time.
+    pub fn verify(key: &[u8], data: &[u8], tag: &[u8]) -> bool {
+        ct_eq(&HmacSha256::mac(key, data), tag)
+    }
+}
+
+/// Compare two byte strings without short circuiting on the first difference.
+pub fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter()
+        .zip(b.iter())
+        .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+        == 0
+}
@@ -1,3 +1,5 @@
 pub mod hash;
+pub mod hmac;
 pub mod random;
+pub mod sha256;
 pub mod ts;
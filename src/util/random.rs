@@ -1,3 +1,8 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
 pub struct XORShift {
     state: u64,
 }
@@ -15,3 +20,9 @@ impl XORShift {
         return self.state.wrapping_mul(0x2545F4914F6CDD1D);
     }
 }
+
+/// Draw a u64 from the randomly keyed hasher std seeds from the OS, for secrets that shouldn't be
+/// predictable from a timestamp.
+pub fn entropy() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
@@ -0,0 +1,123 @@
+use std::{error::Error, thread, time::Duration};
+
+use nbmq::{AsSocket, Bus, Socket};
+
+fn sleep(n: f64) {
+    thread::sleep(Duration::from_secs_f64(n));
+}
+
+fn settle(nodes: &mut [&mut Bus]) -> Result<(), Box<dyn Error>> {
+    for _ in 0..4 {
+        sleep(0.01);
+
+        for node in nodes.iter_mut() {
+            node.tick()?;
+        }
+    }
+
+    Ok(())
+}
+
+fn drain(node: &mut Bus) -> Vec<Vec<u8>> {
+    let mut received = vec![];
+    while let Ok(msg) = node.recv() {
+        received.push(msg[0].clone());
+    }
+    received.sort();
+
+    received
+}
+
+#[test]
+fn full_mesh_hears_everyone() -> Result<(), Box<dyn Error>> {
+    let mut a = Socket::<Bus>::new().bind("127.0.0.1:3600")?;
+    let mut b = Socket::<Bus>::new().bind("127.0.0.1:3601")?;
+    b.connect_to("127.0.0.1:3600")?;
+
+    let mut c = Socket::<Bus>::new().connect("127.0.0.1:3600")?;
+    c.connect_to("127.0.0.1:3601")?;
+
+    settle(&mut [&mut a, &mut b, &mut c])?;
+    assert!(a.peers() == 2 && b.peers() == 2 && c.peers() == 2);
+
+    a.send(&["a".as_bytes()])?;
+    b.send(&["b".as_bytes()])?;
+    c.send(&["c".as_bytes()])?;
+    settle(&mut [&mut a, &mut b, &mut c])?;
+
+    assert!(drain(&mut a) == vec![b"b".to_vec(), b"c".to_vec()]);
+    assert!(drain(&mut b) == vec![b"a".to_vec(), b"c".to_vec()]);
+    assert!(drain(&mut c) == vec![b"a".to_vec(), b"b".to_vec()]);
+
+    Ok(())
+}
+
+#[test]
+fn connections_reconnect_independently() -> Result<(), Box<dyn Error>> {
+    let mut a = Socket::<Bus>::new().bind("127.0.0.1:3602")?;
+
+    let mut c = Socket::<Bus>::new()
+        .set_reconnect_wait(0.01)
+        .connect("127.0.0.1:3602")?;
+    c.connect_to("127.0.0.1:3603")?;
+
+    settle(&mut [&mut a, &mut c])?;
+    assert!(c.peers() == 1);
+
+    // Late to the mesh, picked up by the retries of the second connection alone.
+    let mut b = Socket::<Bus>::new().bind("127.0.0.1:3603")?;
+    settle(&mut [&mut a, &mut b, &mut c])?;
+    assert!(c.peers() == 2 && a.peers() == 1 && b.peers() == 1);
+
+    c.send(&["hello".as_bytes()])?;
+    settle(&mut [&mut a, &mut b, &mut c])?;
+
+    assert!(a.recv()? == vec!["hello".as_bytes().to_vec()]);
+    assert!(b.recv()? == vec!["hello".as_bytes().to_vec()]);
+
+    Ok(())
+}
+
+#[test]
+fn bound_bus_accepts_while_connected_out() -> Result<(), Box<dyn Error>> {
+    let mut a = Socket::<Bus>::new().bind("127.0.0.1:3604")?;
+    let mut b = Socket::<Bus>::new().bind("127.0.0.1:3605")?;
+    a.connect_to("127.0.0.1:3605")?;
+
+    let mut c = Socket::<Bus>::new().connect("127.0.0.1:3604")?;
+    settle(&mut [&mut a, &mut b, &mut c])?;
+    assert!(a.peers() == 2);
+
+    a.send(&["both".as_bytes()])?;
+    settle(&mut [&mut a, &mut b, &mut c])?;
+
+    assert!(b.recv()? == vec!["both".as_bytes().to_vec()]);
+    assert!(c.recv()? == vec!["both".as_bytes().to_vec()]);
+
+    Ok(())
+}
+
+#[test]
+fn connect_to_rejects_another_transport() -> Result<(), Box<dyn Error>> {
+    let mut a = Socket::<Bus>::new().bind("127.0.0.1:3606")?;
+
+    assert!(a.connect_to("tcp://127.0.0.1:3607").is_err());
+    assert!(a.connect_to("udp://127.0.0.1:3607").is_ok());
+
+    Ok(())
+}
+
+#[test]
+fn tcp_bus_connects_over_ipv6() -> Result<(), Box<dyn Error>> {
+    let mut a = Socket::<Bus>::new().bind("tcp://[::1]:3608")?;
+    let mut b = Socket::<Bus>::new().connect("tcp://[::1]:3608")?;
+    settle(&mut [&mut a, &mut b])?;
+    assert!(a.peers() == 1);
+
+    b.send(&["over v6".as_bytes()])?;
+    settle(&mut [&mut a, &mut b])?;
+
+    assert!(a.recv()? == vec!["over v6".as_bytes().to_vec()]);
+
+    Ok(())
+}
@@ -0,0 +1,75 @@
+#![allow(dead_code)]
+
+use std::io;
+
+use nbmq::{Addr, Transport, transport::UdpTransport};
+
+/// UDP losing some of the frames of one kind it receives, either every `every`th one or the
+/// first `first`.
+pub struct Lossy {
+    inner: UdpTransport,
+    kind: u8,
+    every: usize,
+    first: usize,
+    seen: usize,
+}
+
+impl Lossy {
+    /// Lose every `every`th frame of `kind`.
+    pub fn every(kind: u8, every: usize) -> io::Result<Self> {
+        Ok(Self {
+            inner: UdpTransport::bind("0.0.0.0:0")?,
+            kind,
+            every,
+            first: 0,
+            seen: 0,
+        })
+    }
+
+    /// Lose the first `first` frames of `kind`.
+    pub fn first(kind: u8, first: usize) -> io::Result<Self> {
+        Ok(Self {
+            inner: UdpTransport::bind("0.0.0.0:0")?,
+            kind,
+            every: 0,
+            first,
+            seen: 0,
+        })
+    }
+
+    fn lose(&mut self) -> bool {
+        self.seen += 1;
+
+        self.seen <= self.first || (self.every > 0 && self.seen.is_multiple_of(self.every))
+    }
+}
+
+impl Transport for Lossy {
+    fn bind(&mut self, addr: &str) -> io::Result<()> {
+        self.inner.bind(addr)
+    }
+
+    fn connect(&mut self, addr: &Addr) -> io::Result<()> {
+        self.inner.connect(addr)
+    }
+
+    fn send_to(&mut self, data: &[u8], addr: &Addr) -> io::Result<usize> {
+        self.inner.send_to(data, addr)
+    }
+
+    fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, Addr)> {
+        loop {
+            let (n, addr) = self.inner.recv_from(buf)?;
+
+            if n >= 2 && buf[1] == self.kind && self.lose() {
+                continue;
+            }
+
+            return Ok((n, addr));
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Addr> {
+        self.inner.local_addr()
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    error::Error,
+    io,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use nbmq::{
+    Addr, AsSocket, SafeDealer, Socket, Transport, frame::SEQUENCED_DATA_KIND,
+    queue::congestion::INITIAL_CWND, transport::UdpTransport,
+};
+
+fn sleep(n: f64) {
+    thread::sleep(Duration::from_secs_f64(n));
+}
+
+const SACK: u8 = 13;
+
+/// UDP counting the frames of one kind it receives, and losing them all if `lose` is set.
+struct Filter {
+    inner: UdpTransport,
+    kind: u8,
+    lose: bool,
+    count: Arc<AtomicUsize>,
+}
+
+impl Filter {
+    fn new(kind: u8, lose: bool) -> io::Result<(Self, Arc<AtomicUsize>)> {
+        let count = Arc::new(AtomicUsize::new(0));
+
+        Ok((
+            Self {
+                inner: UdpTransport::bind("0.0.0.0:0")?,
+                kind,
+                lose,
+                count: count.clone(),
+            },
+            count,
+        ))
+    }
+}
+
+impl Transport for Filter {
+    fn bind(&mut self, addr: &str) -> io::Result<()> {
+        self.inner.bind(addr)
+    }
+
+    fn connect(&mut self, addr: &Addr) -> io::Result<()> {
+        self.inner.connect(addr)
+    }
+
+    fn send_to(&mut self, data: &[u8], addr: &Addr) -> io::Result<usize> {
+        self.inner.send_to(data, addr)
+    }
+
+    fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, Addr)> {
+        loop {
+            let (n, addr) = self.inner.recv_from(buf)?;
+
+            if n >= 2 && buf[1] == self.kind {
+                self.count.fetch_add(1, Ordering::SeqCst);
+                if self.lose {
+                    continue;
+                }
+            }
+
+            return Ok((n, addr));
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Addr> {
+        self.inner.local_addr()
+    }
+}
+
+fn large_bin() -> Vec<u8> {
+    let mut large_bin = vec![0u8; 20000];
+    large_bin.push(1);
+    large_bin
+}
+
+fn cwnd(socket: &SafeDealer) -> usize {
+    socket
+        .stats()
+        .values()
+        .next()
+        .map(|stats| stats.cwnd)
+        .unwrap_or(0)
+}
+
+#[test]
+fn window_limits_the_first_burst_then_grows() -> Result<(), Box<dyn Error>> {
+    let (transport, data_frames) = Filter::new(SEQUENCED_DATA_KIND, false)?;
+    let mut server = Socket::<SafeDealer>::new().bind_with(transport, "127.0.0.1:9100")?;
+    let mut client = Socket::<SafeDealer>::new()
+        .set_congestion_control(true)
+        .connect("127.0.0.1:9100")?;
+
+    for _ in 0..2 {
+        sleep(0.01);
+        server.tick()?;
+        client.tick()?;
+    }
+
+    let large_bin = large_bin();
+    client.send(&[&large_bin])?;
+    client.tick()?;
+
+    sleep(0.01);
+    server.tick()?;
+    assert!(data_frames.load(Ordering::SeqCst) == INITIAL_CWND as usize);
+
+    for _ in 0..6 {
+        sleep(0.01);
+        client.tick()?;
+        server.tick()?;
+    }
+
+    assert!(server.recv()? == vec![large_bin]);
+    assert!(cwnd(&client) > INITIAL_CWND as usize);
+
+    Ok(())
+}
+
+#[test]
+fn losses_shrink_the_window() -> Result<(), Box<dyn Error>> {
+    let mut server = Socket::<SafeDealer>::new().bind("127.0.0.1:9101")?;
+
+    // Never hears an ack, every frame times out.
+    let (transport, _) = Filter::new(SACK, true)?;
+    let mut client = Socket::<SafeDealer>::new()
+        .set_congestion_control(true)
+        .set_safe_resend_ivl(0.01)
+        .connect_with(transport, "127.0.0.1:9101")?;
+
+    for _ in 0..2 {
+        sleep(0.01);
+        server.tick()?;
+        client.tick()?;
+    }
+
+    client.send(&[&large_bin()])?;
+    for _ in 0..4 {
+        sleep(0.02);
+        client.tick()?;
+        server.tick()?;
+    }
+
+    assert!(cwnd(&client) < INITIAL_CWND as usize);
+
+    Ok(())
+}
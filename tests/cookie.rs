@@ -0,0 +1,195 @@
+use std::{error::Error, net::UdpSocket, thread, time::Duration};
+
+use nbmq::{AsSocket, Bus, Dealer, Socket, frame::ControlFrame};
+
+fn sleep(n: f64) {
+    thread::sleep(Duration::from_secs_f64(n));
+}
+
+#[test]
+fn cookie_handshake_connects() -> Result<(), Box<dyn Error>> {
+    let mut server = Socket::<Dealer>::new()
+        .set_cookie_challenge(true)
+        .bind("0.0.0.0:7200")?;
+    let mut client = Socket::<Dealer>::new().connect("127.0.0.1:7200")?;
+
+    sleep(0.01);
+    server.tick()?; // server answers Connect with a cookie
+    assert!(server.peers() == 0);
+
+    sleep(0.01);
+    client.tick()?; // client echoes the cookie
+
+    sleep(0.01);
+    server.tick()?; // server allocates the session
+    assert!(server.peers() == 1);
+
+    sleep(0.01);
+    client.tick()?;
+    assert!(client.peers() == 1);
+
+    client.send(&["hello".as_bytes()])?;
+    client.tick()?;
+
+    sleep(0.01);
+    server.tick()?;
+    assert!(server.recv()?[0] == "hello".as_bytes());
+
+    Ok(())
+}
+
+#[test]
+fn connect_flood_allocates_nothing() -> Result<(), Box<dyn Error>> {
+    let mut server = Socket::<Dealer>::new()
+        .set_cookie_challenge(true)
+        .bind("0.0.0.0:7201")?;
+
+    let flood = UdpSocket::bind("127.0.0.1:0")?;
+    for _ in 0..100 {
+        flood.send_to(&ControlFrame::Connect.encode(), "127.0.0.1:7201")?;
+    }
+
+    sleep(0.01);
+    server.tick()?;
+    assert!(server.peers() == 0);
+
+    Ok(())
+}
+
+#[test]
+fn forged_and_misdirected_cookies_are_rejected() -> Result<(), Box<dyn Error>> {
+    let mut server = Socket::<Dealer>::new()
+        .set_cookie_challenge(true)
+        .bind("0.0.0.0:7202")?;
+
+    let forger = UdpSocket::bind("127.0.0.1:0")?;
+    forger.set_read_timeout(Some(Duration::from_secs_f64(0.1)))?;
+    forger.send_to(
+        &ControlFrame::CookieEcho(vec![0u8; 24]).encode(),
+        "127.0.0.1:7202",
+    )?;
+
+    // Obtain a real cookie, then echo it from another address.
+    forger.send_to(&ControlFrame::Connect.encode(), "127.0.0.1:7202")?;
+    sleep(0.01);
+    server.tick()?;
+
+    let mut buf = [0u8; 512];
+    let n = forger.recv(&mut buf)?;
+    let Some(ControlFrame::Cookie(cookie)) = ControlFrame::parse(&buf[..n])? else {
+        panic!("expected a cookie");
+    };
+
+    let other = UdpSocket::bind("127.0.0.1:0")?;
+    other.send_to(&ControlFrame::CookieEcho(cookie).encode(), "127.0.0.1:7202")?;
+
+    sleep(0.01);
+    server.tick()?;
+    assert!(server.peers() == 0);
+
+    Ok(())
+}
+
+#[test]
+fn expired_cookie_is_rejected() -> Result<(), Box<dyn Error>> {
+    let mut server = Socket::<Dealer>::new()
+        .set_cookie_challenge(true)
+        .set_cookie_ttl(0.01)
+        .bind("0.0.0.0:7203")?;
+    let mut client = Socket::<Dealer>::new().connect("127.0.0.1:7203")?;
+
+    sleep(0.01);
+    server.tick()?;
+
+    sleep(0.05);
+    client.tick()?;
+
+    sleep(0.01);
+    server.tick()?;
+    assert!(server.peers() == 0);
+
+    Ok(())
+}
+
+#[test]
+fn max_peers_caps_sessions() -> Result<(), Box<dyn Error>> {
+    let mut server = Socket::<Dealer>::new()
+        .set_max_peers(2)
+        .bind("0.0.0.0:7204")?;
+    let _a = Socket::<Dealer>::new().connect("127.0.0.1:7204")?;
+    let _b = Socket::<Dealer>::new().connect("127.0.0.1:7204")?;
+    let _c = Socket::<Dealer>::new().connect("127.0.0.1:7204")?;
+
+    sleep(0.01);
+    server.tick()?;
+    assert!(server.peers() == 2);
+
+    Ok(())
+}
+
+#[test]
+fn cookies_are_only_echoed_to_the_server_connected_to() -> Result<(), Box<dyn Error>> {
+    let server = UdpSocket::bind("127.0.0.1:7205")?;
+    server.set_read_timeout(Some(Duration::from_secs_f64(0.1)))?;
+
+    // A bound socket connecting out takes datagrams from anyone.
+    let mut client = Socket::<Bus>::new().bind("127.0.0.1:7206")?;
+    client.connect_to("127.0.0.1:7205")?;
+
+    let stranger = UdpSocket::bind("127.0.0.1:0")?;
+    stranger.set_read_timeout(Some(Duration::from_secs_f64(0.1)))?;
+    stranger.send_to(
+        &ControlFrame::Cookie(vec![1u8; 24]).encode(),
+        "127.0.0.1:7206",
+    )?;
+
+    sleep(0.01);
+    client.tick()?;
+
+    let mut buf = [0u8; 512];
+    assert!(stranger.recv(&mut buf).is_err());
+
+    server.send_to(
+        &ControlFrame::Cookie(vec![2u8; 24]).encode(),
+        "127.0.0.1:7206",
+    )?;
+
+    sleep(0.01);
+    client.tick()?;
+
+    loop {
+        let n = server.recv(&mut buf)?;
+        if let Some(ControlFrame::CookieEcho(cookie)) = ControlFrame::parse(&buf[..n])? {
+            assert!(cookie == vec![2u8; 24]);
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn unsolicited_connected_admits_nothing() -> Result<(), Box<dyn Error>> {
+    let mut server = Socket::<Dealer>::new()
+        .set_cookie_challenge(true)
+        .set_max_peers(2)
+        .bind("0.0.0.0:7207")?;
+
+    let spoofer = UdpSocket::bind("127.0.0.1:0")?;
+    spoofer.set_read_timeout(Some(Duration::from_secs_f64(0.1)))?;
+    for session_id in 1..=50u64 {
+        spoofer.send_to(
+            &ControlFrame::Connected(session_id).encode(),
+            "127.0.0.1:7207",
+        )?;
+    }
+
+    sleep(0.01);
+    server.tick()?;
+    assert!(server.peers() == 0);
+
+    let mut buf = [0u8; 512];
+    assert!(spoofer.recv(&mut buf).is_err());
+
+    Ok(())
+}
@@ -0,0 +1,145 @@
+use std::{error::Error, hash::Hasher, thread, time::Duration};
+
+use nbmq::{AsSocket, Dealer, Dish, Encryptor, Radio, SafeDealer, Socket, hash::Fnv1a64};
+
+fn sleep(n: f64) {
+    thread::sleep(Duration::from_secs_f64(n));
+}
+
+// Toy authenticated cipher, xor with a key byte and append an 8 byte keyed checksum.
+struct XorEncryptor {
+    key: u8,
+}
+
+impl XorEncryptor {
+    fn tag(&self, session_id: u64, header: &[u8], payload: &[u8]) -> [u8; 8] {
+        let mut hasher = Fnv1a64::new();
+        hasher.write(&[self.key]);
+        hasher.write(&session_id.to_be_bytes());
+        hasher.write(header);
+        hasher.write(payload);
+        hasher.finish().to_be_bytes()
+    }
+}
+
+impl Encryptor for XorEncryptor {
+    fn encrypt(
+        &self,
+        session_id: u64,
+        header: &[u8],
+        payload: &[u8],
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut sealed = payload.iter().map(|b| b ^ self.key).collect::<Vec<u8>>();
+        sealed.extend_from_slice(&self.tag(session_id, header, payload));
+        Ok(sealed)
+    }
+
+    fn decrypt(
+        &self,
+        session_id: u64,
+        header: &[u8],
+        payload: &[u8],
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        if payload.len() < 8 {
+            return Err("Missing tag".into());
+        }
+
+        let (data, tag) = payload.split_at(payload.len() - 8);
+        let opened = data.iter().map(|b| b ^ self.key).collect::<Vec<u8>>();
+
+        if self.tag(session_id, header, &opened) != tag {
+            return Err("Bad tag".into());
+        }
+
+        Ok(opened)
+    }
+
+    fn overhead(&self) -> usize {
+        8
+    }
+}
+
+fn exchanges_with_encryptor<S, R>(p: usize) -> Result<(), Box<dyn Error>>
+where
+    S: AsSocket,
+    R: AsSocket,
+{
+    let mut sender = Socket::<S>::new()
+        .set_encryptor(XorEncryptor { key: 0x5a })
+        .bind(&format!("0.0.0.0:{}", p))?;
+    let mut receiver = Socket::<R>::new()
+        .set_encryptor(XorEncryptor { key: 0x5a })
+        .connect(&format!("127.0.0.1:{}", p))?;
+
+    sleep(0.01);
+    sender.tick()?;
+
+    sleep(0.01);
+    receiver.tick()?;
+    assert!(sender.peers() == 1 && receiver.peers() == 1);
+
+    let large = vec![7u8; 2000];
+    sender.send(&["secret".as_bytes(), &large])?;
+    sender.tick()?;
+
+    sleep(0.01);
+    receiver.tick()?;
+    let msg = receiver.recv()?;
+    assert!(msg[0] == "secret".as_bytes());
+    assert!(msg[1] == large);
+
+    Ok(())
+}
+
+#[test]
+fn dealer_exchanges_with_encryptor() -> Result<(), Box<dyn Error>> {
+    exchanges_with_encryptor::<Dealer, Dealer>(7000)
+}
+
+#[test]
+fn radio_exchanges_with_encryptor() -> Result<(), Box<dyn Error>> {
+    exchanges_with_encryptor::<Radio, Dish>(7001)
+}
+
+#[test]
+fn safedealer_exchanges_with_encryptor() -> Result<(), Box<dyn Error>> {
+    exchanges_with_encryptor::<SafeDealer, SafeDealer>(7002)
+}
+
+#[test]
+fn mismatched_keys_never_connect() -> Result<(), Box<dyn Error>> {
+    let mut server = Socket::<Dealer>::new()
+        .set_encryptor(XorEncryptor { key: 1 })
+        .bind("0.0.0.0:7003")?;
+    let mut client = Socket::<Dealer>::new()
+        .set_encryptor(XorEncryptor { key: 2 })
+        .connect("127.0.0.1:7003")?;
+
+    sleep(0.01);
+    server.tick()?;
+
+    sleep(0.01);
+    client.tick()?;
+
+    assert!(server.peers() == 0);
+    assert!(client.peers() == 0);
+
+    Ok(())
+}
+
+#[test]
+fn plaintext_frames_are_dropped() -> Result<(), Box<dyn Error>> {
+    let mut server = Socket::<Dealer>::new()
+        .set_encryptor(XorEncryptor { key: 1 })
+        .bind("0.0.0.0:7004")?;
+    let mut client = Socket::<Dealer>::new().connect("127.0.0.1:7004")?;
+
+    sleep(0.01);
+    server.tick()?;
+    assert!(server.peers() == 0);
+
+    client.tick()?;
+    assert!(client.recv().is_err());
+
+    Ok(())
+}
@@ -0,0 +1,142 @@
+use std::{error::Error, net::Ipv4Addr, thread, time::Duration};
+
+use nbmq::{AsSocket, Dish, Radio, Socket};
+
+fn sleep(n: f64) {
+    thread::sleep(Duration::from_secs_f64(n));
+}
+
+fn settle(radio: &mut Radio, dishes: &mut [&mut Dish]) -> Result<(), Box<dyn Error>> {
+    for _ in 0..3 {
+        sleep(0.01);
+        radio.tick()?;
+
+        for dish in dishes.iter_mut() {
+            dish.tick()?;
+        }
+    }
+
+    sleep(0.01);
+    radio.tick()?;
+
+    Ok(())
+}
+
+fn drain(dish: &mut Dish) -> Result<Vec<String>, Box<dyn Error>> {
+    sleep(0.01);
+    dish.tick()?;
+
+    let mut parts = vec![];
+    while let Ok(msg) = dish.recv() {
+        parts.push(String::from_utf8(msg.concat())?);
+    }
+
+    parts.sort();
+    Ok(parts)
+}
+
+#[test]
+fn send_group_reaches_members_only() -> Result<(), Box<dyn Error>> {
+    let mut radio = Socket::<Radio>::new().bind("127.0.0.1:7800")?;
+    let mut scores = Socket::<Dish>::new().connect("127.0.0.1:7800")?;
+    let mut both = Socket::<Dish>::new().connect("127.0.0.1:7800")?;
+    let mut none = Socket::<Dish>::new().connect("127.0.0.1:7800")?;
+
+    scores.join("scores".as_bytes())?;
+    both.join("scores".as_bytes())?;
+    both.join("news".as_bytes())?;
+
+    settle(&mut radio, &mut [&mut scores, &mut both, &mut none])?;
+    assert!(radio.peers() == 3);
+
+    radio.send_group("scores".as_bytes(), &["/1-0".as_bytes()])?;
+    radio.send_group("news".as_bytes(), &["/rain".as_bytes()])?;
+    radio.send_group("weather".as_bytes(), &["/sun".as_bytes()])?;
+    radio.tick()?;
+
+    assert!(drain(&mut scores)? == vec!["scores/1-0"]);
+    assert!(drain(&mut both)? == vec!["news/rain", "scores/1-0"]);
+    assert!(drain(&mut none)?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn plain_send_ignores_membership() -> Result<(), Box<dyn Error>> {
+    let mut radio = Socket::<Radio>::new().bind("127.0.0.1:7801")?;
+    let mut member = Socket::<Dish>::new().connect("127.0.0.1:7801")?;
+    let mut other = Socket::<Dish>::new().connect("127.0.0.1:7801")?;
+
+    member.join("scores".as_bytes())?;
+    settle(&mut radio, &mut [&mut member, &mut other])?;
+
+    radio.send(&["all".as_bytes()])?;
+    radio.tick()?;
+
+    assert!(drain(&mut member)? == vec!["all"]);
+    assert!(drain(&mut other)? == vec!["all"]);
+
+    Ok(())
+}
+
+#[test]
+fn leave_stops_delivery() -> Result<(), Box<dyn Error>> {
+    let mut radio = Socket::<Radio>::new().bind("127.0.0.1:7802")?;
+    let mut dish = Socket::<Dish>::new().connect("127.0.0.1:7802")?;
+
+    dish.join("a".as_bytes())?;
+    dish.join("b".as_bytes())?;
+    settle(&mut radio, &mut [&mut dish])?;
+
+    radio.send_group("a".as_bytes(), &[])?;
+    radio.send_group("b".as_bytes(), &[])?;
+    radio.tick()?;
+    assert!(drain(&mut dish)? == vec!["a", "b"]);
+
+    dish.leave("a".as_bytes())?;
+    settle(&mut radio, &mut [&mut dish])?;
+
+    radio.send_group("a".as_bytes(), &[])?;
+    radio.send_group("b".as_bytes(), &[])?;
+    radio.tick()?;
+    assert!(drain(&mut dish)? == vec!["b"]);
+
+    Ok(())
+}
+
+#[test]
+fn membership_survives_reconnect() -> Result<(), Box<dyn Error>> {
+    let mut dish = Socket::<Dish>::new()
+        .set_reconnect_wait(0.01)
+        .connect("127.0.0.1:7803")?;
+    dish.join("a".as_bytes())?;
+    dish.tick()?;
+
+    // The radio comes up after the group was joined.
+    let mut radio = Socket::<Radio>::new().bind("127.0.0.1:7803")?;
+
+    for _ in 0..5 {
+        sleep(0.02);
+        dish.tick()?;
+        radio.tick()?;
+    }
+    assert!(radio.peers() == 1);
+
+    radio.send_group("a".as_bytes(), &["!".as_bytes()])?;
+    radio.tick()?;
+    assert!(drain(&mut dish)? == vec!["a!"]);
+
+    Ok(())
+}
+
+#[test]
+fn send_group_errors_in_multicast_mode() -> Result<(), Box<dyn Error>> {
+    let mut radio = Socket::<Radio>::new()
+        .set_multicast_group("239.255.0.1:7850".parse()?)
+        .set_multicast_interface(Ipv4Addr::LOCALHOST)
+        .bind("127.0.0.1:7804")?;
+
+    assert!(radio.send_group("a".as_bytes(), &[]).is_err());
+
+    Ok(())
+}
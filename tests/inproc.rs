@@ -0,0 +1,138 @@
+use std::{error::Error, thread, time::Duration};
+
+use nbmq::{AsSocket, Dealer, Dish, Radio, SafeDealer, Socket};
+
+fn sleep(n: f64) {
+    thread::sleep(Duration::from_secs_f64(n));
+}
+
+fn exchanges_over_inproc<S, R>(addr: &str) -> Result<(), Box<dyn Error>>
+where
+    S: AsSocket,
+    R: AsSocket,
+{
+    let mut sender = Socket::<S>::new().bind(addr)?;
+    let mut receiver = Socket::<R>::new().connect(addr)?;
+
+    sender.tick()?;
+    receiver.tick()?;
+    assert!(sender.peers() == 1 && receiver.peers() == 1);
+
+    sender.send(&["hello".as_bytes(), "world".as_bytes()])?;
+    sender.tick()?;
+
+    receiver.tick()?;
+    let msg = receiver.recv()?;
+    assert!(msg.len() == 2);
+    assert!(msg[0] == "hello".as_bytes());
+    assert!(msg[1] == "world".as_bytes());
+
+    Ok(())
+}
+
+#[test]
+fn dealer_exchanges_over_inproc() -> Result<(), Box<dyn Error>> {
+    exchanges_over_inproc::<Dealer, Dealer>("inproc://dealer")
+}
+
+#[test]
+fn radio_exchanges_over_inproc() -> Result<(), Box<dyn Error>> {
+    exchanges_over_inproc::<Radio, Dish>("inproc://radio")
+}
+
+#[test]
+fn safedealer_exchanges_over_inproc() -> Result<(), Box<dyn Error>> {
+    exchanges_over_inproc::<SafeDealer, SafeDealer>("inproc://safedealer")
+}
+
+#[test]
+fn inproc_moves_messages_whole() -> Result<(), Box<dyn Error>> {
+    let mut server = Socket::<Dealer>::new()
+        .set_max_tick_send(1)
+        .bind("inproc://whole")?;
+    let mut client = Socket::<Dealer>::new()
+        .set_max_tick_send(1)
+        .connect("inproc://whole")?;
+
+    server.tick()?;
+    client.tick()?;
+
+    // Chunked into frames this would take thousands of ticks at one frame per tick.
+    let mut large_bin = vec![0u8; 1_000_000];
+    large_bin.push(1);
+    client.send(&[&large_bin])?;
+    client.tick()?;
+
+    server.tick()?;
+    let msg = server.recv()?;
+    assert!(msg[0].len() == 1_000_001);
+    assert!(msg[0][1_000_000] == 1);
+
+    Ok(())
+}
+
+#[test]
+fn safedealer_acks_over_inproc() -> Result<(), Box<dyn Error>> {
+    let mut server = Socket::<SafeDealer>::new()
+        .set_safe_resend_ivl(0.01)
+        .bind("inproc://acks")?;
+    let mut client = Socket::<SafeDealer>::new()
+        .set_safe_resend_ivl(0.01)
+        .connect("inproc://acks")?;
+
+    server.tick()?;
+    client.tick()?;
+
+    for i in 0..10u8 {
+        client.send(&[&[i]])?;
+    }
+    client.tick()?;
+
+    server.tick()?;
+    client.tick()?;
+
+    sleep(0.02);
+    client.tick()?;
+    server.tick()?;
+
+    let mut recvd = vec![];
+    while let Ok(msg) = server.recv() {
+        recvd.push(msg[0][0]);
+    }
+
+    recvd.sort();
+    assert!(recvd == (0..10).collect::<Vec<u8>>());
+
+    Ok(())
+}
+
+#[test]
+fn inproc_names_are_exclusive() -> Result<(), Box<dyn Error>> {
+    let server = Socket::<Dealer>::new().bind("inproc://exclusive")?;
+    assert!(Socket::<Dealer>::new().bind("inproc://exclusive").is_err());
+
+    drop(server);
+    assert!(Socket::<Dealer>::new().bind("inproc://exclusive").is_ok());
+
+    Ok(())
+}
+
+#[test]
+fn inproc_reconnects_to_late_binder() -> Result<(), Box<dyn Error>> {
+    let mut client = Socket::<Dealer>::new()
+        .set_reconnect_wait(0.01)
+        .connect("inproc://late")?;
+    client.tick()?;
+    assert!(client.peers() == 0);
+
+    let mut server = Socket::<Dealer>::new().bind("inproc://late")?;
+
+    sleep(0.02);
+    client.tick()?;
+    server.tick()?;
+    client.tick()?;
+
+    assert!(server.peers() == 1 && client.peers() == 1);
+
+    Ok(())
+}
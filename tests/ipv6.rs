@@ -0,0 +1,128 @@
+use std::{error::Error, net::UdpSocket, thread, time::Duration};
+
+use nbmq::{AsSocket, Dealer, Dish, Radio, SafeDealer, Socket};
+
+fn sleep(n: f64) {
+    thread::sleep(Duration::from_secs_f64(n));
+}
+
+// Some CI hosts have no IPv6 loopback at all. Nothing to test there.
+fn ipv6_available() -> bool {
+    UdpSocket::bind("[::1]:0").is_ok()
+}
+
+fn exchanges_over_ipv6<S, R>(bind_addr: &str, connect_addr: &str) -> Result<(), Box<dyn Error>>
+where
+    S: AsSocket,
+    R: AsSocket,
+{
+    let mut sender = Socket::<S>::new()
+        .set_peer_keepalive(0.1)
+        .set_peer_heartbeat_ivl(0.02)
+        .bind(bind_addr)?;
+    let mut receiver = Socket::<R>::new()
+        .set_peer_keepalive(0.1)
+        .set_peer_heartbeat_ivl(0.02)
+        .connect(connect_addr)?;
+
+    sleep(0.01);
+    sender.tick()?;
+
+    sleep(0.01);
+    receiver.tick()?;
+    assert!(sender.peers() == 1 && receiver.peers() == 1);
+
+    // Outlive the keepalive a few times over on heartbeats alone.
+    for _ in 0..20 {
+        sleep(0.02);
+        sender.tick()?;
+        receiver.tick()?;
+    }
+    assert!(sender.peers() == 1 && receiver.peers() == 1);
+
+    sender.send(&["hello".as_bytes(), "world".as_bytes()])?;
+    sender.tick()?;
+
+    sleep(0.01);
+    receiver.tick()?;
+    let msg = receiver.recv()?;
+    assert!(msg[0] == "hello".as_bytes());
+    assert!(msg[1] == "world".as_bytes());
+
+    Ok(())
+}
+
+#[test]
+fn dealer_over_ipv6() -> Result<(), Box<dyn Error>> {
+    if !ipv6_available() {
+        return Ok(());
+    }
+
+    exchanges_over_ipv6::<Dealer, Dealer>("[::1]:7400", "[::1]:7400")
+}
+
+#[test]
+fn safedealer_over_ipv6() -> Result<(), Box<dyn Error>> {
+    if !ipv6_available() {
+        return Ok(());
+    }
+
+    exchanges_over_ipv6::<SafeDealer, SafeDealer>("[::1]:7401", "[::1]:7401")
+}
+
+#[test]
+fn radio_dish_over_ipv6() -> Result<(), Box<dyn Error>> {
+    if !ipv6_available() {
+        return Ok(());
+    }
+
+    exchanges_over_ipv6::<Radio, Dish>("[::1]:7402", "[::1]:7402")
+}
+
+#[test]
+fn dealer_over_tcp_ipv6() -> Result<(), Box<dyn Error>> {
+    if !ipv6_available() {
+        return Ok(());
+    }
+
+    exchanges_over_ipv6::<Dealer, Dealer>("tcp://[::1]:7403", "tcp://[::1]:7403")
+}
+
+#[test]
+fn dual_stack_accepts_ipv4_peer() -> Result<(), Box<dyn Error>> {
+    if !ipv6_available() {
+        return Ok(());
+    }
+
+    exchanges_over_ipv6::<Dealer, Dealer>("[::]:7404", "127.0.0.1:7404")
+}
+
+#[test]
+fn dual_stack_accepts_both_families() -> Result<(), Box<dyn Error>> {
+    if !ipv6_available() {
+        return Ok(());
+    }
+
+    let mut server = Socket::<Radio>::new().bind("[::]:7405")?;
+    let mut v4 = Socket::<Dish>::new().connect("127.0.0.1:7405")?;
+    let mut v6 = Socket::<Dish>::new().connect("[::1]:7405")?;
+
+    sleep(0.01);
+    server.tick()?;
+
+    sleep(0.01);
+    v4.tick()?;
+    v6.tick()?;
+    assert!(server.peers() == 2);
+
+    server.send(&["hello".as_bytes()])?;
+    server.tick()?;
+
+    sleep(0.01);
+    v4.tick()?;
+    v6.tick()?;
+    assert!(v4.recv()?[0] == "hello".as_bytes());
+    assert!(v6.recv()?[0] == "hello".as_bytes());
+
+    Ok(())
+}
@@ -0,0 +1,249 @@
+use std::{
+    error::Error,
+    net::{Ipv4Addr, SocketAddrV4, UdpSocket},
+    thread,
+    time::Duration,
+};
+
+use nbmq::{AsSocket, Dish, Radio, Socket, frame::DataFrame, hmac::HmacSha256};
+
+fn sleep(n: f64) {
+    thread::sleep(Duration::from_secs_f64(n));
+}
+
+fn group(port: u16) -> SocketAddrV4 {
+    SocketAddrV4::new(Ipv4Addr::new(239, 255, 0, 1), port)
+}
+
+fn multicast_pair(radio_port: u16, group_port: u16) -> Result<(Radio, Dish), Box<dyn Error>> {
+    multicast_pair_with(
+        Socket::<Radio>::new(),
+        Socket::<Dish>::new(),
+        radio_port,
+        group_port,
+    )
+}
+
+fn multicast_pair_with(
+    radio: Socket<Radio>,
+    dish: Socket<Dish>,
+    radio_port: u16,
+    group_port: u16,
+) -> Result<(Radio, Dish), Box<dyn Error>> {
+    let radio = radio
+        .set_multicast_group(group(group_port))
+        .set_multicast_interface(Ipv4Addr::LOCALHOST)
+        .set_peer_keepalive(0.1)
+        .set_peer_heartbeat_ivl(0.02)
+        .bind(&format!("127.0.0.1:{}", radio_port))?;
+    let dish = dish
+        .set_multicast_group(group(group_port))
+        .set_multicast_interface(Ipv4Addr::LOCALHOST)
+        .set_peer_keepalive(0.1)
+        .set_peer_heartbeat_ivl(0.02)
+        .connect(&format!("127.0.0.1:{}", radio_port))?;
+
+    Ok((radio, dish))
+}
+
+#[test]
+fn radio_publishes_to_group() -> Result<(), Box<dyn Error>> {
+    let (mut radio, mut dish) = multicast_pair(7600, 7650)?;
+
+    sleep(0.01);
+    radio.tick()?;
+
+    sleep(0.01);
+    dish.tick()?;
+    radio.tick()?;
+    assert!(radio.peers() == 1 && dish.peers() == 1);
+
+    let mut large_bin = vec![0u8; 5000];
+    large_bin.push(1);
+    radio.send(&["hello".as_bytes(), &large_bin])?;
+    radio.tick()?;
+
+    sleep(0.01);
+    dish.tick()?;
+    let msg = dish.recv()?;
+    assert!(msg[0] == "hello".as_bytes());
+    assert!(msg[1].len() == 5001 && msg[1][5000] == 1);
+
+    Ok(())
+}
+
+#[test]
+fn liveness_stays_on_unicast() -> Result<(), Box<dyn Error>> {
+    let (mut radio, mut dish) = multicast_pair(7601, 7651)?;
+
+    sleep(0.01);
+    radio.tick()?;
+
+    sleep(0.01);
+    dish.tick()?;
+    radio.tick()?;
+
+    // Heartbeats alone keep the session through several keepalive periods.
+    for _ in 0..20 {
+        sleep(0.02);
+        radio.tick()?;
+        dish.tick()?;
+    }
+    assert!(radio.peers() == 1 && dish.peers() == 1);
+
+    drop(dish);
+
+    for _ in 0..10 {
+        sleep(0.02);
+        radio.tick()?;
+    }
+    assert!(radio.peers() == 0);
+
+    Ok(())
+}
+
+#[test]
+fn dish_ignores_group_without_session() -> Result<(), Box<dyn Error>> {
+    // Nothing is bound at the unicast address, so the dish never gets a session.
+    let mut dish = Socket::<Dish>::new()
+        .set_multicast_group(group(7652))
+        .set_multicast_interface(Ipv4Addr::LOCALHOST)
+        .connect("127.0.0.1:7602")?;
+
+    let frame = DataFrame::encode(0, 0, 1, 1, 0, 5, 5, 5, 0, "hello".as_bytes());
+    let publisher = UdpSocket::bind("127.0.0.1:0")?;
+    publisher.send_to(&frame, group(7652))?;
+
+    sleep(0.01);
+    dish.tick()?;
+    assert!(dish.peers() == 0);
+    assert!(dish.recv().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn radio_without_peers_drops_sends() -> Result<(), Box<dyn Error>> {
+    let mut radio = Socket::<Radio>::new()
+        .set_multicast_group(group(7653))
+        .set_multicast_interface(Ipv4Addr::LOCALHOST)
+        .bind("127.0.0.1:7603")?;
+
+    radio.send(&["hello".as_bytes()])?;
+    radio.tick()?;
+
+    Ok(())
+}
+
+/// Connect the pair and have a stranger publish to the group while the session is live.
+fn stranger_publishes(
+    radio: &mut Radio,
+    dish: &mut Dish,
+    stranger: &str,
+    group_port: u16,
+) -> Result<(), Box<dyn Error>> {
+    sleep(0.01);
+    radio.tick()?;
+
+    sleep(0.01);
+    dish.tick()?;
+    radio.tick()?;
+    assert!(radio.peers() == 1 && dish.peers() == 1);
+
+    let frame = DataFrame::encode(0, 0, 1, 1, 0, 5, 5, 5, 0, "forge".as_bytes());
+    let publisher = UdpSocket::bind(stranger)?;
+    publisher.send_to(&frame, group(group_port))?;
+
+    radio.send(&["hello".as_bytes()])?;
+    radio.tick()?;
+
+    sleep(0.01);
+    dish.tick()?;
+
+    Ok(())
+}
+
+#[test]
+fn dish_ignores_group_frames_from_other_hosts() -> Result<(), Box<dyn Error>> {
+    let (mut radio, mut dish) = multicast_pair(7604, 7654)?;
+    stranger_publishes(&mut radio, &mut dish, "127.0.0.2:0", 7654)?;
+
+    assert!(dish.recv()? == vec!["hello".as_bytes().to_vec()]);
+    assert!(dish.recv().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn dish_requires_a_mac_on_group_frames_with_psk() -> Result<(), Box<dyn Error>> {
+    let (mut radio, mut dish) = multicast_pair_with(
+        Socket::<Radio>::new().set_psk(b"secret"),
+        Socket::<Dish>::new().set_psk(b"secret"),
+        7605,
+        7655,
+    )?;
+
+    // Same host as the radio, so only the missing MAC gives it away.
+    stranger_publishes(&mut radio, &mut dish, "127.0.0.1:0", 7655)?;
+
+    assert!(dish.recv()? == vec!["hello".as_bytes().to_vec()]);
+    assert!(dish.recv().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn dish_takes_reordered_group_frames_once_with_psk() -> Result<(), Box<dyn Error>> {
+    let (mut radio, mut dish) = multicast_pair_with(
+        Socket::<Radio>::new().set_psk(b"secret"),
+        Socket::<Dish>::new().set_psk(b"secret"),
+        7606,
+        7656,
+    )?;
+
+    sleep(0.01);
+    radio.tick()?;
+
+    sleep(0.01);
+    dish.tick()?;
+    radio.tick()?;
+    assert!(radio.peers() == 1 && dish.peers() == 1);
+
+    let signed = |message_id: u64, data: &[u8], counter: u64| {
+        let mut frame = DataFrame::encode(
+            0,
+            0,
+            message_id,
+            1,
+            0,
+            data.len() as u32,
+            data.len() as u32,
+            data.len() as u16,
+            0,
+            data,
+        );
+        frame.extend_from_slice(&counter.to_be_bytes());
+        let tag = HmacSha256::mac(b"secret", &frame);
+        frame.extend_from_slice(&tag);
+        frame
+    };
+
+    // The later frame overtakes the earlier one, then the earlier one is replayed.
+    let publisher = UdpSocket::bind("127.0.0.1:0")?;
+    publisher.send_to(&signed(2, b"second", 11), group(7656))?;
+    publisher.send_to(&signed(1, b"first", 10), group(7656))?;
+    publisher.send_to(&signed(1, b"first", 10), group(7656))?;
+
+    sleep(0.01);
+    dish.tick()?;
+
+    let mut received = vec![];
+    while let Ok(message) = dish.recv() {
+        received.push(message);
+    }
+    received.sort();
+
+    assert!(received == vec![vec![b"first".to_vec()], vec![b"second".to_vec()]]);
+
+    Ok(())
+}
@@ -0,0 +1,178 @@
+use std::{
+    error::Error,
+    io,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use nbmq::{
+    Addr, AsSocket, SafeDealer, SafeDish, SafeRadio, Socket, Transport, frame::SEQUENCED_DATA_KIND,
+    transport::UdpTransport,
+};
+
+mod common;
+use common::Lossy;
+
+fn sleep(n: f64) {
+    thread::sleep(Duration::from_secs_f64(n));
+}
+
+const NACK: u8 = 14;
+
+/// UDP sending every data frame twice, counting the `Nack`s it receives.
+struct Doubling {
+    inner: UdpTransport,
+    nacks: Arc<AtomicUsize>,
+}
+
+impl Doubling {
+    fn new(nacks: Arc<AtomicUsize>) -> io::Result<Self> {
+        Ok(Self {
+            inner: UdpTransport::bind("0.0.0.0:0")?,
+            nacks,
+        })
+    }
+}
+
+impl Transport for Doubling {
+    fn bind(&mut self, addr: &str) -> io::Result<()> {
+        self.inner.bind(addr)
+    }
+
+    fn connect(&mut self, addr: &Addr) -> io::Result<()> {
+        self.inner.connect(addr)
+    }
+
+    fn send_to(&mut self, data: &[u8], addr: &Addr) -> io::Result<usize> {
+        if data.len() >= 2 && data[1] == SEQUENCED_DATA_KIND {
+            self.inner.send_to(data, addr)?;
+        }
+
+        self.inner.send_to(data, addr)
+    }
+
+    fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, Addr)> {
+        let (n, addr) = self.inner.recv_from(buf)?;
+
+        if n >= 2 && buf[1] == NACK {
+            self.nacks.fetch_add(1, Ordering::Relaxed);
+        }
+
+        Ok((n, addr))
+    }
+
+    fn local_addr(&self) -> io::Result<Addr> {
+        self.inner.local_addr()
+    }
+}
+
+fn large_bin() -> Vec<u8> {
+    let mut large_bin = vec![0u8; 20000];
+    large_bin.push(1);
+    large_bin
+}
+
+#[test]
+fn nack_recovers_losses_before_the_resend_timer() -> Result<(), Box<dyn Error>> {
+    let mut server = Socket::<SafeDealer>::new()
+        .set_safe_nack_ivl(0.01)
+        .bind_with(Lossy::every(SEQUENCED_DATA_KIND, 5)?, "127.0.0.1:3900")?;
+
+    // The timer alone wouldn't resend within the test.
+    let mut client = Socket::<SafeDealer>::new()
+        .set_safe_resend_ivl(10.)
+        .connect("127.0.0.1:3900")?;
+
+    for _ in 0..2 {
+        sleep(0.01);
+        server.tick()?;
+        client.tick()?;
+    }
+
+    client.send(&[&large_bin()])?;
+    client.tick()?;
+
+    sleep(0.01);
+    server.tick()?;
+    assert!(server.recv().is_err());
+
+    for _ in 0..4 {
+        sleep(0.02);
+        server.tick()?;
+        client.tick()?;
+    }
+
+    assert!(server.recv()?[0] == large_bin());
+
+    Ok(())
+}
+
+#[test]
+fn dish_nacks_radio_for_its_own_gaps() -> Result<(), Box<dyn Error>> {
+    let mut radio = Socket::<SafeRadio>::new()
+        .set_safe_resend_ivl(10.)
+        .bind("127.0.0.1:3901")?;
+    let mut lossy = Socket::<SafeDish>::new()
+        .set_safe_nack_ivl(0.01)
+        .connect_with(Lossy::every(SEQUENCED_DATA_KIND, 4)?, "127.0.0.1:3901")?;
+    let mut clean = Socket::<SafeDish>::new().connect("127.0.0.1:3901")?;
+
+    for _ in 0..2 {
+        sleep(0.01);
+        radio.tick()?;
+        lossy.tick()?;
+        clean.tick()?;
+    }
+    assert!(radio.peers() == 2);
+
+    radio.send(&[&large_bin()])?;
+
+    // Resends are lost too, some gaps take more than one ask.
+    for _ in 0..10 {
+        sleep(0.02);
+        radio.tick()?;
+        lossy.tick()?;
+        clean.tick()?;
+    }
+
+    assert!(lossy.recv()?[0] == large_bin());
+    assert!(clean.recv()?[0] == large_bin());
+    assert!(clean.recv().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn duplicates_of_delivered_frames_arent_nacked() -> Result<(), Box<dyn Error>> {
+    let nacks = Arc::new(AtomicUsize::new(0));
+
+    let mut server = Socket::<SafeDealer>::new()
+        .set_safe_nack_ivl(0.01)
+        .bind("127.0.0.1:3902")?;
+    let mut client = Socket::<SafeDealer>::new()
+        .connect_with(Doubling::new(nacks.clone())?, "127.0.0.1:3902")?;
+
+    for _ in 0..2 {
+        sleep(0.01);
+        server.tick()?;
+        client.tick()?;
+    }
+
+    client.send(&[&large_bin()])?;
+
+    for _ in 0..6 {
+        sleep(0.02);
+        server.tick()?;
+        client.tick()?;
+    }
+
+    assert!(server.recv()?[0] == large_bin());
+    assert!(server.recv().is_err());
+    assert!(nacks.load(Ordering::Relaxed) == 0);
+
+    Ok(())
+}
@@ -0,0 +1,177 @@
+use std::{error::Error, thread, time::Duration};
+
+use nbmq::{AsSocket, SafeDealer, Socket, frame::SEQUENCED_DATA_KIND};
+
+mod common;
+use common::Lossy;
+
+fn sleep(n: f64) {
+    thread::sleep(Duration::from_secs_f64(n));
+}
+
+const DATA: u8 = SEQUENCED_DATA_KIND;
+const SACK: u8 = 13;
+
+fn settle(a: &mut SafeDealer, b: &mut SafeDealer, rounds: usize) -> Result<(), Box<dyn Error>> {
+    for _ in 0..rounds {
+        sleep(0.02);
+        a.tick()?;
+        b.tick()?;
+    }
+
+    Ok(())
+}
+
+fn drain(socket: &mut SafeDealer) -> Vec<u8> {
+    let mut received = vec![];
+    while let Ok(msg) = socket.recv() {
+        assert!(msg.len() == 1);
+        received.push(msg[0][0]);
+    }
+
+    received
+}
+
+#[test]
+fn ordered_mode_holds_back_until_gaps_fill() -> Result<(), Box<dyn Error>> {
+    let mut server = Socket::<SafeDealer>::new()
+        .set_safe_ordered(true)
+        .bind_with(Lossy::every(DATA, 4)?, "127.0.0.1:3700")?;
+    let mut client = Socket::<SafeDealer>::new()
+        .set_safe_ordered(true)
+        .set_safe_resend_ivl(0.01)
+        .connect("127.0.0.1:3700")?;
+    settle(&mut server, &mut client, 2)?;
+
+    for i in 0..10u8 {
+        client.send(&[&[i]])?;
+    }
+
+    // A message is two data frames, its sequence number and its body. Every other message is
+    // lost on the first try, so the rest complete ahead of it.
+    client.tick()?;
+    sleep(0.005);
+    server.tick()?;
+    assert!(drain(&mut server) == vec![0]);
+
+    settle(&mut server, &mut client, 5)?;
+    assert!(drain(&mut server) == (1..10).collect::<Vec<u8>>());
+
+    Ok(())
+}
+
+#[test]
+fn ordered_mode_rejects_resent_duplicates() -> Result<(), Box<dyn Error>> {
+    let mut server = Socket::<SafeDealer>::new()
+        .set_safe_ordered(true)
+        .bind("127.0.0.1:3701")?;
+
+    // Never hears an ack, so resends everything until the limit.
+    let mut client = Socket::<SafeDealer>::new()
+        .set_safe_ordered(true)
+        .set_safe_resend_ivl(0.01)
+        .set_safe_resent_limit(3)
+        .connect_with(Lossy::every(SACK, 1)?, "127.0.0.1:3701")?;
+    settle(&mut server, &mut client, 2)?;
+
+    for i in 0..5u8 {
+        client.send(&[&[i]])?;
+    }
+
+    let mut received = vec![];
+    for _ in 0..8 {
+        settle(&mut server, &mut client, 1)?;
+        received.extend(drain(&mut server));
+    }
+
+    assert!(received == (0..5).collect::<Vec<u8>>());
+
+    Ok(())
+}
+
+#[test]
+fn ordered_mode_skips_gaps_given_up_on() -> Result<(), Box<dyn Error>> {
+    let mut server = Socket::<SafeDealer>::new()
+        .set_safe_ordered(true)
+        .set_uncompleted_message_ttl(0.01)
+        .bind_with(Lossy::every(DATA, 4)?, "127.0.0.1:3702")?;
+    let mut client = Socket::<SafeDealer>::new()
+        .set_safe_ordered(true)
+        .set_safe_resend_ivl(0.01)
+        .set_safe_resent_limit(0)
+        .connect("127.0.0.1:3702")?;
+    settle(&mut server, &mut client, 2)?;
+
+    for i in 0..6u8 {
+        client.send(&[&[i]])?;
+    }
+
+    let mut received = vec![];
+    for _ in 0..8 {
+        settle(&mut server, &mut client, 1)?;
+        received.extend(drain(&mut server));
+    }
+
+    assert!(received == vec![0, 2, 4]);
+
+    Ok(())
+}
+
+#[test]
+fn ordered_mode_waits_out_slow_retransmits() -> Result<(), Box<dyn Error>> {
+    // Partial messages and gaps outlive the ttl while the sender is still resending.
+    let mut server = Socket::<SafeDealer>::new()
+        .set_safe_ordered(true)
+        .set_uncompleted_message_ttl(0.01)
+        .bind_with(Lossy::every(DATA, 4)?, "127.0.0.1:3703")?;
+    let mut client = Socket::<SafeDealer>::new()
+        .set_safe_ordered(true)
+        .set_safe_resend_ivl(0.06)
+        .connect("127.0.0.1:3703")?;
+    settle(&mut server, &mut client, 2)?;
+
+    for i in 0..10u8 {
+        client.send(&[&[i]])?;
+    }
+
+    let mut received = vec![];
+    for _ in 0..30 {
+        settle(&mut server, &mut client, 1)?;
+        received.extend(drain(&mut server));
+    }
+
+    assert!(received == (0..10).collect::<Vec<u8>>());
+
+    Ok(())
+}
+
+#[test]
+fn ordered_mode_leaves_frames_beyond_hwm_unacked() -> Result<(), Box<dyn Error>> {
+    let mut server = Socket::<SafeDealer>::new()
+        .set_safe_ordered(true)
+        .set_recv_hwm(3)
+        .bind_with(Lossy::every(DATA, 4)?, "127.0.0.1:3704")?;
+    let mut client = Socket::<SafeDealer>::new()
+        .set_safe_ordered(true)
+        .set_safe_resend_ivl(0.01)
+        .set_safe_resent_limit(100)
+        .connect("127.0.0.1:3704")?;
+    settle(&mut server, &mut client, 2)?;
+
+    for i in 0..12u8 {
+        client.send(&[&[i]])?;
+    }
+
+    // Nothing taken past the hwm is acked, so whatever didn't fit is resent later.
+    let mut received = vec![];
+    for _ in 0..40 {
+        sleep(0.02);
+        client.tick()?;
+        let _ = server.tick();
+        received.extend(drain(&mut server));
+    }
+
+    assert!(received == (0..12).collect::<Vec<u8>>());
+
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use std::{error::Error, thread, time::Duration};
+
+use nbmq::{AsSocket, Pair, Socket};
+
+fn sleep(n: f64) {
+    thread::sleep(Duration::from_secs_f64(n));
+}
+
+fn settle(pairs: &mut [&mut Pair]) -> Result<(), Box<dyn Error>> {
+    for _ in 0..3 {
+        for pair in pairs.iter_mut() {
+            sleep(0.01);
+            pair.tick()?;
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn pair_exchanges_both_ways() -> Result<(), Box<dyn Error>> {
+    let mut server = Socket::<Pair>::new().bind("127.0.0.1:3200")?;
+    let mut client = Socket::<Pair>::new().connect("127.0.0.1:3200")?;
+
+    assert!(server.send(&["early".as_bytes()]).is_err());
+
+    settle(&mut [&mut server, &mut client])?;
+    assert!(server.peers() == 1 && client.peers() == 1);
+
+    client.send(&["ping".as_bytes()])?;
+    settle(&mut [&mut client, &mut server])?;
+    assert!(server.recv()? == vec!["ping".as_bytes().to_vec()]);
+
+    server.send(&["pong".as_bytes()])?;
+    settle(&mut [&mut server, &mut client])?;
+    assert!(client.recv()? == vec!["pong".as_bytes().to_vec()]);
+
+    Ok(())
+}
+
+#[test]
+fn pair_rejects_second_peer() -> Result<(), Box<dyn Error>> {
+    let mut server = Socket::<Pair>::new().bind("127.0.0.1:3201")?;
+    let mut first = Socket::<Pair>::new().connect("127.0.0.1:3201")?;
+    settle(&mut [&mut server, &mut first])?;
+
+    let mut second = Socket::<Pair>::new().connect("127.0.0.1:3201")?;
+    settle(&mut [&mut server, &mut first, &mut second])?;
+
+    assert!(server.peers() == 1);
+    assert!(first.peers() == 1);
+    assert!(second.peers() == 0);
+
+    server.send(&["only first".as_bytes()])?;
+    settle(&mut [&mut server, &mut first, &mut second])?;
+
+    assert!(first.recv()? == vec!["only first".as_bytes().to_vec()]);
+    assert!(second.recv().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn pair_replaces_stale_peer() -> Result<(), Box<dyn Error>> {
+    let mut server = Socket::<Pair>::new()
+        .set_peer_keepalive(0.04)
+        .bind("127.0.0.1:3202")?;
+    let mut first = Socket::<Pair>::new().connect("127.0.0.1:3202")?;
+    settle(&mut [&mut server, &mut first])?;
+    assert!(server.peers() == 1);
+
+    // Silent for longer than the keepalive, though not pruned yet.
+    drop(first);
+    sleep(0.05);
+
+    let mut second = Socket::<Pair>::new()
+        .set_peer_heartbeat_ivl(0.01)
+        .connect("127.0.0.1:3202")?;
+    settle(&mut [&mut server, &mut second])?;
+
+    assert!(server.peers() == 1);
+    assert!(second.peers() == 1);
+
+    server.send(&["to second".as_bytes()])?;
+    settle(&mut [&mut server, &mut second])?;
+    assert!(second.recv()? == vec!["to second".as_bytes().to_vec()]);
+
+    Ok(())
+}
+
+#[test]
+fn pair_keeps_peer_heartbeating_slower_than_itself() -> Result<(), Box<dyn Error>> {
+    let mut server = Socket::<Pair>::new()
+        .set_peer_heartbeat_ivl(0.01)
+        .bind("127.0.0.1:3203")?;
+    let mut first = Socket::<Pair>::new()
+        .set_peer_heartbeat_ivl(0.2)
+        .connect("127.0.0.1:3203")?;
+    settle(&mut [&mut server, &mut first])?;
+
+    // Quiet for several of the server's intervals, well within its own.
+    sleep(0.05);
+
+    // Were the first peer evicted it would reconnect and take the slot back, so the second must
+    // never hold it at all.
+    let mut second = Socket::<Pair>::new().connect("127.0.0.1:3203")?;
+    for _ in 0..3 {
+        second.tick()?;
+        sleep(0.01);
+        server.tick()?;
+        sleep(0.01);
+        second.tick()?;
+        assert!(second.peers() == 0);
+    }
+
+    assert!(server.peers() == 1);
+
+    Ok(())
+}
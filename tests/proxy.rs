@@ -0,0 +1,181 @@
+use std::{error::Error, thread, time::Duration};
+
+use nbmq::{AsSocket, Dealer, Proxy, Pull, Push, Router, Socket};
+
+fn sleep(n: f64) {
+    thread::sleep(Duration::from_secs_f64(n));
+}
+
+#[test]
+fn router_dealer_proxy_keeps_envelope() -> Result<(), Box<dyn Error>> {
+    let frontend = Socket::<Router>::new().bind("127.0.0.1:3500")?;
+    let backend = Socket::<Dealer>::new().bind("127.0.0.1:3501")?;
+    let mut proxy = Proxy::new(frontend, backend);
+
+    let mut a = Socket::<Dealer>::new().connect("127.0.0.1:3500")?;
+    let mut b = Socket::<Dealer>::new().connect("127.0.0.1:3500")?;
+    let mut worker = Socket::<Dealer>::new().connect("127.0.0.1:3501")?;
+
+    for _ in 0..3 {
+        sleep(0.01);
+        proxy.step()?;
+        a.tick()?;
+        b.tick()?;
+        worker.tick()?;
+    }
+    assert!(proxy.frontend().peers() == 2 && proxy.backend().peers() == 1);
+
+    a.send(&["from a".as_bytes()])?;
+    b.send(&["from b".as_bytes()])?;
+    a.tick()?;
+    b.tick()?;
+
+    sleep(0.01);
+    assert!(proxy.step()? == 2);
+
+    sleep(0.01);
+    worker.tick()?;
+
+    // The worker sees the router's session id frame and echoes it back.
+    while let Ok(request) = worker.recv() {
+        assert!(request.len() == 2 && request[0].len() == 8);
+
+        let reply = [request[1].as_slice(), " done".as_bytes()].concat();
+        worker.send(&[&request[0], &reply])?;
+    }
+    worker.tick()?;
+
+    sleep(0.01);
+    assert!(proxy.step()? == 2);
+
+    sleep(0.01);
+    a.tick()?;
+    b.tick()?;
+
+    assert!(a.recv()? == vec!["from a done".as_bytes().to_vec()]);
+    assert!(b.recv()? == vec!["from b done".as_bytes().to_vec()]);
+
+    Ok(())
+}
+
+#[test]
+fn proxy_mirrors_to_capture() -> Result<(), Box<dyn Error>> {
+    let frontend = Socket::<Pull>::new().bind("127.0.0.1:3502")?;
+    let backend = Socket::<Push>::new().bind("127.0.0.1:3503")?;
+    let capture = Socket::<Push>::new().bind("127.0.0.1:3504")?;
+    let mut proxy = Proxy::new(frontend, backend).with_capture(capture);
+
+    let mut producer = Socket::<Push>::new().connect("127.0.0.1:3502")?;
+    let mut consumer = Socket::<Pull>::new().connect("127.0.0.1:3503")?;
+    let mut monitor = Socket::<Pull>::new().connect("127.0.0.1:3504")?;
+
+    for _ in 0..3 {
+        sleep(0.01);
+        proxy.step()?;
+        producer.tick()?;
+        consumer.tick()?;
+        monitor.tick()?;
+    }
+    assert!(proxy.capture().map(|capture| capture.peers()) == Some(1));
+
+    for i in 0..5u8 {
+        producer.send(&["job".as_bytes(), &[i]])?;
+    }
+    producer.tick()?;
+
+    sleep(0.01);
+    assert!(proxy.step()? == 5);
+
+    sleep(0.01);
+    consumer.tick()?;
+    monitor.tick()?;
+
+    for _ in 0..5 {
+        let job = consumer.recv()?;
+        assert!(monitor.recv()? == job);
+    }
+    assert!(consumer.recv().is_err() && monitor.recv().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn refused_message_waits_for_the_far_side() -> Result<(), Box<dyn Error>> {
+    let frontend = Socket::<Pull>::new().bind("127.0.0.1:3505")?;
+    let backend = Socket::<Push>::new().bind("127.0.0.1:3506")?;
+    let mut proxy = Proxy::new(frontend, backend);
+
+    let mut producer = Socket::<Push>::new().connect("127.0.0.1:3505")?;
+
+    for _ in 0..3 {
+        sleep(0.01);
+        proxy.step()?;
+        producer.tick()?;
+    }
+
+    // No consumer yet, Push refuses without a peer.
+    producer.send(&["job".as_bytes()])?;
+    producer.tick()?;
+    sleep(0.01);
+    assert!(proxy.step()? == 0);
+
+    let mut consumer = Socket::<Pull>::new().connect("127.0.0.1:3506")?;
+
+    let mut moved = 0;
+    for _ in 0..3 {
+        sleep(0.01);
+        moved += proxy.step()?;
+        consumer.tick()?;
+    }
+    assert!(moved == 1);
+
+    sleep(0.01);
+    consumer.tick()?;
+    assert!(consumer.recv()? == vec!["job".as_bytes().to_vec()]);
+
+    Ok(())
+}
+
+#[test]
+fn stalled_backend_holds_the_proxy_back_without_ending_it() -> Result<(), Box<dyn Error>> {
+    let frontend = Socket::<Pull>::new()
+        .set_recv_hwm(2)
+        .bind("127.0.0.1:3507")?;
+    let backend = Socket::<Push>::new().bind("127.0.0.1:3508")?;
+    let mut proxy = Proxy::new(frontend, backend);
+
+    let mut producer = Socket::<Push>::new().connect("127.0.0.1:3507")?;
+
+    for _ in 0..3 {
+        sleep(0.01);
+        proxy.step()?;
+        producer.tick()?;
+    }
+
+    // Nothing takes from the backend, so the frontend fills up to its hwm.
+    for i in 0..5u8 {
+        producer.send(&[&[i]])?;
+    }
+    producer.tick()?;
+
+    for _ in 0..3 {
+        sleep(0.01);
+        assert!(proxy.step()? == 0);
+    }
+
+    let mut consumer = Socket::<Pull>::new().connect("127.0.0.1:3508")?;
+
+    let mut moved = 0;
+    for _ in 0..3 {
+        sleep(0.01);
+        moved += proxy.step()?;
+        consumer.tick()?;
+    }
+    assert!(moved > 0);
+
+    sleep(0.01);
+    consumer.tick()?;
+    assert!(consumer.recv()? == vec![vec![0u8]]);
+
+    Ok(())
+}
@@ -0,0 +1,326 @@
+use std::{
+    error::Error,
+    io,
+    net::UdpSocket,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use nbmq::{
+    Addr, AsSocket, Dealer, Socket, Transport,
+    frame::{ControlFrame, DataFrame},
+    hmac::HmacSha256,
+    sha256::Sha256,
+    transport::UdpTransport,
+};
+
+fn sleep(n: f64) {
+    thread::sleep(Duration::from_secs_f64(n));
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[test]
+fn sha256_matches_known_digests() {
+    assert!(
+        hex(&Sha256::digest(b""))
+            == "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+    assert!(
+        hex(&Sha256::digest(b"abc"))
+            == "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    assert!(
+        hex(&Sha256::digest(
+            b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+        )) == "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+    );
+}
+
+#[test]
+fn hmac_matches_rfc4231() {
+    let key = [0x0bu8; 20];
+    assert!(
+        hex(&HmacSha256::mac(&key, b"Hi There"))
+            == "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+    );
+
+    assert!(
+        hex(&HmacSha256::mac(b"Jefe", b"what do ya want for nothing?"))
+            == "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+
+    let key = [0xaau8; 131];
+    assert!(
+        hex(&HmacSha256::mac(
+            &key,
+            b"Test Using Larger Than Block-Size Key - Hash Key First"
+        )) == "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+    );
+}
+
+#[test]
+fn psk_peers_connect_and_exchange() -> Result<(), Box<dyn Error>> {
+    let mut server = Socket::<Dealer>::new()
+        .set_psk("hunter2".as_bytes())
+        .bind("0.0.0.0:7100")?;
+    let mut client = Socket::<Dealer>::new()
+        .set_psk("hunter2".as_bytes())
+        .connect("127.0.0.1:7100")?;
+
+    sleep(0.01);
+    server.tick()?;
+    // Session is handed out, but not admitted until the client proves the key.
+    assert!(server.peers() == 0);
+
+    sleep(0.01);
+    client.tick()?;
+    assert!(client.peers() == 1);
+    client.send(&["hello".as_bytes()])?;
+    client.tick()?;
+
+    sleep(0.01);
+    server.tick()?;
+    assert!(server.peers() == 1);
+    assert!(server.recv()?[0] == "hello".as_bytes());
+
+    Ok(())
+}
+
+#[test]
+fn wrong_psk_is_dropped() -> Result<(), Box<dyn Error>> {
+    let mut server = Socket::<Dealer>::new()
+        .set_psk("hunter2".as_bytes())
+        .bind("0.0.0.0:7101")?;
+    let mut client = Socket::<Dealer>::new()
+        .set_psk("hunter3".as_bytes())
+        .connect("127.0.0.1:7101")?;
+
+    sleep(0.01);
+    server.tick()?;
+
+    sleep(0.01);
+    client.tick()?;
+
+    sleep(0.01);
+    server.tick()?;
+
+    assert!(server.peers() == 0);
+    assert!(client.peers() == 0);
+
+    Ok(())
+}
+
+#[test]
+fn missing_psk_is_dropped() -> Result<(), Box<dyn Error>> {
+    let mut server = Socket::<Dealer>::new()
+        .set_psk("hunter2".as_bytes())
+        .bind("0.0.0.0:7102")?;
+    let mut client = Socket::<Dealer>::new().connect("127.0.0.1:7102")?;
+
+    sleep(0.01);
+    server.tick()?;
+
+    sleep(0.01);
+    client.tick()?;
+
+    assert!(server.peers() == 0);
+    assert!(client.peers() == 0);
+
+    Ok(())
+}
+
+const HEARTBEAT: u8 = 4;
+
+type Recorded = Arc<Mutex<Option<(Vec<u8>, Addr)>>>;
+
+/// UDP keeping the last heartbeat it received, and handing it up again once per tick while
+/// `replay` is set.
+struct Replaying {
+    inner: UdpTransport,
+    recorded: Recorded,
+    replay: Arc<AtomicBool>,
+    replayed: bool,
+}
+
+impl Replaying {
+    fn new() -> io::Result<(Self, Recorded, Arc<AtomicBool>)> {
+        let recorded = Arc::new(Mutex::new(None));
+        let replay = Arc::new(AtomicBool::new(false));
+
+        Ok((
+            Self {
+                inner: UdpTransport::bind("0.0.0.0:0")?,
+                recorded: recorded.clone(),
+                replay: replay.clone(),
+                replayed: false,
+            },
+            recorded,
+            replay,
+        ))
+    }
+}
+
+impl Transport for Replaying {
+    fn bind(&mut self, addr: &str) -> io::Result<()> {
+        self.inner.bind(addr)
+    }
+
+    fn connect(&mut self, addr: &Addr) -> io::Result<()> {
+        self.inner.connect(addr)
+    }
+
+    fn send_to(&mut self, data: &[u8], addr: &Addr) -> io::Result<usize> {
+        self.inner.send_to(data, addr)
+    }
+
+    fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, Addr)> {
+        if self.replay.load(Ordering::SeqCst) && !self.replayed {
+            self.replayed = true;
+
+            if let Some((frame, addr)) = self.recorded.lock().unwrap().clone() {
+                buf[..frame.len()].copy_from_slice(&frame);
+                return Ok((frame.len(), addr));
+            }
+        }
+        self.replayed = false;
+
+        let (n, addr) = self.inner.recv_from(buf)?;
+        if n >= 2 && buf[1] == HEARTBEAT {
+            *self.recorded.lock().unwrap() = Some((buf[..n].to_vec(), addr.clone()));
+        }
+
+        Ok((n, addr))
+    }
+
+    fn local_addr(&self) -> io::Result<Addr> {
+        self.inner.local_addr()
+    }
+}
+
+#[test]
+fn replayed_heartbeat_doesnt_keep_a_silent_peer_alive() -> Result<(), Box<dyn Error>> {
+    let (transport, _, replay) = Replaying::new()?;
+    let mut server = Socket::<Dealer>::new()
+        .set_psk("hunter2".as_bytes())
+        .set_peer_keepalive(0.05)
+        .bind_with(transport, "127.0.0.1:7103")?;
+    let mut client = Socket::<Dealer>::new()
+        .set_psk("hunter2".as_bytes())
+        .connect("127.0.0.1:7103")?;
+
+    for _ in 0..3 {
+        sleep(0.01);
+        server.tick()?;
+        client.tick()?;
+    }
+    assert!(server.peers() == 1);
+
+    // The client goes quiet, only its captured heartbeat keeps arriving.
+    replay.store(true, Ordering::SeqCst);
+    for _ in 0..10 {
+        sleep(0.01);
+        server.tick()?;
+    }
+
+    assert!(server.peers() == 0);
+
+    Ok(())
+}
+
+#[test]
+fn replayed_heartbeat_doesnt_move_the_session() -> Result<(), Box<dyn Error>> {
+    let (transport, recorded, _) = Replaying::new()?;
+    let mut server = Socket::<Dealer>::new()
+        .set_psk("hunter2".as_bytes())
+        .bind_with(transport, "127.0.0.1:7104")?;
+    let mut client = Socket::<Dealer>::new()
+        .set_psk("hunter2".as_bytes())
+        .connect("127.0.0.1:7104")?;
+
+    for _ in 0..3 {
+        sleep(0.01);
+        server.tick()?;
+        client.tick()?;
+    }
+    assert!(server.peers() == 1);
+
+    let attacker = UdpSocket::bind("127.0.0.1:0")?;
+    attacker.set_nonblocking(true)?;
+
+    let (heartbeat, _) = recorded
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or("no heartbeat seen")?;
+    attacker.send_to(&heartbeat, "127.0.0.1:7104")?;
+
+    sleep(0.01);
+    server.tick()?;
+    server.send(&["still yours".as_bytes()])?;
+    server.tick()?;
+
+    sleep(0.01);
+    client.tick()?;
+    assert!(client.recv()? == vec!["still yours".as_bytes().to_vec()]);
+
+    let mut buf = [0u8; 1024];
+    assert!(attacker.recv_from(&mut buf).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn unauthenticated_data_doesnt_move_the_session() -> Result<(), Box<dyn Error>> {
+    let (transport, recorded, _) = Replaying::new()?;
+    let mut server = Socket::<Dealer>::new()
+        .set_psk("hunter2".as_bytes())
+        .bind_with(transport, "127.0.0.1:7105")?;
+    let mut client = Socket::<Dealer>::new()
+        .set_psk("hunter2".as_bytes())
+        .connect("127.0.0.1:7105")?;
+
+    for _ in 0..3 {
+        sleep(0.01);
+        server.tick()?;
+        client.tick()?;
+    }
+    assert!(server.peers() == 1);
+
+    // The session id travels in the clear, anyone on path can put it on a data frame.
+    let (heartbeat, _) = recorded
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or("no heartbeat seen")?;
+    let Some(ControlFrame::Heartbeat((session_id, _))) = ControlFrame::parse(&heartbeat)? else {
+        panic!("expected a heartbeat");
+    };
+
+    let attacker = UdpSocket::bind("127.0.0.1:0")?;
+    attacker.set_nonblocking(true)?;
+    attacker.send_to(
+        &DataFrame::encode(0, session_id, 1, 1, 0, 1, 1, 1, 0, &[0]),
+        "127.0.0.1:7105",
+    )?;
+
+    sleep(0.01);
+    server.tick()?;
+    server.send(&["still yours".as_bytes()])?;
+    server.tick()?;
+
+    sleep(0.01);
+    client.tick()?;
+    assert!(client.recv()? == vec!["still yours".as_bytes().to_vec()]);
+
+    let mut buf = [0u8; 1024];
+    assert!(attacker.recv_from(&mut buf).is_err());
+
+    Ok(())
+}
@@ -0,0 +1,210 @@
+use std::{error::Error, thread, time::Duration};
+
+use nbmq::{AsSocket, Pub, Socket, Sub};
+
+fn sleep(n: f64) {
+    thread::sleep(Duration::from_secs_f64(n));
+}
+
+fn settle(publisher: &mut Pub, subscribers: &mut [&mut Sub]) -> Result<(), Box<dyn Error>> {
+    for _ in 0..3 {
+        sleep(0.01);
+        publisher.tick()?;
+
+        for sub in subscribers.iter_mut() {
+            sub.tick()?;
+        }
+    }
+
+    sleep(0.01);
+    publisher.tick()?;
+
+    Ok(())
+}
+
+fn drain(sub: &mut Sub) -> Result<Vec<String>, Box<dyn Error>> {
+    sleep(0.01);
+    sub.tick()?;
+
+    let mut topics = vec![];
+    while let Ok(msg) = sub.recv() {
+        topics.push(String::from_utf8(msg[0].clone())?);
+    }
+
+    topics.sort();
+    Ok(topics)
+}
+
+#[test]
+fn pub_errors_on_recv() -> Result<(), Box<dyn Error>> {
+    let mut publisher = Socket::<Pub>::new().bind("127.0.0.1:7700")?;
+    assert!(publisher.recv().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn sub_errors_on_send() -> Result<(), Box<dyn Error>> {
+    let mut sub = Socket::<Sub>::new().connect("127.0.0.1:7701")?;
+    assert!(sub.send(&["test".as_bytes()]).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn publisher_filters_by_prefix() -> Result<(), Box<dyn Error>> {
+    let mut publisher = Socket::<Pub>::new().bind("127.0.0.1:7702")?;
+    let mut weather = Socket::<Sub>::new().connect("127.0.0.1:7702")?;
+    let mut prices = Socket::<Sub>::new().connect("127.0.0.1:7702")?;
+    let mut everything = Socket::<Sub>::new().connect("127.0.0.1:7702")?;
+
+    weather.subscribe("weather.".as_bytes())?;
+    prices.subscribe("prices.".as_bytes())?;
+    everything.subscribe(&[])?;
+
+    settle(
+        &mut publisher,
+        &mut [&mut weather, &mut prices, &mut everything],
+    )?;
+    assert!(publisher.peers() == 3);
+
+    publisher.send(&["weather.oslo".as_bytes(), "-3".as_bytes()])?;
+    publisher.send(&["prices.btc".as_bytes(), "1".as_bytes()])?;
+    publisher.send(&["news".as_bytes(), "none".as_bytes()])?;
+    publisher.tick()?;
+
+    assert!(drain(&mut weather)? == vec!["weather.oslo"]);
+    assert!(drain(&mut prices)? == vec!["prices.btc"]);
+    assert!(drain(&mut everything)? == vec!["news", "prices.btc", "weather.oslo"]);
+
+    Ok(())
+}
+
+#[test]
+fn unwanted_messages_stay_off_the_wire() -> Result<(), Box<dyn Error>> {
+    let mut publisher = Socket::<Pub>::new().bind("127.0.0.1:7703")?;
+    let mut sub = Socket::<Sub>::new().connect("127.0.0.1:7703")?;
+
+    settle(&mut publisher, &mut [&mut sub])?;
+
+    // With no subscription at all nothing is queued, so even a full send queue can't form.
+    for _ in 0..2000 {
+        publisher.send(&["weather".as_bytes()])?;
+    }
+    publisher.tick()?;
+
+    assert!(drain(&mut sub)?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn unsubscribe_stops_delivery() -> Result<(), Box<dyn Error>> {
+    let mut publisher = Socket::<Pub>::new().bind("127.0.0.1:7704")?;
+    let mut sub = Socket::<Sub>::new().connect("127.0.0.1:7704")?;
+
+    sub.subscribe("a".as_bytes())?;
+    sub.subscribe("b".as_bytes())?;
+    settle(&mut publisher, &mut [&mut sub])?;
+
+    publisher.send(&["a".as_bytes()])?;
+    publisher.send(&["b".as_bytes()])?;
+    publisher.tick()?;
+    assert!(drain(&mut sub)? == vec!["a", "b"]);
+
+    sub.unsubscribe("a".as_bytes())?;
+    settle(&mut publisher, &mut [&mut sub])?;
+
+    publisher.send(&["a".as_bytes()])?;
+    publisher.send(&["b".as_bytes()])?;
+    publisher.tick()?;
+    assert!(drain(&mut sub)? == vec!["b"]);
+
+    Ok(())
+}
+
+#[test]
+fn subscriptions_survive_reconnect() -> Result<(), Box<dyn Error>> {
+    let mut sub = Socket::<Sub>::new()
+        .set_reconnect_wait(0.01)
+        .connect("127.0.0.1:7705")?;
+    sub.subscribe("a".as_bytes())?;
+    sub.tick()?;
+
+    // The publisher comes up after the subscription was made.
+    let mut publisher = Socket::<Pub>::new().bind("127.0.0.1:7705")?;
+
+    for _ in 0..5 {
+        sleep(0.02);
+        sub.tick()?;
+        publisher.tick()?;
+    }
+    assert!(publisher.peers() == 1);
+
+    publisher.send(&["a".as_bytes()])?;
+    publisher.tick()?;
+    assert!(drain(&mut sub)? == vec!["a"]);
+
+    Ok(())
+}
+
+#[test]
+fn subscriptions_are_refreshed() -> Result<(), Box<dyn Error>> {
+    let mut publisher = Socket::<Pub>::new().bind("127.0.0.1:7706")?;
+    let mut sub = Socket::<Sub>::new()
+        .set_peer_heartbeat_ivl(0.02)
+        .connect("127.0.0.1:7706")?;
+
+    settle(&mut publisher, &mut [&mut sub])?;
+    assert!(publisher.peers() == 1);
+
+    // Subscribing before the next refresh reaches the publisher either way.
+    sub.subscribe("late".as_bytes())?;
+
+    for _ in 0..3 {
+        sleep(0.02);
+        sub.tick()?;
+        publisher.tick()?;
+    }
+
+    publisher.send(&["late".as_bytes()])?;
+    publisher.tick()?;
+    assert!(drain(&mut sub)? == vec!["late"]);
+
+    Ok(())
+}
+
+#[test]
+fn full_subscriber_holds_the_message_back_from_all() -> Result<(), Box<dyn Error>> {
+    let mut publisher = Socket::<Pub>::new()
+        .set_send_hwm(2)
+        .bind("127.0.0.1:7707")?;
+    let mut slow = Socket::<Sub>::new().connect("127.0.0.1:7707")?;
+    let mut narrow = (0..4)
+        .map(|_| Socket::<Sub>::new().connect("127.0.0.1:7707"))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    slow.subscribe("weather.".as_bytes())?;
+    for sub in narrow.iter_mut() {
+        sub.subscribe("weather.oslo".as_bytes())?;
+    }
+
+    let mut subs = vec![&mut slow];
+    subs.extend(narrow.iter_mut());
+    settle(&mut publisher, &mut subs)?;
+    assert!(publisher.peers() == 5);
+
+    // Only the broad subscriber's queue fills.
+    publisher.send(&["weather.rome".as_bytes()])?;
+    publisher.send(&["weather.lima".as_bytes()])?;
+
+    assert!(publisher.send(&["weather.oslo".as_bytes()]).is_err());
+    publisher.tick()?;
+
+    for sub in narrow.iter_mut() {
+        assert!(drain(sub)?.is_empty());
+    }
+    assert!(drain(&mut slow)? == vec!["weather.lima", "weather.rome"]);
+
+    Ok(())
+}
@@ -0,0 +1,140 @@
+use std::{error::Error, thread, time::Duration};
+
+use nbmq::{AsSocket, Pull, Push, Socket};
+
+fn sleep(n: f64) {
+    thread::sleep(Duration::from_secs_f64(n));
+}
+
+fn drain(pull: &mut Pull) -> Result<Vec<Vec<Vec<u8>>>, Box<dyn Error>> {
+    sleep(0.01);
+    pull.tick()?;
+
+    let mut messages = vec![];
+    while let Ok(msg) = pull.recv() {
+        messages.push(msg);
+    }
+
+    Ok(messages)
+}
+
+#[test]
+fn push_errors_on_recv_and_pull_on_send() -> Result<(), Box<dyn Error>> {
+    let mut push = Socket::<Push>::new().bind("127.0.0.1:3100")?;
+    let mut pull = Socket::<Pull>::new().connect("127.0.0.1:3100")?;
+
+    assert!(push.recv().is_err());
+    assert!(pull.send(&["job".as_bytes()]).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn push_spreads_jobs_across_idle_workers() -> Result<(), Box<dyn Error>> {
+    let mut push = Socket::<Push>::new().bind("127.0.0.1:3101")?;
+    let mut a = Socket::<Pull>::new().connect("127.0.0.1:3101")?;
+    let mut b = Socket::<Pull>::new().connect("127.0.0.1:3101")?;
+
+    for _ in 0..3 {
+        sleep(0.01);
+        push.tick()?;
+        a.tick()?;
+        b.tick()?;
+    }
+    assert!(push.peers() == 2);
+
+    for i in 0..10u8 {
+        push.send(&[&[i]])?;
+    }
+    push.tick()?;
+
+    assert!(drain(&mut a)?.len() == 5);
+    assert!(drain(&mut b)?.len() == 5);
+
+    Ok(())
+}
+
+#[test]
+fn push_prefers_shallowest_queue() -> Result<(), Box<dyn Error>> {
+    // One frame per tick, so a backlog builds up in front of the first worker.
+    let mut push = Socket::<Push>::new()
+        .set_max_tick_send(0)
+        .bind("127.0.0.1:3102")?;
+    let mut a = Socket::<Pull>::new().connect("127.0.0.1:3102")?;
+
+    for _ in 0..3 {
+        sleep(0.01);
+        push.tick()?;
+        a.tick()?;
+    }
+    assert!(push.peers() == 1);
+
+    for i in 0..6u8 {
+        push.send(&["a".as_bytes(), &[i]])?;
+    }
+
+    let mut b = Socket::<Pull>::new().connect("127.0.0.1:3102")?;
+
+    for _ in 0..3 {
+        sleep(0.01);
+        push.tick()?;
+        a.tick()?;
+        b.tick()?;
+    }
+    assert!(push.peers() == 2);
+
+    // Round robin would hand the backlogged worker some of these, shallowest queue doesn't.
+    for i in 0..3u8 {
+        push.send(&["b".as_bytes(), &[i]])?;
+    }
+
+    for _ in 0..20 {
+        push.tick()?;
+    }
+
+    let mut at_a = drain(&mut a)?;
+    at_a.retain(|msg| msg[0] == "b".as_bytes());
+    let at_b = drain(&mut b)?;
+
+    assert!(at_a.is_empty());
+    assert!(at_b.len() == 3);
+
+    Ok(())
+}
+
+#[test]
+fn pull_fair_queues_pushers() -> Result<(), Box<dyn Error>> {
+    let mut pull = Socket::<Pull>::new().bind("127.0.0.1:3103")?;
+    let mut a = Socket::<Push>::new().connect("127.0.0.1:3103")?;
+    let mut b = Socket::<Push>::new().connect("127.0.0.1:3103")?;
+
+    for _ in 0..3 {
+        sleep(0.01);
+        pull.tick()?;
+        a.tick()?;
+        b.tick()?;
+    }
+    assert!(pull.peers() == 2);
+
+    // Everything from a lands before anything from b.
+    for i in 0..5u8 {
+        a.send(&["a".as_bytes(), &[i]])?;
+    }
+    a.tick()?;
+    sleep(0.01);
+
+    for i in 0..5u8 {
+        b.send(&["b".as_bytes(), &[i]])?;
+    }
+    b.tick()?;
+
+    let messages = drain(&mut pull)?;
+    assert!(messages.len() == 10);
+
+    for pair in messages.chunks(2) {
+        assert!(pair[0][0] != pair[1][0]);
+        assert!(pair[0][1] == pair[1][1]);
+    }
+
+    Ok(())
+}
@@ -137,3 +137,249 @@ pub fn safe_send_queue_will_stop_sending_when_confirmed() {
     assert!(sq.sent.len() == 0);
     assert!(sq.exp.len() == 0);
 }
+
+#[test]
+pub fn safe_send_queue_confirms_many_frames_per_sack() {
+    let opt = SockOpt::default();
+    let mut sq = SendQueue::new(opt.clone());
+
+    let a = message(5000);
+    let ref_a = a.iter().map(|x| x.as_slice()).collect::<Vec<_>>();
+    sq.push(0, ref_a.as_slice(), 0).unwrap();
+
+    let mut frames = vec![];
+    while let Some(f) = sq.pull_safe() {
+        frames.push(f);
+    }
+    assert!(frames.len() > 6 && sq.sent.len() == frames.len());
+
+    // Everything below 2, and 4 up to 6.
+    assert!(sq.confirm_sack(2, &[(4, 6)]) == 4);
+    assert!(sq.sent.len() == frames.len() - 4);
+
+    // Repeats confirm nothing new.
+    assert!(sq.confirm_sack(2, &[(4, 6)]) == 0);
+
+    assert!(sq.confirm_sack(frames.len() as u64, &[]) == frames.len() - 4);
+    assert!(sq.sent.is_empty() && sq.sequenced.is_empty());
+}
+
+#[test]
+pub fn recv_queue_batches_sequences_into_sacks() {
+    let opt = SockOpt::default();
+    let mut sq = SendQueue::new(opt.clone());
+    let mut rq = RecvQueue::new(opt);
+
+    let a = message(5000);
+    let ref_a = a.iter().map(|x| x.as_slice()).collect::<Vec<_>>();
+    sq.push(7, ref_a.as_slice(), 0).unwrap();
+
+    let mut frames = vec![];
+    while let Some(f) = sq.pull_safe() {
+        frames.push(DataFrame::parse(&f).unwrap().unwrap());
+    }
+
+    for i in [0, 1, 3, 4, 6] {
+        let df = &frames[i];
+        rq.push(df).unwrap();
+        rq.record_sequence(df.session_id, df.sequence.unwrap());
+    }
+
+    let sacks = rq.take_sacks(16);
+    assert!(sacks.len() == 1);
+    assert!(sacks[0].session_id == 7 && sacks[0].cumulative == 2);
+    assert!(sacks[0].ranges == vec![(3, 5), (6, 7)]);
+
+    // Nothing new taken, nothing to acknowledge.
+    assert!(rq.take_sacks(16).is_empty());
+
+    // Split when the ranges don't fit in one.
+    rq.record_sequence(7, 8);
+    assert!(rq.take_sacks(2).len() == 2);
+}
+
+#[test]
+pub fn stalled_message_nacks_only_missing_frames() {
+    let opt = SockOpt {
+        safe_nack_ivl: Duration::from_secs_f64(0.01),
+        ..Default::default()
+    };
+
+    let mut sq = SendQueue::new(opt.clone());
+    let mut rq = RecvQueue::new(opt);
+
+    let a = message(5000);
+    let ref_a = a.iter().map(|x| x.as_slice()).collect::<Vec<_>>();
+    sq.push(0, ref_a.as_slice(), 0).unwrap();
+
+    let mut frames = vec![];
+    while let Some(f) = sq.pull_safe() {
+        frames.push(f);
+    }
+
+    for (i, f) in frames.iter().enumerate() {
+        if i != 2 && i != 5 {
+            rq.push(&DataFrame::parse(f).unwrap().unwrap()).unwrap();
+        }
+    }
+
+    // Not stalled for long enough yet.
+    assert!(rq.take_nacks(16).is_empty());
+    sleep(0.02);
+
+    let nacks = rq.take_nacks(16);
+    assert!(nacks.len() == 1 && nacks[0].ranges.len() == 2);
+    assert!(rq.take_nacks(16).is_empty());
+
+    assert!(sq.nack(nacks[0].message_id, &nacks[0].ranges) == 2);
+
+    let mut resent = vec![sq.pull_safe().unwrap(), sq.pull_safe().unwrap()];
+    resent.sort();
+    let mut lost = vec![frames[2].clone(), frames[5].clone()];
+    lost.sort();
+
+    assert!(resent == lost);
+    assert!(sq.pull_safe().is_none());
+
+    for f in resent.iter() {
+        rq.push(&DataFrame::parse(f).unwrap().unwrap()).unwrap();
+    }
+    assert!(rq.pull().unwrap().0 == a);
+}
+
+#[test]
+pub fn adaptive_rto_backs_off_from_measured_rtt() {
+    let opt = SockOpt {
+        safe_resend_ivl: Duration::from_secs_f64(1.),
+        adaptive_rto: true,
+        ..Default::default()
+    };
+    let mut sq = SendQueue::new(opt);
+
+    // Srtt 2ms, rttvar 1ms, a timeout of 6ms instead of the static second.
+    sq.rtt.sample(Duration::from_millis(2));
+    assert!(sq.rtt.rto(0) == Duration::from_millis(6));
+
+    sq.push(0, &["hello".as_bytes()], 0).unwrap();
+    let frame = sq.pull_safe().unwrap();
+
+    sleep(0.01);
+    assert!(sq.pull_safe() == Some(frame.clone()));
+
+    // The resend waits twice as long.
+    sleep(0.006);
+    assert!(sq.pull_safe().is_none());
+
+    sleep(0.01);
+    assert!(sq.pull_safe() == Some(frame));
+}
+
+#[test]
+pub fn congestion_window_gates_new_frames() {
+    let opt = SockOpt {
+        max_frame_size: 100,
+        safe_resend_ivl: Duration::from_secs_f64(0.01),
+        congestion_control: true,
+        ..Default::default()
+    };
+    let mut sq = SendQueue::new(opt);
+
+    let m = message(5000);
+    let ref_m = m.iter().map(|x| x.as_slice()).collect::<Vec<_>>();
+    sq.push(0, ref_m.as_slice(), 0).unwrap();
+
+    let mut sent = 0;
+    while sq.pull_safe().is_some() {
+        sent += 1;
+    }
+    assert!(sent == 10 && sq.stats().in_flight == 10);
+
+    // Slow start, one more frame per frame acked.
+    assert!(sq.confirm_sack(4, &[]) == 4);
+    assert!(sq.stats().cwnd == 14);
+
+    let mut sent = 0;
+    while sq.pull_safe().is_some() {
+        sent += 1;
+    }
+    assert!(sent == 8);
+
+    // The resend timer firing halves the window.
+    sleep(0.02);
+    assert!(sq.pull_safe().is_some());
+    assert!(sq.stats().cwnd == 7);
+}
+
+#[test]
+pub fn nacks_within_one_rto_cut_the_window_once() {
+    let opt = SockOpt {
+        max_frame_size: 100,
+        congestion_control: true,
+        ..Default::default()
+    };
+    let mut sq = SendQueue::new(opt);
+
+    let m = message(5000);
+    let ref_m = m.iter().map(|x| x.as_slice()).collect::<Vec<_>>();
+    sq.push(0, ref_m.as_slice(), 0).unwrap();
+
+    let mut frames = vec![];
+    while let Some(f) = sq.pull_safe() {
+        frames.push(DataFrame::parse(&f).unwrap().unwrap());
+    }
+    assert!(frames.len() == 10);
+
+    for f in frames[2..4].iter() {
+        let range = (f.part_index, f.chunk_offset, f.chunk_offset + 1);
+        assert!(sq.nack(f.message_id, &[range]) == 1);
+    }
+    assert!(sq.stats().cwnd == 5);
+
+    // Frames confirmed are no longer asked for.
+    assert!(sq.confirm_sack(10, &[]) == 10);
+    let f = &frames[5];
+    assert!(sq.nack(f.message_id, &[(f.part_index, 0, u32::MAX)]) == 0);
+}
+
+#[test]
+pub fn recv_queue_frees_stuck_sequenced_messages() {
+    let opt = SockOpt {
+        uncompleted_message_ttl: Duration::from_secs_f64(0.01),
+        queue_maint_ivl: Duration::from_secs_f64(0.01),
+        safe_resend_ivl: Duration::from_secs_f64(0.01),
+        safe_resend_limit: 2,
+        ..Default::default()
+    };
+
+    let mut rq = RecvQueue::new(opt.clone());
+
+    // Both messages miss their last frame, session 1 goes away meanwhile.
+    for session in [0, 1] {
+        let mut sq = SendQueue::new(opt.clone());
+        let a = message(5000);
+        let ref_a = a.iter().map(|x| x.as_slice()).collect::<Vec<_>>();
+        sq.push(session, ref_a.as_slice(), 0).unwrap();
+
+        let mut frames = vec![];
+        while let Some(f) = sq.pull_safe() {
+            frames.push(DataFrame::parse(&f).unwrap().unwrap());
+        }
+        frames.pop();
+
+        for df in frames.iter() {
+            rq.push(df).unwrap();
+            rq.record_sequence(df.session_id, df.sequence.unwrap());
+        }
+    }
+
+    rq.sacks.remove(&1);
+
+    sleep(0.02);
+    assert!(rq.pull().is_none());
+    assert!(rq.incoming.len() == 1);
+    assert!(rq.incoming.keys().all(|(session, _)| *session == 0));
+
+    sleep(0.03);
+    assert!(rq.pull().is_none());
+    assert!(rq.incoming.is_empty());
+}
@@ -0,0 +1,222 @@
+use std::{error::Error, io, thread, time::Duration};
+
+use nbmq::{AsSocket, Dealer, Rep, Req, Socket};
+
+fn sleep(n: f64) {
+    thread::sleep(Duration::from_secs_f64(n));
+}
+
+fn settle(rep: &mut Rep, reqs: &mut [&mut Req]) -> Result<(), Box<dyn Error>> {
+    for _ in 0..3 {
+        sleep(0.01);
+        rep.tick()?;
+
+        for req in reqs.iter_mut() {
+            req.tick()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Flush `from` and take what arrived into `to`.
+fn deliver<A: AsSocket, B: AsSocket>(from: &mut A, to: &mut B) -> Result<(), Box<dyn Error>> {
+    sleep(0.01);
+    from.tick()?;
+    sleep(0.01);
+    to.tick()?;
+
+    Ok(())
+}
+
+fn parts(message: &[Vec<u8>]) -> Vec<&[u8]> {
+    message.iter().map(|part| part.as_slice()).collect()
+}
+
+#[test]
+fn request_reply() -> Result<(), Box<dyn Error>> {
+    let mut rep = Socket::<Rep>::new().bind("127.0.0.1:7900")?;
+    let mut req = Socket::<Req>::new().connect("127.0.0.1:7900")?;
+    settle(&mut rep, &mut [&mut req])?;
+
+    for i in 0..3u8 {
+        req.send(&["ping".as_bytes(), &[i]])?;
+        deliver(&mut req, &mut rep)?;
+
+        let request = rep.recv()?;
+        assert!(request == vec!["ping".as_bytes().to_vec(), vec![i]]);
+
+        rep.send(&["pong".as_bytes(), &request[1]])?;
+        deliver(&mut rep, &mut req)?;
+
+        let reply = req.recv()?;
+        assert!(reply == vec!["pong".as_bytes().to_vec(), vec![i]]);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn req_enforces_alternation() -> Result<(), Box<dyn Error>> {
+    let mut rep = Socket::<Rep>::new().bind("127.0.0.1:7901")?;
+    let mut req = Socket::<Req>::new().connect("127.0.0.1:7901")?;
+    settle(&mut rep, &mut [&mut req])?;
+
+    assert!(req.recv().is_err());
+
+    req.send(&["one".as_bytes()])?;
+    assert!(req.send(&["two".as_bytes()]).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn rep_enforces_alternation() -> Result<(), Box<dyn Error>> {
+    let mut rep = Socket::<Rep>::new().bind("127.0.0.1:7902")?;
+    let mut a = Socket::<Req>::new().connect("127.0.0.1:7902")?;
+    let mut b = Socket::<Req>::new().connect("127.0.0.1:7902")?;
+    settle(&mut rep, &mut [&mut a, &mut b])?;
+
+    assert!(rep.send(&["unprompted".as_bytes()]).is_err());
+
+    a.send(&["a".as_bytes()])?;
+    b.send(&["b".as_bytes()])?;
+    deliver(&mut a, &mut rep)?;
+    deliver(&mut b, &mut rep)?;
+
+    let first = rep.recv()?;
+    assert!(rep.recv().is_err());
+    rep.send(&parts(&first))?;
+
+    let second = rep.recv()?;
+    rep.send(&parts(&second))?;
+
+    deliver(&mut rep, &mut a)?;
+    b.tick()?;
+
+    // Each reply went back to the session that asked.
+    assert!(a.recv()? == vec!["a".as_bytes().to_vec()]);
+    assert!(b.recv()? == vec!["b".as_bytes().to_vec()]);
+
+    Ok(())
+}
+
+#[test]
+fn req_retries_unanswered_request() -> Result<(), Box<dyn Error>> {
+    let mut req = Socket::<Req>::new()
+        .set_req_timeout(0.05)
+        .set_req_retries(3)
+        .bind("127.0.0.1:7903")?;
+    let mut a = Socket::<Rep>::new().connect("127.0.0.1:7903")?;
+    let mut b = Socket::<Rep>::new().connect("127.0.0.1:7903")?;
+
+    for _ in 0..3 {
+        sleep(0.01);
+        a.tick()?;
+        b.tick()?;
+        req.tick()?;
+    }
+    assert!(req.peers() == 2);
+
+    req.send(&["ping".as_bytes()])?;
+    sleep(0.01);
+    req.tick()?;
+    sleep(0.01);
+    a.tick()?;
+    b.tick()?;
+
+    // Whichever replier got the request sits on it, the retry goes to the other one.
+    let (silent, mut responsive) = match a.recv() {
+        Ok(_) => (a, b),
+        Err(_) => {
+            assert!(b.recv().is_ok());
+            (b, a)
+        }
+    };
+
+    for _ in 0..5 {
+        sleep(0.02);
+        req.tick()?;
+        responsive.tick()?;
+    }
+
+    let request = responsive.recv()?;
+    assert!(request == vec!["ping".as_bytes().to_vec()]);
+    responsive.send(&["pong".as_bytes()])?;
+    deliver(&mut responsive, &mut req)?;
+
+    assert!(req.recv()? == vec!["pong".as_bytes().to_vec()]);
+    drop(silent);
+
+    Ok(())
+}
+
+#[test]
+fn req_gives_up_after_retries() -> Result<(), Box<dyn Error>> {
+    let mut rep = Socket::<Rep>::new().bind("127.0.0.1:7904")?;
+    let mut req = Socket::<Req>::new()
+        .set_req_timeout(0.02)
+        .set_req_retries(2)
+        .connect("127.0.0.1:7904")?;
+    settle(&mut rep, &mut [&mut req])?;
+
+    req.send(&["ping".as_bytes()])?;
+
+    for _ in 0..6 {
+        sleep(0.02);
+        req.tick()?;
+    }
+
+    let err = req.recv().err().ok_or("expected timeout")?;
+    assert!(err.downcast_ref::<io::Error>().map(|e| e.kind()) == Some(io::ErrorKind::TimedOut));
+
+    // Requests sent before the timeout, answered late, never surface.
+    rep.tick()?;
+    while let Ok(request) = rep.recv() {
+        rep.send(&parts(&request))?;
+    }
+    rep.tick()?;
+
+    req.send(&["again".as_bytes()])?;
+    deliver(&mut req, &mut rep)?;
+    let request = rep.recv()?;
+    rep.send(&parts(&request))?;
+    deliver(&mut rep, &mut req)?;
+
+    assert!(req.recv()? == vec!["again".as_bytes().to_vec()]);
+
+    Ok(())
+}
+
+#[test]
+fn refused_reply_can_be_retried() -> Result<(), Box<dyn Error>> {
+    let mut rep = Socket::<Rep>::new()
+        .set_send_hwm(1)
+        .bind("127.0.0.1:7905")?;
+    let mut requester = Socket::<Dealer>::new().connect("127.0.0.1:7905")?;
+
+    for _ in 0..3 {
+        deliver(&mut requester, &mut rep)?;
+    }
+
+    // Two requests taken in one tick, the second reply finds the first still queued.
+    requester.send(&[&1u64.to_be_bytes(), "a".as_bytes()])?;
+    requester.send(&[&2u64.to_be_bytes(), "b".as_bytes()])?;
+    deliver(&mut requester, &mut rep)?;
+
+    assert!(rep.recv()? == vec!["a".as_bytes().to_vec()]);
+    rep.send(&["for a".as_bytes()])?;
+
+    assert!(rep.recv()? == vec!["b".as_bytes().to_vec()]);
+    assert!(rep.send(&["for b".as_bytes()]).is_err());
+    assert!(rep.send(&["for b".as_bytes()]).is_err());
+
+    deliver(&mut rep, &mut requester)?;
+    rep.send(&["for b".as_bytes()])?;
+    deliver(&mut rep, &mut requester)?;
+
+    assert!(requester.recv()? == vec![1u64.to_be_bytes().to_vec(), "for a".as_bytes().to_vec()]);
+    assert!(requester.recv()? == vec![2u64.to_be_bytes().to_vec(), "for b".as_bytes().to_vec()]);
+
+    Ok(())
+}
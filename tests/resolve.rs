@@ -0,0 +1,215 @@
+use std::{
+    error::Error,
+    io,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use nbmq::{Addr, AsSocket, Dealer, Socket, Transport, transport::UdpTransport};
+
+fn sleep(n: f64) {
+    thread::sleep(Duration::from_secs_f64(n));
+}
+
+/// UDP with a resolver backed by a table the test can rewrite, standing in for DNS.
+struct Resolver {
+    inner: UdpTransport,
+    table: Arc<Mutex<Vec<Addr>>>,
+}
+
+impl Resolver {
+    fn new(table: &Arc<Mutex<Vec<Addr>>>) -> io::Result<Self> {
+        Ok(Self {
+            inner: UdpTransport::bind("0.0.0.0:0")?,
+            table: table.clone(),
+        })
+    }
+}
+
+impl Transport for Resolver {
+    fn bind(&mut self, addr: &str) -> io::Result<()> {
+        self.inner.bind(addr)
+    }
+
+    fn resolve(&self, _addr: &str) -> io::Result<Vec<Addr>> {
+        Ok(self.table.lock().unwrap().clone())
+    }
+
+    fn connect(&mut self, addr: &Addr) -> io::Result<()> {
+        self.inner.connect(addr)
+    }
+
+    fn send_to(&mut self, data: &[u8], addr: &Addr) -> io::Result<usize> {
+        self.inner.send_to(data, addr)
+    }
+
+    fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, Addr)> {
+        self.inner.recv_from(buf)
+    }
+
+    fn local_addr(&self) -> io::Result<Addr> {
+        self.inner.local_addr()
+    }
+}
+
+fn inet(addr: &str) -> Addr {
+    Addr::Inet(addr.parse().unwrap())
+}
+
+fn tick_until_connected(
+    server: &mut Dealer,
+    client: &mut Dealer,
+    ticks: usize,
+) -> Result<bool, Box<dyn Error>> {
+    for _ in 0..ticks {
+        server.tick()?;
+        client.tick()?;
+
+        if server.peers() == 1 && client.peers() == 1 {
+            return Ok(true);
+        }
+
+        sleep(0.01);
+    }
+
+    Ok(false)
+}
+
+#[test]
+fn connects_by_hostname() -> Result<(), Box<dyn Error>> {
+    let mut server = Socket::<Dealer>::new().bind("127.0.0.1:7500")?;
+    let mut client = Socket::<Dealer>::new()
+        .set_reconnect_wait(0.02)
+        .connect("localhost:7500")?;
+
+    assert!(tick_until_connected(&mut server, &mut client, 50)?);
+
+    Ok(())
+}
+
+#[test]
+fn connects_by_hostname_over_tcp() -> Result<(), Box<dyn Error>> {
+    let mut server = Socket::<Dealer>::new().bind("tcp://127.0.0.1:7501")?;
+    let mut client = Socket::<Dealer>::new()
+        .set_reconnect_wait(0.02)
+        .connect("tcp://localhost:7501")?;
+
+    assert!(tick_until_connected(&mut server, &mut client, 50)?);
+
+    Ok(())
+}
+
+#[test]
+fn unresolvable_hostname_errors() {
+    assert!(
+        Socket::<Dealer>::new()
+            .connect("nonexistent.invalid:7502")
+            .is_err()
+    );
+}
+
+#[test]
+fn tries_every_resolved_address() -> Result<(), Box<dyn Error>> {
+    // Nothing listens on the first address.
+    let table = Arc::new(Mutex::new(vec![
+        inet("127.0.0.1:7503"),
+        inet("127.0.0.1:7504"),
+    ]));
+
+    let mut server = Socket::<Dealer>::new().bind("127.0.0.1:7504")?;
+    let mut client = Socket::<Dealer>::new()
+        .set_reconnect_wait(0.02)
+        .connect_with(Resolver::new(&table)?, "broker.local:7504")?;
+
+    assert!(tick_until_connected(&mut server, &mut client, 50)?);
+
+    Ok(())
+}
+
+#[test]
+fn reresolves_on_reconnect() -> Result<(), Box<dyn Error>> {
+    let table = Arc::new(Mutex::new(vec![inet("127.0.0.1:7505")]));
+
+    let mut server = Socket::<Dealer>::new()
+        .set_peer_keepalive(0.05)
+        .bind("127.0.0.1:7505")?;
+    let mut client = Socket::<Dealer>::new()
+        .set_peer_keepalive(0.05)
+        .set_reconnect_wait(0.02)
+        .set_reresolve_on_reconnect(true)
+        .connect_with(Resolver::new(&table)?, "broker.local:7505")?;
+
+    assert!(tick_until_connected(&mut server, &mut client, 50)?);
+
+    // The broker comes back somewhere else, and its record is updated.
+    drop(server);
+    *table.lock().unwrap() = vec![inet("127.0.0.1:7506")];
+    let mut server = Socket::<Dealer>::new().bind("127.0.0.1:7506")?;
+
+    assert!(tick_until_connected(&mut server, &mut client, 100)?);
+
+    Ok(())
+}
+
+#[test]
+fn keeps_resolved_addresses_without_reresolve() -> Result<(), Box<dyn Error>> {
+    let table = Arc::new(Mutex::new(vec![inet("127.0.0.1:7507")]));
+
+    let mut server = Socket::<Dealer>::new()
+        .set_peer_keepalive(0.05)
+        .bind("127.0.0.1:7507")?;
+    let mut client = Socket::<Dealer>::new()
+        .set_peer_keepalive(0.05)
+        .set_reconnect_wait(0.02)
+        .connect_with(Resolver::new(&table)?, "broker.local:7507")?;
+
+    assert!(tick_until_connected(&mut server, &mut client, 50)?);
+
+    drop(server);
+    *table.lock().unwrap() = vec![inet("127.0.0.1:7508")];
+    let mut server = Socket::<Dealer>::new().bind("127.0.0.1:7508")?;
+
+    assert!(!tick_until_connected(&mut server, &mut client, 30)?);
+
+    Ok(())
+}
+
+#[test]
+fn reresolves_hostnames_on_reconnect() -> Result<(), Box<dyn Error>> {
+    let mut client = Socket::<Dealer>::new()
+        .set_reconnect_wait(0.02)
+        .set_reresolve_on_reconnect(true)
+        .connect("localhost:7509")?;
+
+    // Lookups run in the background while nothing answers.
+    for _ in 0..10 {
+        client.tick()?;
+        sleep(0.01);
+    }
+
+    let mut server = Socket::<Dealer>::new().bind("127.0.0.1:7509")?;
+    assert!(tick_until_connected(&mut server, &mut client, 100)?);
+
+    Ok(())
+}
+
+#[test]
+fn reresolve_doesnt_wait_on_the_lookup() -> Result<(), Box<dyn Error>> {
+    let mut transport = UdpTransport::ephemeral()?;
+
+    let started = transport.reresolve("localhost:7510");
+    assert!(started.is_err_and(|e| e.kind() == io::ErrorKind::WouldBlock));
+
+    let mut resolved = vec![];
+    for _ in 0..100 {
+        sleep(0.01);
+        if let Ok(addrs) = transport.reresolve("localhost:7510") {
+            resolved = addrs;
+            break;
+        }
+    }
+    assert!(!resolved.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use std::{error::Error, thread, time::Duration};
+
+use nbmq::{AsSocket, Dealer, Router, Socket};
+
+fn sleep(n: f64) {
+    thread::sleep(Duration::from_secs_f64(n));
+}
+
+fn settle(router: &mut Router, dealers: &mut [&mut Dealer]) -> Result<(), Box<dyn Error>> {
+    for _ in 0..3 {
+        sleep(0.01);
+        router.tick()?;
+
+        for dealer in dealers.iter_mut() {
+            dealer.tick()?;
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn router_replies_to_sender() -> Result<(), Box<dyn Error>> {
+    let mut router = Socket::<Router>::new().bind("127.0.0.1:3000")?;
+    let mut a = Socket::<Dealer>::new().connect("127.0.0.1:3000")?;
+    let mut b = Socket::<Dealer>::new().connect("127.0.0.1:3000")?;
+    settle(&mut router, &mut [&mut a, &mut b])?;
+    assert!(router.peers() == 2);
+
+    a.send(&["from a".as_bytes()])?;
+    b.send(&["from b".as_bytes()])?;
+    a.tick()?;
+    b.tick()?;
+
+    sleep(0.01);
+    router.tick()?;
+
+    for _ in 0..2 {
+        let msg = router.recv()?;
+        assert!(msg.len() == 2 && msg[0].len() == 8);
+
+        let reply = [msg[1].as_slice(), "echo".as_bytes()].concat();
+        router.send(&[&msg[0], &reply])?;
+    }
+    router.tick()?;
+
+    sleep(0.01);
+    a.tick()?;
+    b.tick()?;
+
+    assert!(a.recv()? == vec!["from aecho".as_bytes().to_vec()]);
+    assert!(b.recv()? == vec!["from becho".as_bytes().to_vec()]);
+    assert!(a.recv().is_err() && b.recv().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn router_send_requires_session_id() -> Result<(), Box<dyn Error>> {
+    let mut router = Socket::<Router>::new().bind("127.0.0.1:3001")?;
+
+    assert!(router.send(&[]).is_err());
+    assert!(
+        router
+            .send(&["short".as_bytes(), "data".as_bytes()])
+            .is_err()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn router_send_to_unknown_peer_errors() -> Result<(), Box<dyn Error>> {
+    let mut router = Socket::<Router>::new().bind("127.0.0.1:3002")?;
+    let mut dealer = Socket::<Dealer>::new().connect("127.0.0.1:3002")?;
+    settle(&mut router, &mut [&mut dealer])?;
+
+    dealer.send(&["hello".as_bytes()])?;
+    dealer.tick()?;
+    sleep(0.01);
+    router.tick()?;
+
+    let msg = router.recv()?;
+    let session_id = u64::from_be_bytes(msg[0].as_slice().try_into()?);
+
+    let unknown = session_id.wrapping_add(1).to_be_bytes();
+    assert!(router.send(&[&unknown, "data".as_bytes()]).is_err());
+    assert!(router.send(&[&msg[0], "data".as_bytes()]).is_ok());
+
+    Ok(())
+}
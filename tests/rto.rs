@@ -0,0 +1,108 @@
+use std::{error::Error, thread, time::Duration};
+
+use nbmq::{AsSocket, SafeDealer, Socket, frame::SEQUENCED_DATA_KIND};
+
+mod common;
+use common::Lossy;
+
+fn sleep(n: f64) {
+    thread::sleep(Duration::from_secs_f64(n));
+}
+
+const DATA: u8 = SEQUENCED_DATA_KIND;
+const HEARTBEAT_ECHO: u8 = 15;
+
+/// Lose the first send of a message and see whether it arrives within 0.2s.
+fn recovered_quickly(port: u16, adaptive_rto: bool) -> Result<bool, Box<dyn Error>> {
+    let addr = format!("127.0.0.1:{}", port);
+    let mut server = Socket::<SafeDealer>::new().bind_with(Lossy::first(DATA, 1)?, &addr)?;
+
+    // The static interval alone wouldn't resend within the test.
+    let mut client = Socket::<SafeDealer>::new()
+        .set_safe_resend_ivl(5.)
+        .set_adaptive_rto(adaptive_rto)
+        .connect(&addr)?;
+
+    for _ in 0..3 {
+        sleep(0.01);
+        server.tick()?;
+        client.tick()?;
+    }
+
+    client.send(&["hello".as_bytes()])?;
+
+    for _ in 0..10 {
+        client.tick()?;
+        sleep(0.02);
+        server.tick()?;
+
+        if let Ok(msg) = server.recv() {
+            assert!(msg == vec!["hello".as_bytes().to_vec()]);
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+#[test]
+fn adaptive_rto_resends_on_measured_rtt() -> Result<(), Box<dyn Error>> {
+    assert!(recovered_quickly(9000, true)?);
+    Ok(())
+}
+
+#[test]
+fn static_resend_ivl_without_adaptive_rto() -> Result<(), Box<dyn Error>> {
+    assert!(!recovered_quickly(9001, false)?);
+    Ok(())
+}
+
+/// Run a client that never gets its message through, so round trips come from heartbeats alone,
+/// and return the first smoothed round trip it measured.
+fn heartbeat_srtt(
+    port: u16,
+    adaptive_rto: bool,
+    lost_echoes: usize,
+) -> Result<Option<Duration>, Box<dyn Error>> {
+    let addr = format!("127.0.0.1:{}", port);
+    let mut server =
+        Socket::<SafeDealer>::new().bind_with(Lossy::first(DATA, usize::MAX)?, &addr)?;
+    let mut client = Socket::<SafeDealer>::new()
+        .set_peer_heartbeat_ivl(0.03)
+        .set_adaptive_rto(adaptive_rto)
+        .connect_with(Lossy::first(HEARTBEAT_ECHO, lost_echoes)?, &addr)?;
+
+    for _ in 0..3 {
+        sleep(0.005);
+        server.tick()?;
+        client.tick()?;
+    }
+
+    client.send(&["hello".as_bytes()])?;
+
+    for _ in 0..40 {
+        sleep(0.005);
+        server.tick()?;
+        client.tick()?;
+
+        if let Some(srtt) = client.stats().values().find_map(|stats| stats.srtt) {
+            return Ok(Some(srtt));
+        }
+    }
+
+    Ok(None)
+}
+
+#[test]
+fn lost_heartbeat_echoes_dont_inflate_rtt() -> Result<(), Box<dyn Error>> {
+    // Echoes of the first heartbeats are lost, the round trip is timed from the one answered.
+    let srtt = heartbeat_srtt(9002, true, 2)?.expect("no round trip measured");
+    assert!(srtt < Duration::from_millis(20));
+    Ok(())
+}
+
+#[test]
+fn heartbeats_arent_echoed_without_adaptive_rto() -> Result<(), Box<dyn Error>> {
+    assert!(heartbeat_srtt(9003, false, 0)?.is_none());
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use std::{
+    error::Error,
+    io,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use nbmq::{
+    Addr, AsSocket, SafeDealer, Socket, Transport, frame::SEQUENCED_DATA_KIND,
+    transport::UdpTransport,
+};
+
+fn sleep(n: f64) {
+    thread::sleep(Duration::from_secs_f64(n));
+}
+
+const SACK: u8 = 13;
+
+/// UDP counting the frames of one kind it receives.
+struct Counting {
+    inner: UdpTransport,
+    kind: u8,
+    count: Arc<AtomicUsize>,
+}
+
+impl Counting {
+    fn new(kind: u8) -> io::Result<(Self, Arc<AtomicUsize>)> {
+        let count = Arc::new(AtomicUsize::new(0));
+
+        Ok((
+            Self {
+                inner: UdpTransport::bind("0.0.0.0:0")?,
+                kind,
+                count: count.clone(),
+            },
+            count,
+        ))
+    }
+}
+
+impl Transport for Counting {
+    fn bind(&mut self, addr: &str) -> io::Result<()> {
+        self.inner.bind(addr)
+    }
+
+    fn connect(&mut self, addr: &Addr) -> io::Result<()> {
+        self.inner.connect(addr)
+    }
+
+    fn send_to(&mut self, data: &[u8], addr: &Addr) -> io::Result<usize> {
+        self.inner.send_to(data, addr)
+    }
+
+    fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, Addr)> {
+        let (n, addr) = self.inner.recv_from(buf)?;
+
+        if n >= 2 && buf[1] == self.kind {
+            self.count.fetch_add(1, Ordering::SeqCst);
+        }
+
+        Ok((n, addr))
+    }
+
+    fn local_addr(&self) -> io::Result<Addr> {
+        self.inner.local_addr()
+    }
+}
+
+#[test]
+fn one_sack_acknowledges_a_whole_tick() -> Result<(), Box<dyn Error>> {
+    let (transport, data_frames) = Counting::new(SEQUENCED_DATA_KIND)?;
+    let mut server = Socket::<SafeDealer>::new().bind_with(transport, "127.0.0.1:3800")?;
+
+    let (transport, sacks) = Counting::new(SACK)?;
+    let mut client = Socket::<SafeDealer>::new()
+        .set_safe_resend_ivl(0.02)
+        .connect_with(transport, "127.0.0.1:3800")?;
+
+    for _ in 0..2 {
+        sleep(0.01);
+        server.tick()?;
+        client.tick()?;
+    }
+
+    let mut large_bin = vec![0u8; 20000];
+    large_bin.push(1);
+    client.send(&[&large_bin])?;
+    client.tick()?;
+
+    sleep(0.01);
+    server.tick()?;
+    let sent = data_frames.load(Ordering::SeqCst);
+    assert!(sent > 40);
+
+    sleep(0.01);
+    client.tick()?;
+    assert!(sacks.load(Ordering::SeqCst) == 1);
+
+    // Everything was confirmed by the one ack, nothing is resent.
+    for _ in 0..3 {
+        sleep(0.03);
+        client.tick()?;
+        server.tick()?;
+    }
+
+    assert!(data_frames.load(Ordering::SeqCst) == sent);
+    assert!(server.recv()?[0] == large_bin);
+
+    Ok(())
+}
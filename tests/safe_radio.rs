@@ -0,0 +1,151 @@
+use std::{error::Error, thread, time::Duration};
+
+use nbmq::{AsSocket, SafeDish, SafeRadio, Socket, frame::SEQUENCED_DATA_KIND};
+
+mod common;
+use common::Lossy;
+
+fn sleep(n: f64) {
+    thread::sleep(Duration::from_secs_f64(n));
+}
+
+fn settle(radio: &mut SafeRadio, dishes: &mut [&mut SafeDish]) -> Result<(), Box<dyn Error>> {
+    for _ in 0..3 {
+        sleep(0.01);
+        radio.tick()?;
+
+        for dish in dishes.iter_mut() {
+            dish.tick()?;
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn safe_radio_reaches_every_dish() -> Result<(), Box<dyn Error>> {
+    let mut radio = Socket::<SafeRadio>::new().bind("127.0.0.1:3400")?;
+    let mut a = Socket::<SafeDish>::new().connect("127.0.0.1:3400")?;
+    let mut b = Socket::<SafeDish>::new().connect("127.0.0.1:3400")?;
+    settle(&mut radio, &mut [&mut a, &mut b])?;
+    assert!(radio.peers() == 2);
+
+    assert!(radio.recv().is_err());
+    assert!(a.send(&["up".as_bytes()]).is_err());
+
+    let mut large_bin = vec![0u8; 5000];
+    large_bin.push(1);
+    radio.send(&["hello".as_bytes(), &large_bin])?;
+    settle(&mut radio, &mut [&mut a, &mut b])?;
+
+    for dish in [&mut a, &mut b] {
+        let msg = dish.recv()?;
+        assert!(msg[0] == "hello".as_bytes());
+        assert!(msg[1].len() == 5001 && msg[1][5000] == 1);
+        assert!(dish.recv().is_err());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn safe_radio_resends_lost_frames() -> Result<(), Box<dyn Error>> {
+    let mut radio = Socket::<SafeRadio>::new()
+        .set_safe_resend_ivl(0.01)
+        .bind("127.0.0.1:3401")?;
+    let mut dish = Socket::<SafeDish>::new()
+        .connect_with(Lossy::every(SEQUENCED_DATA_KIND, 2)?, "127.0.0.1:3401")?;
+    settle(&mut radio, &mut [&mut dish])?;
+    assert!(radio.peers() == 1);
+
+    for i in 0..10u8 {
+        radio.send(&["msg".as_bytes(), &[i]])?;
+    }
+
+    for _ in 0..10 {
+        sleep(0.02);
+        radio.tick()?;
+        dish.tick()?;
+    }
+
+    let mut received = vec![];
+    while let Ok(msg) = dish.recv() {
+        received.push(msg[1][0]);
+    }
+    received.sort();
+
+    assert!(received == (0..10).collect::<Vec<u8>>());
+    assert!(radio.dropped().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn safe_radio_reports_dropped_per_subscriber() -> Result<(), Box<dyn Error>> {
+    let mut radio = Socket::<SafeRadio>::new()
+        .set_safe_resend_ivl(0.01)
+        .set_safe_resent_limit(2)
+        .bind("127.0.0.1:3402")?;
+    let mut alive = Socket::<SafeDish>::new().connect("127.0.0.1:3402")?;
+    let mut gone = Socket::<SafeDish>::new().connect("127.0.0.1:3402")?;
+    settle(&mut radio, &mut [&mut alive, &mut gone])?;
+    assert!(radio.peers() == 2);
+
+    // Still a peer until keepalive runs out, but no longer acking.
+    drop(gone);
+
+    radio.send(&["hello".as_bytes()])?;
+    let message_id = radio.last_message_id().ok_or("no message id")?;
+
+    for _ in 0..10 {
+        sleep(0.02);
+        radio.tick()?;
+        alive.tick()?;
+    }
+
+    assert!(alive.recv()? == vec!["hello".as_bytes().to_vec()]);
+
+    let dropped = radio.dropped();
+    assert!(dropped.len() == 1);
+    assert!(dropped.values().all(|ids| *ids == vec![message_id]));
+
+    // The report is taken.
+    assert!(radio.dropped().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn safe_radio_refuses_a_send_no_subscriber_has_room_for() -> Result<(), Box<dyn Error>> {
+    let mut radio = Socket::<SafeRadio>::new()
+        .set_send_hwm(2)
+        .set_congestion_control(true)
+        .set_safe_resend_ivl(1.)
+        .bind("127.0.0.1:3403")?;
+    let mut stalled = Socket::<SafeDish>::new().connect("127.0.0.1:3403")?;
+    settle(&mut radio, &mut [&mut stalled])?;
+
+    // Fill the window, then the queue, of a subscriber that stops acking.
+    for i in 0..100u8 {
+        if radio.send(&[&[i]]).is_err() {
+            break;
+        }
+        radio.tick()?;
+    }
+
+    let mut fresh = (0..5)
+        .map(|_| Socket::<SafeDish>::new().connect("127.0.0.1:3403"))
+        .collect::<Result<Vec<_>, _>>()?;
+    settle(&mut radio, &mut fresh.iter_mut().collect::<Vec<_>>())?;
+    assert!(radio.peers() == 6);
+
+    // Refused outright, not queued to the subscribers that had room.
+    assert!(radio.send(&["third".as_bytes()]).is_err());
+    settle(&mut radio, &mut fresh.iter_mut().collect::<Vec<_>>())?;
+
+    for dish in fresh.iter_mut() {
+        assert!(dish.recv().is_err());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,198 @@
+use std::{error::Error, io, thread, time::Duration};
+
+use nbmq::{AsSocket, Dealer, Respondent, Socket, Surveyor};
+
+fn sleep(n: f64) {
+    thread::sleep(Duration::from_secs_f64(n));
+}
+
+fn settle(
+    surveyor: &mut Surveyor,
+    respondents: &mut [&mut Respondent],
+) -> Result<(), Box<dyn Error>> {
+    for _ in 0..3 {
+        sleep(0.01);
+        surveyor.tick()?;
+
+        for respondent in respondents.iter_mut() {
+            respondent.tick()?;
+        }
+    }
+
+    Ok(())
+}
+
+fn is_timeout(result: Result<Vec<Vec<u8>>, Box<dyn Error>>) -> bool {
+    let Err(e) = result else {
+        return false;
+    };
+
+    e.downcast_ref::<io::Error>().map(|e| e.kind()) == Some(io::ErrorKind::TimedOut)
+}
+
+#[test]
+fn alternation_is_enforced() -> Result<(), Box<dyn Error>> {
+    let mut surveyor = Socket::<Surveyor>::new().bind("127.0.0.1:3300")?;
+    let mut respondent = Socket::<Respondent>::new().connect("127.0.0.1:3300")?;
+
+    assert!(surveyor.recv().is_err());
+    assert!(respondent.send(&["unasked".as_bytes()]).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn surveyor_collects_responses() -> Result<(), Box<dyn Error>> {
+    let mut surveyor = Socket::<Surveyor>::new().bind("127.0.0.1:3301")?;
+    let mut a = Socket::<Respondent>::new().connect("127.0.0.1:3301")?;
+    let mut b = Socket::<Respondent>::new().connect("127.0.0.1:3301")?;
+    let mut c = Socket::<Respondent>::new().connect("127.0.0.1:3301")?;
+    settle(&mut surveyor, &mut [&mut a, &mut b, &mut c])?;
+    assert!(surveyor.peers() == 3);
+
+    surveyor.send(&["healthy?".as_bytes()])?;
+    surveyor.tick()?;
+    sleep(0.01);
+
+    for (name, respondent) in [("a", &mut a), ("b", &mut b), ("c", &mut c)] {
+        respondent.tick()?;
+        assert!(respondent.recv()? == vec!["healthy?".as_bytes().to_vec()]);
+        respondent.send(&[name.as_bytes()])?;
+        respondent.tick()?;
+    }
+
+    sleep(0.01);
+    surveyor.tick()?;
+
+    let mut votes = vec![];
+    while let Ok(vote) = surveyor.recv() {
+        votes.push(String::from_utf8(vote[0].clone())?);
+    }
+    votes.sort();
+
+    assert!(votes == vec!["a", "b", "c"]);
+
+    Ok(())
+}
+
+#[test]
+fn late_responses_are_dropped() -> Result<(), Box<dyn Error>> {
+    let mut surveyor = Socket::<Surveyor>::new()
+        .set_survey_deadline(0.05)
+        .bind("127.0.0.1:3302")?;
+    let mut respondent = Socket::<Respondent>::new().connect("127.0.0.1:3302")?;
+    settle(&mut surveyor, &mut [&mut respondent])?;
+
+    surveyor.send(&["first".as_bytes()])?;
+    surveyor.tick()?;
+    sleep(0.01);
+    respondent.tick()?;
+    assert!(respondent.recv()? == vec!["first".as_bytes().to_vec()]);
+
+    // The respondent sits on the survey past its deadline.
+    sleep(0.06);
+    surveyor.tick()?;
+    assert!(is_timeout(surveyor.recv()));
+
+    respondent.send(&["late".as_bytes()])?;
+    respondent.tick()?;
+    sleep(0.01);
+    surveyor.tick()?;
+    assert!(is_timeout(surveyor.recv()));
+
+    // The next survey collects as usual.
+    surveyor.send(&["second".as_bytes()])?;
+    surveyor.tick()?;
+
+    sleep(0.01);
+    respondent.tick()?;
+    assert!(respondent.recv()? == vec!["second".as_bytes().to_vec()]);
+    respondent.send(&["on time".as_bytes()])?;
+    respondent.tick()?;
+
+    sleep(0.01);
+    surveyor.tick()?;
+    assert!(surveyor.recv()? == vec!["on time".as_bytes().to_vec()]);
+    assert!(surveyor.recv().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn stale_survey_response_is_dropped() -> Result<(), Box<dyn Error>> {
+    let mut surveyor = Socket::<Surveyor>::new()
+        .set_survey_deadline(0.05)
+        .bind("127.0.0.1:3303")?;
+    let mut respondent = Socket::<Respondent>::new().connect("127.0.0.1:3303")?;
+    settle(&mut surveyor, &mut [&mut respondent])?;
+
+    surveyor.send(&["first".as_bytes()])?;
+    surveyor.tick()?;
+    sleep(0.01);
+    respondent.tick()?;
+    respondent.recv()?;
+
+    sleep(0.06);
+    surveyor.tick()?;
+    surveyor.send(&["second".as_bytes()])?;
+
+    // Answers the first survey while the second is running.
+    respondent.send(&["stale".as_bytes()])?;
+    respondent.tick()?;
+
+    sleep(0.01);
+    surveyor.tick()?;
+    assert!(surveyor.recv().is_err());
+    assert!(!is_timeout(surveyor.recv()));
+
+    Ok(())
+}
+
+#[test]
+fn refused_response_can_be_retried() -> Result<(), Box<dyn Error>> {
+    let mut asker = Socket::<Dealer>::new().bind("127.0.0.1:3304")?;
+    let mut respondent = Socket::<Respondent>::new()
+        .set_send_hwm(1)
+        .connect("127.0.0.1:3304")?;
+
+    for _ in 0..3 {
+        sleep(0.01);
+        respondent.tick()?;
+        asker.tick()?;
+    }
+
+    // Two surveys taken in one tick, the second response finds the first still queued.
+    asker.send(&["first".as_bytes()])?;
+    asker.send(&["second".as_bytes()])?;
+    asker.tick()?;
+    sleep(0.01);
+    respondent.tick()?;
+
+    assert!(respondent.recv()? == vec!["first".as_bytes().to_vec()]);
+    respondent.send(&["one".as_bytes()])?;
+
+    assert!(respondent.recv()? == vec!["second".as_bytes().to_vec()]);
+    assert!(respondent.send(&["two".as_bytes()]).is_err());
+    assert!(respondent.send(&["two".as_bytes()]).is_err());
+
+    respondent.tick()?;
+    respondent.send(&["two".as_bytes()])?;
+    respondent.tick()?;
+
+    sleep(0.01);
+    asker.tick()?;
+
+    let mut responses = vec![];
+    while let Ok(response) = asker.recv() {
+        responses.push(response);
+    }
+    assert!(
+        responses
+            == vec![
+                vec!["one".as_bytes().to_vec()],
+                vec!["two".as_bytes().to_vec()]
+            ]
+    );
+
+    Ok(())
+}
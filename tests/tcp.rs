@@ -0,0 +1,149 @@
+use std::{
+    error::Error,
+    net::{TcpListener, TcpStream},
+    thread,
+    time::{Duration, Instant},
+};
+
+use nbmq::{AsSocket, Dealer, Dish, Radio, SafeDealer, Socket};
+
+fn sleep(n: f64) {
+    thread::sleep(Duration::from_secs_f64(n));
+}
+
+fn exchanges_over_tcp<S, R>(p: usize) -> Result<(), Box<dyn Error>>
+where
+    S: AsSocket,
+    R: AsSocket,
+{
+    let mut sender = Socket::<S>::new().bind(&format!("tcp://0.0.0.0:{}", p))?;
+    let mut receiver = Socket::<R>::new().connect(&format!("tcp://127.0.0.1:{}", p))?;
+
+    sleep(0.01);
+    sender.tick()?;
+
+    sleep(0.01);
+    receiver.tick()?;
+    assert!(sender.peers() == 1 && receiver.peers() == 1);
+
+    sender.send(&["hello".as_bytes()])?;
+    sender.tick()?;
+
+    sleep(0.01);
+    receiver.tick()?;
+    assert!(receiver.recv()?[0] == "hello".as_bytes());
+
+    Ok(())
+}
+
+#[test]
+fn dealer_exchanges_over_tcp() -> Result<(), Box<dyn Error>> {
+    exchanges_over_tcp::<Dealer, Dealer>(7300)
+}
+
+#[test]
+fn radio_exchanges_over_tcp() -> Result<(), Box<dyn Error>> {
+    exchanges_over_tcp::<Radio, Dish>(7301)
+}
+
+#[test]
+fn safedealer_exchanges_over_tcp() -> Result<(), Box<dyn Error>> {
+    exchanges_over_tcp::<SafeDealer, SafeDealer>(7302)
+}
+
+#[test]
+fn large_message_over_tcp() -> Result<(), Box<dyn Error>> {
+    let mut server = Socket::<Dealer>::new().bind("tcp://0.0.0.0:7303")?;
+    let mut client = Socket::<Dealer>::new().connect("tcp://127.0.0.1:7303")?;
+
+    sleep(0.01);
+    server.tick()?;
+
+    sleep(0.01);
+    client.tick()?;
+    let mut large_bin = vec![0u8; 100000];
+    large_bin.push(1);
+    client.send(&[&large_bin])?;
+    client.tick()?;
+
+    sleep(0.01);
+    server.tick()?;
+    let msgb = server.recv()?;
+    assert!(msgb[0].len() == 100001);
+    assert!(msgb[0][100000] == 1);
+
+    Ok(())
+}
+
+#[test]
+fn tcp_client_reconnects_until_success() -> Result<(), Box<dyn Error>> {
+    let mut client = Socket::<Dealer>::new()
+        .set_reconnect_wait(0.005)
+        .set_peer_keepalive(0.03)
+        .set_peer_heartbeat_ivl(0.01)
+        .connect("tcp://127.0.0.1:7304")?;
+
+    sleep(0.01);
+    client.tick()?;
+    assert!(client.peers() == 0);
+
+    // Connects run in the background, let the refused attempt finish first.
+    sleep(0.01);
+    let mut server = Socket::<Dealer>::new().bind("tcp://0.0.0.0:7304")?;
+
+    sleep(0.01);
+    client.tick()?;
+
+    sleep(0.01);
+    server.tick()?;
+
+    sleep(0.01);
+    client.tick()?;
+
+    assert!(server.peers() == 1);
+    assert!(client.peers() == 1);
+
+    Ok(())
+}
+
+#[test]
+fn unsupported_scheme_errors() {
+    assert!(
+        Socket::<Dealer>::new()
+            .bind("carrier-pigeon://coop")
+            .is_err()
+    );
+}
+
+#[test]
+fn connect_to_unresponsive_listener_does_not_stall_tick() -> Result<(), Box<dyn Error>> {
+    // A listener that never accepts drops new handshakes once its backlog fills, so connecting
+    // to it can only time out.
+    let listener = TcpListener::bind("127.0.0.1:7305")?;
+    let addr = listener.local_addr()?;
+
+    let mut backlog = vec![];
+    for _ in 0..1024 {
+        match TcpStream::connect_timeout(&addr, Duration::from_secs_f64(0.05)) {
+            Ok(stream) => backlog.push(stream),
+            Err(_) => break,
+        }
+    }
+
+    let started = Instant::now();
+
+    let mut client = Socket::<Dealer>::new()
+        .set_connect_timeout(1.)
+        .set_reconnect_wait(0.005)
+        .connect("tcp://127.0.0.1:7305")?;
+
+    for _ in 0..5 {
+        sleep(0.01);
+        client.tick()?;
+    }
+
+    assert!(client.peers() == 0);
+    assert!(started.elapsed() < Duration::from_secs_f64(0.5));
+
+    Ok(())
+}
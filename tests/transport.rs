@@ -0,0 +1,197 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    error::Error,
+    io,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use nbmq::{Addr, AsSocket, Dealer, Dish, Radio, SafeDealer, Socket, Transport};
+
+fn sleep(n: f64) {
+    thread::sleep(Duration::from_secs_f64(n));
+}
+
+type Ether = Arc<Mutex<HashMap<String, VecDeque<(Vec<u8>, String)>>>>;
+
+/// A shared in-memory medium where stations address each other by name, dropping every
+/// `loss`-th frame sent.
+struct Station {
+    ether: Ether,
+    name: String,
+    loss: usize,
+    sent: usize,
+}
+
+impl Station {
+    fn new(ether: &Ether, name: &str, loss: usize) -> Self {
+        ether
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), VecDeque::new());
+
+        Self {
+            ether: ether.clone(),
+            name: name.to_string(),
+            loss,
+            sent: 0,
+        }
+    }
+}
+
+impl Transport for Station {
+    fn bind(&mut self, addr: &str) -> io::Result<()> {
+        let mut ether = self.ether.lock().unwrap();
+        let inbox = ether.remove(&self.name).unwrap_or_default();
+        ether.insert(addr.to_string(), inbox);
+
+        self.name = addr.to_string();
+        Ok(())
+    }
+
+    fn connect(&mut self, addr: &Addr) -> io::Result<()> {
+        match self.ether.lock().unwrap().contains_key(&addr.to_string()) {
+            true => Ok(()),
+            false => Err(io::Error::from(io::ErrorKind::ConnectionRefused)),
+        }
+    }
+
+    fn send_to(&mut self, data: &[u8], addr: &Addr) -> io::Result<usize> {
+        self.sent += 1;
+        if self.loss > 0 && self.sent.is_multiple_of(self.loss) {
+            return Ok(data.len());
+        }
+
+        let mut ether = self.ether.lock().unwrap();
+        let Some(inbox) = ether.get_mut(&addr.to_string()) else {
+            return Err(io::Error::from(io::ErrorKind::ConnectionRefused));
+        };
+
+        inbox.push_back((data.to_vec(), self.name.clone()));
+        Ok(data.len())
+    }
+
+    fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, Addr)> {
+        let mut ether = self.ether.lock().unwrap();
+        let Some((data, from)) = ether
+            .get_mut(&self.name)
+            .and_then(|inbox| inbox.pop_front())
+        else {
+            return Err(io::Error::from(io::ErrorKind::WouldBlock));
+        };
+
+        let n = data.len().min(buf.len());
+        buf[..n].copy_from_slice(&data[..n]);
+
+        Ok((n, Addr::Name(from)))
+    }
+
+    fn local_addr(&self) -> io::Result<Addr> {
+        Ok(Addr::Name(self.name.clone()))
+    }
+}
+
+fn exchanges_over_station<S, R>() -> Result<(), Box<dyn Error>>
+where
+    S: AsSocket,
+    R: AsSocket,
+{
+    let ether = Ether::default();
+
+    let mut sender = Socket::<S>::new().bind_with(Station::new(&ether, "", 0), "base")?;
+    let mut receiver = Socket::<R>::new().connect_with(Station::new(&ether, "rover", 0), "base")?;
+
+    sender.tick()?;
+    receiver.tick()?;
+    assert!(sender.peers() == 1 && receiver.peers() == 1);
+
+    // Large enough to be fragmented into many frames by the layers above the transport.
+    let mut large_bin = vec![0u8; 20000];
+    large_bin.push(1);
+    sender.send(&["hello".as_bytes(), &large_bin])?;
+    sender.tick()?;
+
+    receiver.tick()?;
+    let msg = receiver.recv()?;
+    assert!(msg[0] == "hello".as_bytes());
+    assert!(msg[1].len() == 20001 && msg[1][20000] == 1);
+
+    Ok(())
+}
+
+#[test]
+fn dealer_exchanges_over_custom_transport() -> Result<(), Box<dyn Error>> {
+    exchanges_over_station::<Dealer, Dealer>()
+}
+
+#[test]
+fn radio_exchanges_over_custom_transport() -> Result<(), Box<dyn Error>> {
+    exchanges_over_station::<Radio, Dish>()
+}
+
+#[test]
+fn safedealer_recovers_losses_on_custom_transport() -> Result<(), Box<dyn Error>> {
+    let ether = Ether::default();
+
+    let mut server = Socket::<SafeDealer>::new()
+        .set_safe_resend_ivl(0.01)
+        .bind_with(Station::new(&ether, "", 0), "base")?;
+    let mut client = Socket::<SafeDealer>::new()
+        .set_safe_resend_ivl(0.01)
+        .connect_with(Station::new(&ether, "rover", 7), "base")?;
+
+    server.tick()?;
+    client.tick()?;
+    assert!(server.peers() == 1 && client.peers() == 1);
+
+    for i in 0..20u8 {
+        client.send(&[&[i]])?;
+    }
+
+    let mut recvd = vec![];
+    for _ in 0..20 {
+        client.tick()?;
+        server.tick()?;
+
+        while let Ok(msg) = server.recv() {
+            recvd.push(msg[0][0]);
+        }
+
+        sleep(0.01);
+    }
+
+    recvd.sort();
+    assert!(recvd == (0..20).collect::<Vec<u8>>());
+
+    Ok(())
+}
+
+#[test]
+fn bind_with_reports_transport_errors() {
+    struct Refuses;
+
+    impl Transport for Refuses {
+        fn bind(&mut self, _addr: &str) -> io::Result<()> {
+            Err(io::Error::from(io::ErrorKind::AddrInUse))
+        }
+
+        fn connect(&mut self, _addr: &Addr) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn send_to(&mut self, data: &[u8], _addr: &Addr) -> io::Result<usize> {
+            Ok(data.len())
+        }
+
+        fn recv_from(&mut self, _buf: &mut [u8]) -> io::Result<(usize, Addr)> {
+            Err(io::Error::from(io::ErrorKind::WouldBlock))
+        }
+
+        fn local_addr(&self) -> io::Result<Addr> {
+            Err(io::Error::from(io::ErrorKind::NotConnected))
+        }
+    }
+
+    assert!(Socket::<Dealer>::new().bind_with(Refuses, "base").is_err());
+}
@@ -0,0 +1,140 @@
+#![cfg(unix)]
+
+use std::{env, error::Error, fs, os::unix::net::UnixDatagram, thread, time::Duration};
+
+use nbmq::{AsSocket, Dealer, Dish, Radio, SafeDealer, Socket};
+
+fn sleep(n: f64) {
+    thread::sleep(Duration::from_secs_f64(n));
+}
+
+fn endpoint(name: &str) -> String {
+    let path = env::temp_dir().join(format!("nbmq-test-{}-{}.sock", name, std::process::id()));
+    format!("unix://{}", path.display())
+}
+
+fn exchanges_over_unix<S, R>(name: &str) -> Result<(), Box<dyn Error>>
+where
+    S: AsSocket,
+    R: AsSocket,
+{
+    let addr = endpoint(name);
+    let mut sender = Socket::<S>::new().bind(&addr)?;
+    let mut receiver = Socket::<R>::new().connect(&addr)?;
+
+    sleep(0.01);
+    sender.tick()?;
+
+    sleep(0.01);
+    receiver.tick()?;
+    assert!(sender.peers() == 1 && receiver.peers() == 1);
+
+    sender.send(&["hello".as_bytes()])?;
+    sender.tick()?;
+
+    sleep(0.01);
+    receiver.tick()?;
+    assert!(receiver.recv()?[0] == "hello".as_bytes());
+
+    Ok(())
+}
+
+#[test]
+fn dealer_exchanges_over_unix() -> Result<(), Box<dyn Error>> {
+    exchanges_over_unix::<Dealer, Dealer>("dealer")
+}
+
+#[test]
+fn radio_exchanges_over_unix() -> Result<(), Box<dyn Error>> {
+    exchanges_over_unix::<Radio, Dish>("radio")
+}
+
+#[test]
+fn safedealer_exchanges_over_unix() -> Result<(), Box<dyn Error>> {
+    exchanges_over_unix::<SafeDealer, SafeDealer>("safedealer")
+}
+
+#[test]
+fn unix_heartbeats_keep_peers_alive() -> Result<(), Box<dyn Error>> {
+    let addr = endpoint("heartbeat");
+    let mut server = Socket::<Dealer>::new()
+        .set_peer_keepalive(0.05)
+        .set_peer_heartbeat_ivl(0.01)
+        .bind(&addr)?;
+    let mut client = Socket::<Dealer>::new()
+        .set_peer_keepalive(0.05)
+        .set_peer_heartbeat_ivl(0.01)
+        .connect(&addr)?;
+
+    for _ in 0..10 {
+        sleep(0.01);
+        server.tick()?;
+        client.tick()?;
+    }
+
+    assert!(server.peers() == 1);
+    assert!(client.peers() == 1);
+
+    Ok(())
+}
+
+#[test]
+fn large_frames_over_unix() -> Result<(), Box<dyn Error>> {
+    let addr = endpoint("large");
+    let mut server = Socket::<Dealer>::new()
+        .set_max_frame_size(60000)
+        .bind(&addr)?;
+    let mut client = Socket::<Dealer>::new()
+        .set_max_frame_size(60000)
+        .connect(&addr)?;
+
+    sleep(0.01);
+    server.tick()?;
+
+    sleep(0.01);
+    client.tick()?;
+    let mut large_bin = vec![0u8; 100000];
+    large_bin.push(1);
+    client.send(&[&large_bin])?;
+    client.tick()?;
+
+    sleep(0.01);
+    server.tick()?;
+    let msgb = server.recv()?;
+    assert!(msgb[0].len() == 100001);
+    assert!(msgb[0][100000] == 1);
+
+    Ok(())
+}
+
+#[test]
+fn oversized_frames_are_refused_by_udp() {
+    assert!(
+        Socket::<Dealer>::new()
+            .set_max_frame_size(100000)
+            .bind("0.0.0.0:7350")
+            .is_err()
+    );
+}
+
+#[test]
+fn bind_replaces_only_stale_sockets() -> Result<(), Box<dyn Error>> {
+    let path = env::temp_dir().join(format!("nbmq-test-stale-{}.sock", std::process::id()));
+    let addr = format!("unix://{}", path.display());
+
+    // Left behind by a process that's gone.
+    drop(UnixDatagram::bind(&path)?);
+    let live = Socket::<Dealer>::new().bind(&addr)?;
+
+    // Still bound by a live socket.
+    assert!(Socket::<Dealer>::new().bind(&addr).is_err());
+    drop(live);
+
+    // Not a socket at all.
+    fs::write(&path, "keep me")?;
+    assert!(Socket::<Dealer>::new().bind(&addr).is_err());
+    assert!(fs::read_to_string(&path)? == "keep me");
+    fs::remove_file(&path)?;
+
+    Ok(())
+}